@@ -33,10 +33,14 @@ async fn main() -> Result<(), Whatever> {
         .await
         .whatever_context("failed to build publisher")?;
 
-    publisher
+    let mut subscription = publisher
         .subscribe(["chat.bob"], "text", None)
         .await
         .whatever_context("failed to subscribe")?;
 
+    while let Some(object) = subscription.recv().await {
+        println!("{}", String::from_utf8_lossy(&object.payload));
+    }
+
     Ok(())
 }
@@ -1,11 +1,17 @@
 use {
-    super::{Client, ClientConfig, ClientError, Connection, ctx},
-    crate::{ControlStream, Protocol, types::RequestId},
+    super::{
+        Client, ClientConfig, ClientError, Connection, OnRequestsBlocked, TlsVerification, ctx,
+    },
+    crate::{
+        ControlStream, DRAFT_VERSION, Protocol, Timeouts,
+        transport::memory,
+        types::{RequestWindow, Token, message::ClientSetup},
+    },
     bon::bon,
+    core::time::Duration,
     snafu::ResultExt,
-    tokio::sync::RwLock,
     tracing::debug,
-    webtransport::endpoint::IntoConnectOptions,
+    url::Url,
 };
 
 #[bon]
@@ -14,9 +20,23 @@ impl Client {
     #[tracing::instrument]
     pub async fn new(config: ClientConfig) -> Result<Self, ClientError> {
         match &config.protocol {
-            Protocol::Quic => Self::quic_builder().connect(config.relay).build().await,
+            Protocol::Quic => {
+                Self::quic_builder()
+                    .tls(config.tls_verification())
+                    .on_blocked(config.on_blocked)
+                    .reconnect(config.reconnect)
+                    .timeouts(config.timeouts())
+                    .maybe_auth_token(config.auth_token())
+                    .connect(config.relay)
+                    .build()
+                    .await
+            }
             Protocol::WebTransport => {
                 Self::webtransport_builder()
+                    .on_blocked(config.on_blocked)
+                    .reconnect(config.reconnect)
+                    .timeouts(config.timeouts())
+                    .maybe_auth_token(config.auth_token())
                     .connect(config.relay)
                     .build()
                     .await
@@ -26,54 +46,325 @@ impl Client {
 
     /// Create a WebTransport [Publisher] using a Builder pattern
     #[builder(start_fn = webtransport_builder, finish_fn = build)]
-    pub async fn new_webtransport<O>(
-        #[builder(setters(doc {
+    pub async fn new_webtransport(
+        #[builder(into, setters(doc {
             /// ## Connection URL
             /// 
             /// The Endpoint will connect this WebTransport server.
         }))]
-        connect: O,
-    ) -> Result<Self, ClientError>
-    where
-        O: IntoConnectOptions,
-    {
-        // establish he connection to the relay
-        let transport = Connection::webtransport_builder()
-            .config(webtransport::ClientConfig::default())
-            .connect(connect)
-            .build()
-            .await
-            .context(ctx::ConnectionSnafu)?;
-        debug!("connection established");
-
-        let control_stream = ControlStream::open(&transport)
-            .await
-            .context(ctx::ControlStreamSnafu)?;
-
-        Ok(Self {
+        connect: Url,
+        #[builder(default, setters(doc {
+            /// ## On Requests Blocked
+            /// 
+            /// What to do when the server granted no
+            /// more Request IDs.
+        }))]
+        on_blocked: OnRequestsBlocked,
+        #[builder(default, setters(doc {
+            /// ## Reconnect
+            /// 
+            /// Migrate to a new session and re-issue all
+            /// Subscriptions when the server sends a Goaway.
+        }))]
+        reconnect: bool,
+        #[builder(default, setters(doc {
+            /// ## Timeouts
+            /// 
+            /// How long the server may take to complete
+            /// the setup, respond or send data.
+        }))]
+        timeouts: Timeouts,
+        #[builder(setters(doc {
+            /// ## Authorization Token
+            /// 
+            /// Sent with the setup of every session to
+            /// authorize it and all its Requests.
+        }))]
+        auth_token: Option<Token>,
+    ) -> Result<Self, ClientError> {
+        let connector = Connector::webtransport(auth_token);
+        let (transport, control_stream) = connector.connect(&connect, timeouts.setup).await?;
+
+        Ok(Self::from_parts(
+            connector,
+            connect,
             transport,
             control_stream,
-            request_id: RwLock::new(RequestId::new_client()),
-        })
+            on_blocked,
+            reconnect,
+            timeouts,
+        ))
     }
 
     /// Create a QUIC [Publisher] using a Builder pattern
     #[builder(start_fn = quic_builder, finish_fn = build)]
-    pub async fn new_quic<O>(
-        #[builder(setters(doc {
+    pub async fn new_quic(
+        #[builder(into, setters(doc {
             /// ## Connection URL
             /// 
             /// The Endpoint will connect this QUIC server.
         }))]
-        _connect: O,
-    ) -> Result<Self, ClientError>
-    where
-        O: IntoConnectOptions,
-    {
-        todo!("# TODO quic support")
-        // Ok(Self {
-        //     transport: Connection::quic_builder().config(quic::ClientConfig::new(Arc::new(data)))
-        //         .context(ctx::ConnectionSnafu)?,
-        // })
+        connect: Url,
+        #[builder(default, setters(doc {
+            /// ## Certificate Verification
+            /// 
+            /// How to verify the certificate of the server.
+        }))]
+        tls: TlsVerification,
+        #[builder(default, setters(doc {
+            /// ## On Requests Blocked
+            /// 
+            /// What to do when the server granted no
+            /// more Request IDs.
+        }))]
+        on_blocked: OnRequestsBlocked,
+        #[builder(default, setters(doc {
+            /// ## Reconnect
+            /// 
+            /// Migrate to a new session and re-issue all
+            /// Subscriptions when the server sends a Goaway.
+        }))]
+        reconnect: bool,
+        #[builder(default, setters(doc {
+            /// ## Timeouts
+            /// 
+            /// How long the server may take to complete
+            /// the setup, respond or send data.
+        }))]
+        timeouts: Timeouts,
+        #[builder(setters(doc {
+            /// ## Authorization Token
+            /// 
+            /// Sent with the setup of every session to
+            /// authorize it and all its Requests.
+        }))]
+        auth_token: Option<Token>,
+    ) -> Result<Self, ClientError> {
+        let connector = Connector::quic(tls, auth_token);
+        let (transport, control_stream) = connector.connect(&connect, timeouts.setup).await?;
+
+        Ok(Self::from_parts(
+            connector,
+            connect,
+            transport,
+            control_stream,
+            on_blocked,
+            reconnect,
+            timeouts,
+        ))
+    }
+
+    /// Create an in-memory [Client] using a Builder pattern
+    #[builder(start_fn = memory_builder, finish_fn = build)]
+    pub async fn new_memory(
+        #[builder(setters(doc {
+            /// ## Memory Connector
+            /// 
+            /// Connects to an in-memory [Server](crate::Server).
+        }))]
+        connect: memory::Connector,
+        #[builder(default, setters(doc {
+            /// ## Link Conditions
+            /// 
+            /// The latency and loss of the connection.
+        }))]
+        conditions: memory::Conditions,
+        #[builder(default, setters(doc {
+            /// ## On Requests Blocked
+            /// 
+            /// What to do when the server granted no
+            /// more Request IDs.
+        }))]
+        on_blocked: OnRequestsBlocked,
+        #[builder(default, setters(doc {
+            /// ## Reconnect
+            /// 
+            /// Migrate to a new session and re-issue all
+            /// Subscriptions when the server sends a Goaway.
+        }))]
+        reconnect: bool,
+        #[builder(default, setters(doc {
+            /// ## Timeouts
+            /// 
+            /// How long the server may take to complete
+            /// the setup, respond or send data.
+        }))]
+        timeouts: Timeouts,
+        #[builder(setters(doc {
+            /// ## Authorization Token
+            /// 
+            /// Sent with the setup of every session to
+            /// authorize it and all its Requests.
+        }))]
+        auth_token: Option<Token>,
+    ) -> Result<Self, ClientError> {
+        let url = connect.url();
+        let connector = Connector::memory(connect, conditions, auth_token);
+        let (transport, control_stream) = connector.connect(&url, timeouts.setup).await?;
+
+        Ok(Self::from_parts(
+            connector,
+            url,
+            transport,
+            control_stream,
+            on_blocked,
+            reconnect,
+            timeouts,
+        ))
+    }
+}
+
+/// How a [Client] connects to the relay, also
+/// when migrating to a new session.
+#[derive(Debug, Clone)]
+pub(crate) struct Connector {
+    transport: Link,
+    /// Sent with every ClientSetup to authorize
+    /// the session.
+    auth_token: Option<Token>,
+}
+
+/// The transport a [Connector] establishes
+/// sessions on.
+#[derive(Debug, Clone)]
+enum Link {
+    WebTransport,
+    Quic(TlsVerification),
+    /// Always connects the same in-memory Server.
+    Memory(memory::Connector, memory::Conditions),
+}
+
+impl Connector {
+    pub fn webtransport(auth_token: Option<Token>) -> Self {
+        Self {
+            transport: Link::WebTransport,
+            auth_token,
+        }
+    }
+
+    pub fn quic(tls: TlsVerification, auth_token: Option<Token>) -> Self {
+        Self {
+            transport: Link::Quic(tls),
+            auth_token,
+        }
+    }
+
+    pub fn memory(
+        connector: memory::Connector,
+        conditions: memory::Conditions,
+        auth_token: Option<Token>,
+    ) -> Self {
+        Self {
+            transport: Link::Memory(connector, conditions),
+            auth_token,
+        }
+    }
+
+    /// Establishes a connection to `url` and
+    /// opens the ControlStream on it within
+    /// `setup_timeout`.
+    pub async fn connect(
+        &self,
+        url: &Url,
+        setup_timeout: Duration,
+    ) -> Result<(Connection, ControlStream), ClientError> {
+        match &self.transport {
+            Link::WebTransport => {
+                // establish he connection to the relay
+                let transport = Connection::webtransport_builder()
+                    .config(webtransport::ClientConfig::default())
+                    .connect(url.as_str())
+                    .build()
+                    .await
+                    .context(ctx::ConnectionSnafu)?;
+                debug!("connection established");
+
+                let setup = self.setup(None);
+                let control_stream = ControlStream::open_with(&transport, setup, setup_timeout)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+                Ok((transport, control_stream))
+            }
+            Link::Quic(tls) => {
+                let transport = Connection::quic_builder()
+                    .config(tls.quic_config()?)
+                    .connect(url.clone())
+                    .build()
+                    .await
+                    .context(ctx::ConnectionSnafu)?;
+                debug!("connection established");
+
+                // without WebTransport, the URL reaches the relay in the setup
+                let setup = self.setup(Some(url));
+                let control_stream = ControlStream::open_with(&transport, setup, setup_timeout)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+                Ok((transport, control_stream))
+            }
+            Link::Memory(connector, conditions) => {
+                let transport = Connection::Memory(Box::new(
+                    connector
+                        .connect(conditions)
+                        .context(ctx::ConnectionSnafu)?,
+                ));
+                debug!("connection established");
+
+                let setup = self.setup(None);
+                let control_stream = ControlStream::open_with(&transport, setup, setup_timeout)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+                Ok((transport, control_stream))
+            }
+        }
+    }
+
+    /// The ClientSetup opening a session, with
+    /// the Path and Authority of `url` if any.
+    fn setup(&self, url: Option<&Url>) -> ClientSetup {
+        let mut setup = ClientSetup::builder()
+            .version(DRAFT_VERSION)
+            .max_request_id(RequestWindow::new_server().max());
+        if let Some(url) = url {
+            setup = setup.path(setup_path(url)).authority(setup_authority(url));
+        }
+        if let Some(token) = &self.auth_token {
+            setup = setup.auth_token(token.clone());
+        }
+        setup.build()
+    }
+}
+
+/// The Path Parameter of `url`, its path
+/// and query.
+fn setup_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_owned(),
+    }
+}
+
+/// The Authority Parameter of `url`, its host
+/// and explicit port.
+fn setup_authority(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setup_parameters_test() {
+        let url = Url::parse("https://relay.example.com:4443/moq/live?room=1").unwrap();
+        assert_eq!(setup_path(&url), "/moq/live?room=1");
+        assert_eq!(setup_authority(&url), "relay.example.com:4443");
+
+        let url = Url::parse("https://[::1]").unwrap();
+        assert_eq!(setup_path(&url), "/");
+        assert_eq!(setup_authority(&url), "[::1]");
     }
 }
@@ -1,8 +1,12 @@
 use {
-    crate::Protocol,
+    super::TlsVerification,
+    crate::{Protocol, Timeouts, types::Token},
     bon::Builder,
     clap::Parser,
+    core::time::Duration,
     serde::{Deserialize, Serialize},
+    std::path::PathBuf,
+    strum_lite::strum,
     url::Url,
 };
 
@@ -25,4 +29,164 @@ pub struct ClientConfig {
         /// Whether to use QUIC or WebTransport
     }))]
     pub protocol: Protocol,
+
+    /// PEM file with the CA certificates to trust
+    /// instead of the native roots (QUIC only)
+    #[arg(long = "ca")]
+    #[builder(into, setters(doc {
+        /// ## CA Certificates
+        /// 
+        /// PEM file with the CA certificates to trust
+        /// instead of the native roots (QUIC only)
+    }))]
+    pub ca: Option<PathBuf>,
+
+    /// Accept self-signed certificates of a relay
+    /// on localhost (QUIC only)
+    #[arg(long = "insecure-localhost")]
+    #[serde(default)]
+    #[builder(default, setters(doc {
+        /// ## Insecure Localhost
+        /// 
+        /// Accept self-signed certificates of a relay
+        /// on localhost (QUIC only)
+    }))]
+    pub insecure_localhost: bool,
+
+    /// What to do when the relay granted no more
+    /// Request IDs
+    #[arg(long = "on-blocked", default_value = "wait")]
+    #[serde(default)]
+    #[builder(default, setters(doc {
+        /// ## On Requests Blocked
+        /// 
+        /// What to do when the relay granted no more
+        /// Request IDs
+    }))]
+    pub on_blocked: OnRequestsBlocked,
+
+    /// Migrate to a new session and re-issue all
+    /// Subscriptions when the relay sends a Goaway
+    #[arg(long = "reconnect")]
+    #[serde(default)]
+    #[builder(default, setters(doc {
+        /// ## Reconnect
+        /// 
+        /// Migrate to a new session and re-issue all
+        /// Subscriptions when the relay sends a Goaway
+    }))]
+    pub reconnect: bool,
+
+    /// Milliseconds the relay may take to complete
+    /// the setup, defaults to 10 seconds
+    #[arg(long = "setup-timeout")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Setup Timeout
+        /// 
+        /// Milliseconds the relay may take to complete
+        /// the setup, defaults to 10 seconds
+    }))]
+    pub setup_timeout: Option<u64>,
+
+    /// Milliseconds the relay may take to respond
+    /// to a Request, defaults to 10 seconds
+    #[arg(long = "request-timeout")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Request Timeout
+        /// 
+        /// Milliseconds the relay may take to respond
+        /// to a Request, defaults to 10 seconds
+    }))]
+    pub request_timeout: Option<u64>,
+
+    /// Milliseconds the relay may take to send the
+    /// header of a data stream, defaults to 10 seconds
+    #[arg(long = "data-stream-timeout")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Data Stream Timeout
+        /// 
+        /// Milliseconds the relay may take to send the
+        /// header of a data stream, defaults to 10 seconds
+    }))]
+    pub data_stream_timeout: Option<u64>,
+
+    /// Milliseconds before a Subscription expires
+    /// to refresh it, 0 never, defaults to 1 second
+    #[arg(long = "refresh-margin")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Refresh Margin
+        /// 
+        /// Milliseconds before a Subscription expires
+        /// to refresh it, 0 never, defaults to 1 second
+    }))]
+    pub refresh_margin: Option<u64>,
+
+    /// Authorization Token sent with the setup to
+    /// authorize the session and all its Requests
+    #[arg(long = "auth-token")]
+    #[serde(default)]
+    #[builder(into, setters(doc {
+        /// ## Authorization Token
+        /// 
+        /// Authorization Token sent with the setup to
+        /// authorize the session and all its Requests
+    }))]
+    pub auth_token: Option<String>,
+}
+
+impl ClientConfig {
+    /// How to verify the certificate of the relay.
+    pub fn tls_verification(&self) -> TlsVerification {
+        match (&self.ca, self.insecure_localhost) {
+            (_, true) => TlsVerification::InsecureLocalhost,
+            (Some(ca), false) => TlsVerification::CaFile(ca.clone()),
+            (None, false) => TlsVerification::NativeRoots,
+        }
+    }
+
+    /// The [auth_token](Self::auth_token) as the
+    /// Value of a Token of Type 0.
+    pub fn auth_token(&self) -> Option<Token> {
+        self.auth_token.as_ref().map(|value| {
+            Token::builder()
+                .use_value()
+                .typ(0u8)
+                .value(value.as_bytes())
+                .build()
+        })
+    }
+
+    /// How long the relay may take before the
+    /// session is closed.
+    pub fn timeouts(&self) -> Timeouts {
+        let timeouts = Timeouts::from_millis(
+            self.setup_timeout,
+            self.request_timeout,
+            self.data_stream_timeout,
+        );
+        Timeouts {
+            refresh: self
+                .refresh_margin
+                .map_or(Timeouts::REFRESH, Duration::from_millis),
+            ..timeouts
+        }
+    }
+}
+
+strum! {
+    /// What a [Client](super::Client) does when a new
+    /// Request exceeds the maximum Request ID the
+    /// peer granted
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+    pub enum OnRequestsBlocked {
+        /// Wait until the peer grants new Request IDs
+        #[default]
+        Wait = "wait" | "w",
+        /// Fail the Request right away
+        Fail = "fail" | "f",
+    }
 }
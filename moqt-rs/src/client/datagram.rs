@@ -0,0 +1,153 @@
+use {
+    super::{ClientError, error::ctx, track_publisher::Delivery},
+    crate::{
+        transport::Connection,
+        types::data::{Extensions, ObjectDatagram, ObjectPayload, ObjectStatus},
+    },
+    snafu::ResultExt,
+    std::sync::Arc,
+    tokio::sync::watch,
+    varint::{VarIntNumber, x},
+};
+
+/// ## Datagram Publisher
+///
+/// Delivers the Objects of a Track accepted by
+/// [Client::publish](super::Client::publish) as
+/// datagrams, see [TrackPublisher::datagram_publisher](super::TrackPublisher::datagram_publisher).
+///
+/// Every Object is sent on its own, lost Objects
+/// are not retransmitted and do not delay the
+/// delivery of later Objects. While the peer
+/// disabled forwarding, or for Objects outside
+/// the range it asked for, nothing is sent.
+pub struct DatagramPublisher {
+    transport: Arc<Connection>,
+    track_alias: x!(i),
+    publisher_priority: u8,
+    delivery: watch::Receiver<Delivery>,
+}
+
+impl DatagramPublisher {
+    pub(super) fn new(
+        transport: Arc<Connection>,
+        track_alias: x!(i),
+        publisher_priority: u8,
+        delivery: watch::Receiver<Delivery>,
+    ) -> Self {
+        Self {
+            transport,
+            track_alias,
+            publisher_priority,
+            delivery,
+        }
+    }
+
+    /// Sends an Object with `payload`.
+    pub fn send<G, O, P>(&self, group: G, object: O, payload: P) -> Result<(), ClientError>
+    where
+        G: Into<x!(i)>,
+        O: Into<x!(i)>,
+        P: Into<ObjectPayload>,
+    {
+        self.transmit(
+            ObjectDatagram::builder()
+                .alias(self.track_alias.clone())
+                .group(group)
+                .object(object)
+                .priority(self.publisher_priority)
+                .payload(payload)
+                .build(),
+        )
+    }
+
+    /// Sends an Object with `payload` and Extension Headers.
+    pub fn send_with_extensions<G, O, P>(
+        &self,
+        group: G,
+        object: O,
+        extensions: Extensions,
+        payload: P,
+    ) -> Result<(), ClientError>
+    where
+        G: Into<x!(i)>,
+        O: Into<x!(i)>,
+        P: Into<ObjectPayload>,
+    {
+        self.transmit(
+            ObjectDatagram::builder()
+                .alias(self.track_alias.clone())
+                .group(group)
+                .object(object)
+                .priority(self.publisher_priority)
+                .extensions(extensions)
+                .payload(payload)
+                .build(),
+        )
+    }
+
+    /// Sends the final Object of `group` with `payload`.
+    pub fn send_end_of_group<G, O, P>(
+        &self,
+        group: G,
+        object: O,
+        payload: P,
+    ) -> Result<(), ClientError>
+    where
+        G: Into<x!(i)>,
+        O: Into<x!(i)>,
+        P: Into<ObjectPayload>,
+    {
+        self.transmit(
+            ObjectDatagram::builder()
+                .alias(self.track_alias.clone())
+                .group(group)
+                .object(object)
+                .priority(self.publisher_priority)
+                .end_of_group(true)
+                .payload(payload)
+                .build(),
+        )
+    }
+
+    /// Sends an [ObjectStatus] for the Object at
+    /// `group` and `object`.
+    pub fn send_status<G, O>(
+        &self,
+        group: G,
+        object: O,
+        status: ObjectStatus,
+    ) -> Result<(), ClientError>
+    where
+        G: Into<x!(i)>,
+        O: Into<x!(i)>,
+    {
+        self.transmit(
+            ObjectDatagram::builder()
+                .alias(self.track_alias.clone())
+                .group(group)
+                .object(object)
+                .priority(self.publisher_priority)
+                .payload(status)
+                .build(),
+        )
+    }
+
+    /// Sends `datagram` unless the peer did not
+    /// ask for it.
+    ///
+    /// Fails if it exceeds the maximum datagram
+    /// size of the connection.
+    fn transmit(&self, datagram: ObjectDatagram) -> Result<(), ClientError> {
+        let (group, object) = (
+            datagram.group_id.number::<u64>(),
+            datagram.resolve_object_id().number::<u64>(),
+        );
+        if !self.delivery.borrow().accepts(group, object) {
+            return Ok(());
+        }
+        self.transport
+            .send_object_datagram(&datagram)
+            .context(ctx::DatagramSnafu)
+    }
+}
@@ -1,7 +1,10 @@
 use {
     crate::{
-        error::ControlStreamError, transport::error::ConnectionError, types::error::RequestIdError,
+        error::{ControlStreamError, FetchStreamError, SubgroupStreamError},
+        transport::error::{ConnectionError, DatagramError},
+        types::{error::RequestIdError, error_code},
     },
+    core::time::Duration,
     snafu::Snafu,
 };
 
@@ -17,6 +20,75 @@ pub enum ClientError {
     #[snafu(display("client's ControlStream ran into an error"))]
     ControlStream { source: ControlStreamError },
 
+    #[snafu(display("failed to transmit a datagram"))]
+    Datagram { source: DatagramError },
+
+    #[snafu(display("peer did not open the data stream within {timeout:?}"))]
+    DataStreamTimeout { timeout: Duration },
+
+    #[snafu(display("failed to serve or receive a fetch"))]
+    Fetch { source: FetchStreamError },
+
+    #[snafu(display("fetch rejected with {code:?}: {reason}"))]
+    FetchRejected {
+        code: error_code::Fetch,
+        reason: String,
+    },
+
+    #[snafu(display("the peer sent a goaway, no new requests are sent"))]
+    GoingAway,
+
+    #[snafu(display("group or object ID exceeds the maximum value"))]
+    IdOverflow,
+
+    #[snafu(display("publish rejected with {code:?}: {reason}"))]
+    PublishRejected {
+        code: error_code::Publish,
+        reason: String,
+    },
+
+    #[snafu(display("publish namespace rejected with {code:?}: {reason}"))]
+    PublishNamespaceRejected {
+        code: error_code::PublishNamespace,
+        reason: String,
+    },
+
     #[snafu(display("maximum request ID reached"))]
     RequestLimitReached { source: RequestIdError },
+
+    #[snafu(display("peer did not respond within {timeout:?}"))]
+    RequestTimeout { timeout: Duration },
+
+    #[snafu(display("session closed before the request completed"))]
+    SessionClosed,
+
+    #[snafu(display("failed to send or receive a subgroup"))]
+    Subgroup { source: SubgroupStreamError },
+
+    #[snafu(display("subscribe rejected with {code:?}: {reason}"))]
+    SubscribeRejected {
+        code: error_code::Subscribe,
+        reason: String,
+    },
+
+    #[snafu(display("subscribe namespace rejected with {code:?}: {reason}"))]
+    SubscribeNamespaceRejected {
+        code: error_code::SubscribeNamespace,
+        reason: String,
+    },
+
+    #[snafu(display("failed to configure TLS: {msg}"))]
+    Tls { msg: String },
+
+    #[snafu(display("track status rejected with {code:?}: {reason}"))]
+    TrackStatusRejected {
+        code: error_code::TrackStatus,
+        reason: String,
+    },
+
+    #[snafu(display("unexpected response message {key:#x}"))]
+    UnexpectedResponse { key: u32 },
+
+    #[snafu(display("no active subscription with request ID {request_id}"))]
+    UnknownSubscription { request_id: u64 },
 }
@@ -0,0 +1,395 @@
+use {
+    super::{
+        ClientError, OnRequestsBlocked,
+        builder::Connector,
+        ctx,
+        routes::{self, Routes},
+        subscription::{self, Active, Resumable},
+    },
+    crate::{
+        ControlStream, Timeouts,
+        transport::{Connection, RecvStream},
+        types::{
+            data::ObjectDatagram,
+            error::RequestIdError,
+            error_code::Termination,
+            message::{ControlMessage, RequestsBlocked, Subscribe, Unsubscribe},
+        },
+    },
+    bytes::BytesMut,
+    snafu::{OptionExt, ResultExt},
+    std::sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    tokio::{
+        sync::{mpsc, oneshot, watch},
+        task::JoinHandle,
+    },
+    tracing::{debug, info, warn},
+    url::Url,
+    varint::{VarIntNumber, x},
+};
+
+/// ## Link
+///
+/// A single session of a [Client](super::Client)
+/// with the relay at its URL.
+///
+/// Replaced by a new Link whenever the Client
+/// migrates to another session.
+pub(super) struct Link {
+    url: Url,
+    on_blocked: OnRequestsBlocked,
+    timeouts: Timeouts,
+    pub transport: Arc<Connection>,
+    pub control_stream: Arc<ControlStream>,
+    pub routes: Arc<Routes>,
+    /// The maximum Request ID the last
+    /// RequestsBlocked was sent for.
+    blocked_at: AtomicU64,
+    dispatcher: JoinHandle<()>,
+}
+
+impl Link {
+    /// Creates a Link on an established connection
+    /// and starts routing everything the peer sends.
+    fn new(
+        url: Url,
+        on_blocked: OnRequestsBlocked,
+        timeouts: Timeouts,
+        transport: Connection,
+        control_stream: ControlStream,
+        datagrams: mpsc::Sender<ObjectDatagram>,
+        goaway: watch::Sender<Option<Url>>,
+    ) -> Self {
+        let transport = Arc::new(transport);
+        let routes = Arc::new(Routes::new(control_stream.peer_max_request_id()));
+        let control_stream = Arc::new(control_stream);
+
+        let dispatcher = tokio::spawn(routes::dispatch(
+            transport.clone(),
+            control_stream.clone(),
+            routes.clone(),
+            datagrams,
+            goaway,
+            timeouts.data_stream,
+        ));
+
+        Self {
+            url,
+            on_blocked,
+            timeouts,
+            transport,
+            control_stream,
+            routes,
+            blocked_at: AtomicU64::new(u64::MAX),
+            dispatcher,
+        }
+    }
+
+    /// How long the peer may take before the
+    /// session is closed.
+    pub fn timeouts(&self) -> &Timeouts {
+        &self.timeouts
+    }
+
+    /// Sends the Request `msg` with `request_id` and
+    /// waits for the response.
+    ///
+    /// Closes the session with
+    /// [ControlMessageTimeout](Termination::ControlMessageTimeout)
+    /// unless the peer responds in time.
+    pub async fn request(
+        &self,
+        request_id: &x!(i),
+        msg: ControlMessage,
+    ) -> Result<ControlMessage, ClientError> {
+        let (tx, rx) = oneshot::channel();
+        self.routes.responses.insert(request_id.number(), tx);
+
+        if let Err(err) = self.control_stream.send(msg).await {
+            self.routes.responses.remove(&request_id.number::<u64>());
+            return Err(err).context(ctx::ControlStreamSnafu);
+        }
+
+        let timeout = self.timeouts.request;
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(res) => res.ok().context(ctx::SessionClosedSnafu),
+            Err(_) => {
+                self.routes.responses.remove(&request_id.number::<u64>());
+                warn!(?request_id, ?timeout, "peer took too long to respond");
+                self.transport.close(Termination::ControlMessageTimeout);
+                ctx::RequestTimeoutSnafu { timeout }.fail()
+            }
+        }
+    }
+
+    /// Waits for the stream of the accepted Fetch
+    /// with `request_id`.
+    ///
+    /// Closes the session with
+    /// [DataStreamTimeout](Termination::DataStreamTimeout)
+    /// unless the peer opens it in time.
+    pub async fn fetch_stream(
+        &self,
+        request_id: &x!(i),
+        stream: oneshot::Receiver<(RecvStream, BytesMut)>,
+    ) -> Result<(RecvStream, BytesMut), ClientError> {
+        let timeout = self.timeouts.data_stream;
+        match tokio::time::timeout(timeout, stream).await {
+            Ok(res) => res.ok().context(ctx::SessionClosedSnafu),
+            Err(_) => {
+                self.routes.fetches.remove(&request_id.number::<u64>());
+                warn!(
+                    ?request_id,
+                    ?timeout,
+                    "peer took too long to open the fetch stream"
+                );
+                self.transport.close(Termination::DataStreamTimeout);
+                ctx::DataStreamTimeoutSnafu { timeout }.fail()
+            }
+        }
+    }
+
+    /// Returns the Request ID for the next Request.
+    ///
+    /// Once the peer granted no more Request IDs,
+    /// sends a [RequestsBlocked] and waits for new
+    /// ones unless configured to [fail](OnRequestsBlocked::Fail).
+    ///
+    /// Fails once the peer sent a Goaway.
+    pub async fn next_id(&self) -> Result<x!(i), ClientError> {
+        loop {
+            snafu::ensure!(!self.routes.is_going_away(), ctx::GoingAwaySnafu);
+
+            // register before checking to not miss a grant
+            let credit = self.routes.credit.notified();
+            tokio::pin!(credit);
+            credit.as_mut().enable();
+
+            let res = self
+                .routes
+                .request_id
+                .lock()
+                .expect("request ID lock poisoned")
+                .get();
+            let max = match res {
+                Ok(request_id) => return Ok(request_id),
+                Err(RequestIdError::Blocked { max }) => max,
+                Err(err) => return Err(err).context(ctx::RequestLimitReachedSnafu),
+            };
+
+            if self.blocked_at.swap(max, Ordering::Relaxed) != max {
+                let max_id = <x!(i)>::try_from(max).ok().context(ctx::IdOverflowSnafu)?;
+                self.control_stream
+                    .send(ControlMessage::RequestsBlocked(RequestsBlocked::new(
+                        max_id,
+                    )))
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+            }
+
+            if self.on_blocked == OnRequestsBlocked::Fail {
+                return Err(RequestIdError::Blocked { max }).context(ctx::RequestLimitReachedSnafu);
+            }
+            if self.dispatcher.is_finished() {
+                return ctx::SessionClosedSnafu.fail();
+            }
+
+            credit.await;
+        }
+    }
+
+    /// Ends the session after the Client migrated
+    /// to a new one.
+    fn close(&self) {
+        self.transport.close(Termination::NoError);
+        self.dispatcher.abort();
+        self.routes.close();
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        self.dispatcher.abort();
+    }
+}
+
+/// ## Links
+///
+/// The current [Link] of a [Client](super::Client)
+/// and everything it takes to replace it.
+pub(super) struct Links {
+    current: RwLock<Arc<Link>>,
+    connector: Connector,
+    on_blocked: OnRequestsBlocked,
+    timeouts: Timeouts,
+    /// Weak to let the datagram channel close
+    /// with the last session.
+    datagrams: mpsc::WeakSender<ObjectDatagram>,
+    /// Sends the migration URL of every Goaway.
+    goaway: watch::Sender<Option<Url>>,
+}
+
+impl Links {
+    /// Creates the Links of a Client connected
+    /// to `url`, which routes all datagrams not
+    /// belonging to a Subscription to `datagrams`.
+    pub fn new(
+        connector: Connector,
+        on_blocked: OnRequestsBlocked,
+        timeouts: Timeouts,
+        url: Url,
+        transport: Connection,
+        control_stream: ControlStream,
+        datagrams: mpsc::Sender<ObjectDatagram>,
+    ) -> Self {
+        let (goaway, _) = watch::channel(None);
+        let weak_datagrams = datagrams.downgrade();
+        let link = Link::new(
+            url,
+            on_blocked,
+            timeouts,
+            transport,
+            control_stream,
+            datagrams,
+            goaway.clone(),
+        );
+
+        Self {
+            current: RwLock::new(Arc::new(link)),
+            connector,
+            on_blocked,
+            timeouts,
+            datagrams: weak_datagrams,
+            goaway,
+        }
+    }
+
+    /// The Link of the current session.
+    pub fn current(&self) -> Arc<Link> {
+        self.current.read().expect("link lock poisoned").clone()
+    }
+
+    /// Receives the migration URL of every
+    /// following Goaway.
+    pub fn goaway(&self) -> watch::Receiver<Option<Url>> {
+        self.goaway.subscribe()
+    }
+
+    /// Connects a new session to `url`, or to the
+    /// URL of the current one without `url`, and
+    /// re-issues all active Subscriptions on it.
+    ///
+    /// The current session is closed afterwards.
+    pub async fn migrate(&self, url: Option<Url>) -> Result<(), ClientError> {
+        let old = self.current();
+        let url = url.unwrap_or_else(|| old.url.clone());
+        let datagrams = self.datagrams.upgrade().context(ctx::SessionClosedSnafu)?;
+
+        let (transport, control_stream) = self.connector.connect(&url, self.timeouts.setup).await?;
+        let link = Link::new(
+            url,
+            self.on_blocked,
+            self.timeouts,
+            transport,
+            control_stream,
+            datagrams,
+            self.goaway.clone(),
+        );
+        info!(url = %link.url, "connected a new session to migrate to");
+
+        let subscriptions: Vec<Resumable> = old
+            .routes
+            .subscriptions
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        for subscription in subscriptions {
+            if let Err(err) = resubscribe(&link, subscription).await {
+                warn!(%err, "failed to re-issue a subscription");
+            }
+        }
+
+        *self.current.write().expect("link lock poisoned") = Arc::new(link);
+        old.close();
+        Ok(())
+    }
+}
+
+/// Re-issues `subscription` on `link`, routing
+/// its Objects to the existing Subscription.
+async fn resubscribe(link: &Link, subscription: Resumable) -> Result<(), ClientError> {
+    if subscription.active.strong_count() == 0 {
+        return Ok(());
+    }
+
+    let request_id = link.next_id().await?;
+    let subscribe = Subscribe {
+        request_id: request_id.clone(),
+        ..subscription.subscribe
+    };
+    link.routes
+        .pending_tracks
+        .insert(request_id.number(), subscription.objects.clone());
+
+    let res = link
+        .request(&request_id, ControlMessage::Subscribe(subscribe.clone()))
+        .await;
+    let ok = match res {
+        Ok(ControlMessage::SubscribeOk(ok)) => ok,
+        res => {
+            link.routes
+                .pending_tracks
+                .remove(&request_id.number::<u64>());
+            return match res? {
+                ControlMessage::SubscribeError(err) => ctx::SubscribeRejectedSnafu {
+                    code: err.code,
+                    reason: err.reason.to_string(),
+                }
+                .fail(),
+                msg => ctx::UnexpectedResponseSnafu { key: msg.key() }.fail(),
+            };
+        }
+    };
+
+    let Some(active) = subscription.active.upgrade() else {
+        // dropped while it was re-issued
+        link.routes.tracks.remove(&ok.alias.number::<u64>());
+        return link
+            .control_stream
+            .send(ControlMessage::Unsubscribe(Unsubscribe::new(request_id)))
+            .await
+            .context(ctx::ControlStreamSnafu);
+    };
+
+    debug!(?request_id, alias = ?ok.alias, "re-issued subscription");
+    *active.write().expect("subscription lock poisoned") = Active {
+        ok,
+        control_stream: link.control_stream.clone(),
+        routes: link.routes.clone(),
+    };
+    link.routes.subscriptions.insert(
+        request_id.number(),
+        Resumable {
+            subscribe,
+            ..subscription
+        },
+    );
+    subscription::keep_alive(&active, &link.timeouts);
+    Ok(())
+}
+
+/// Migrates the Client of `links` to a new
+/// session on every Goaway.
+pub(super) async fn reconnect(links: Arc<Links>) {
+    let mut goaway = links.goaway();
+
+    while goaway.changed().await.is_ok() {
+        let url = goaway.borrow_and_update().clone();
+        if let Err(err) = links.migrate(url).await {
+            warn!(%err, "failed to migrate to a new session");
+        }
+    }
+}
@@ -1,67 +1,601 @@
 mod builder;
 mod config;
+mod datagram;
 mod error;
+mod link;
+mod namespace_subscription;
+mod published_namespace;
+mod routes;
+mod subscription;
+mod tls;
+mod track_publisher;
 
-pub use {config::ClientConfig, error::ClientError};
+pub(crate) use builder::Connector;
+pub use {
+    config::{ClientConfig, OnRequestsBlocked},
+    datagram::DatagramPublisher,
+    error::ClientError,
+    namespace_subscription::{NamespaceEvent, NamespaceSubscription},
+    published_namespace::PublishedNamespace,
+    subscription::Subscription,
+    tls::TlsVerification,
+    track_publisher::TrackPublisher,
+};
 
 use {
     crate::{
-        ControlStream,
+        ControlStream, FetchStream, FetchWriter, Timeouts,
         transport::Connection,
         types::{
-            Name, Namespace, RequestId,
-            config::SubscribeConfig,
-            message::{ControlMessage, Subscribe},
+            GroupOrder, JoiningFetch, Name, Namespace, StandaloneFetch,
+            config::{DEFAULT_PRIORITY, SubscribeConfig},
+            data::ObjectDatagram,
+            message::{
+                ControlMessage, Fetch, Publish, PublishNamespace, Subscribe, SubscribeNamespace,
+                SubscribeUpdate, TrackStatus, TrackStatusOk,
+            },
         },
     },
     error::ctx,
-    snafu::ResultExt,
-    tokio::sync::RwLock,
-    varint::x,
+    link::{Link, Links},
+    routes::OBJECT_BUFFER,
+    snafu::{OptionExt, ResultExt},
+    std::sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    subscription::{Active, Resumable},
+    tokio::{
+        sync::{Mutex, mpsc, oneshot},
+        task::JoinHandle,
+    },
+    url::Url,
+    varint::{VarIntNumber, x},
 };
 
-/// TODO docs
+/// ## Client
+///
+/// A MOQT session to a relay, established over
+/// WebTransport, native QUIC or in memory with
+/// the respective builder.
+///
+/// Publishes Namespaces and Tracks, subscribes
+/// to Tracks and Namespace prefixes, fetches
+/// past Objects and queries the status of
+/// Tracks. Requests wait for the response of
+/// the peer, closing the session if it takes
+/// longer than the request timeout.
+///
+/// Once the peer sends a Goaway, no new Requests
+/// are sent until the Client [migrated](Self::migrate)
+/// to a new session, which happens automatically
+/// with reconnecting enabled.
 pub struct Client {
-    transport: Connection,
-    control_stream: ControlStream,
-    request_id: RwLock<RequestId>,
+    links: Arc<Links>,
+    track_alias: AtomicU64,
+    datagrams: Mutex<mpsc::Receiver<ObjectDatagram>>,
+    /// Migrates to a new session on every Goaway
+    /// if reconnecting is enabled.
+    reconnect: Option<JoinHandle<()>>,
 }
 
 impl Client {
-    // TODO publish(namespace, name)
-    // TODO subscribe(namespace, name)
-    // TODO etc.
+    /// Creates a [Client] on an established
+    /// connection to `url` and starts routing
+    /// everything the peer sends.
+    fn from_parts(
+        connector: Connector,
+        url: Url,
+        transport: Connection,
+        control_stream: ControlStream,
+        on_blocked: OnRequestsBlocked,
+        reconnect: bool,
+        timeouts: Timeouts,
+    ) -> Self {
+        let (datagram_tx, datagram_rx) = mpsc::channel(OBJECT_BUFFER);
+        let links = Arc::new(Links::new(
+            connector,
+            on_blocked,
+            timeouts,
+            url,
+            transport,
+            control_stream,
+            datagram_tx,
+        ));
+
+        let reconnect = reconnect.then(|| tokio::spawn(link::reconnect(links.clone())));
+
+        Self {
+            links,
+            track_alias: AtomicU64::new(0),
+            datagrams: Mutex::new(datagram_rx),
+            reconnect,
+        }
+    }
+
+    /// Waits for the peer to send a Goaway and
+    /// returns its migration URL.
+    ///
+    /// Without a URL, the peer asks to reconnect
+    /// to the current one. Either way, no new
+    /// Requests are sent on the current session.
+    pub async fn goaway(&self) -> Result<Option<Url>, ClientError> {
+        let mut goaway = self.links.goaway();
+        goaway
+            .changed()
+            .await
+            .ok()
+            .context(ctx::SessionClosedSnafu)?;
+
+        let url = goaway.borrow_and_update().clone();
+        Ok(url)
+    }
+
+    /// Connects a new session to `url`, or to the
+    /// current URL without one, and re-issues all
+    /// active Subscriptions on it.
+    ///
+    /// The current session is closed afterwards,
+    /// ending its published Namespaces, Tracks,
+    /// Fetches and Namespace subscriptions.
+    ///
+    /// With reconnecting enabled this happens on
+    /// every Goaway.
+    pub async fn migrate(&self, url: Option<Url>) -> Result<(), ClientError> {
+        self.links.migrate(url).await
+    }
+
+    /// Announces that this Client publishes Tracks
+    /// within `namespace`.
+    ///
+    /// The Namespace stays published until the
+    /// returned [PublishedNamespace] is dropped.
+    pub async fn publish_namespace<S>(
+        &self,
+        namespace: S,
+    ) -> Result<PublishedNamespace, ClientError>
+    where
+        S: Into<Namespace>,
+    {
+        let namespace = namespace.into();
+        let link = self.link();
+        let request_id = link.next_id().await?;
+        let msg = PublishNamespace::builder()
+            .id(request_id.clone())
+            .namespace(namespace.clone())
+            .build();
+
+        match link
+            .request(&request_id, ControlMessage::PublishNamespace(msg))
+            .await?
+        {
+            ControlMessage::PublishNamespaceOk(_) => Ok(PublishedNamespace::new(
+                request_id,
+                namespace,
+                link.control_stream.clone(),
+            )),
+            ControlMessage::PublishNamespaceError(err) => ctx::PublishNamespaceRejectedSnafu {
+                code: err.code,
+                reason: err.reason.to_string(),
+            }
+            .fail(),
+            msg => ctx::UnexpectedResponseSnafu { key: msg.key() }.fail(),
+        }
+    }
+
+    /// Subscribes to all Namespaces starting with
+    /// `prefix`.
+    ///
+    /// The returned [NamespaceSubscription] yields
+    /// the Namespaces the peer publishes within the
+    /// prefix, the existing ones first, until it is
+    /// dropped.
+    pub async fn subscribe_namespace<S>(
+        &self,
+        prefix: S,
+    ) -> Result<NamespaceSubscription, ClientError>
+    where
+        S: Into<Namespace>,
+    {
+        let prefix = prefix.into();
+        let link = self.link();
+        let request_id = link.next_id().await?;
+        let msg = SubscribeNamespace::builder()
+            .id(request_id.clone())
+            .namespace_prefix(prefix.clone())
+            .build();
+
+        // route before the request, the peer publishes
+        // the existing Namespaces right after its response
+        let (tx, rx) = mpsc::unbounded_channel();
+        link.routes
+            .namespaces
+            .insert(request_id.number(), (prefix.clone(), tx));
+
+        let res = link
+            .request(&request_id, ControlMessage::SubscribeNamespace(msg))
+            .await;
+        let err = match res {
+            Ok(ControlMessage::SubscribeNamespaceOk(_)) => {
+                return Ok(NamespaceSubscription::new(
+                    request_id,
+                    prefix,
+                    link.control_stream.clone(),
+                    link.routes.clone(),
+                    rx,
+                ));
+            }
+            Ok(ControlMessage::SubscribeNamespaceError(err)) => {
+                ctx::SubscribeNamespaceRejectedSnafu {
+                    code: err.code,
+                    reason: err.reason.to_string(),
+                }
+                .build()
+            }
+            Ok(msg) => ctx::UnexpectedResponseSnafu { key: msg.key() }.build(),
+            Err(err) => err,
+        };
+
+        link.routes.namespaces.remove(&request_id.number::<u64>());
+        Err(err)
+    }
+
+    /// Publishes the Track `name` in `namespace`.
+    ///
+    /// Waits for the peer to accept the Track and
+    /// returns a [TrackPublisher] to send its Objects.
+    pub async fn publish<S, N>(&self, namespace: S, name: N) -> Result<TrackPublisher, ClientError>
+    where
+        S: Into<Namespace>,
+        N: Into<Name>,
+    {
+        let link = self.link();
+        let request_id = link.next_id().await?;
+        let track_alias = <x!(i)>::try_from(self.track_alias.fetch_add(1, Ordering::Relaxed))
+            .ok()
+            .context(ctx::IdOverflowSnafu)?;
+        let msg = Publish::builder()
+            .id(request_id.clone())
+            .namespace(namespace)
+            .name(name)
+            .alias(track_alias.clone())
+            .group_order(GroupOrder::Ascending)
+            .forward(true)
+            .build();
+
+        match link
+            .request(&request_id, ControlMessage::Publish(msg))
+            .await?
+        {
+            ControlMessage::PublishOk(ok) => Ok(TrackPublisher::new(
+                ok,
+                track_alias,
+                DEFAULT_PRIORITY,
+                link.transport.clone(),
+                link.control_stream.clone(),
+                link.routes.clone(),
+            )),
+            ControlMessage::PublishError(err) => ctx::PublishRejectedSnafu {
+                code: err.code,
+                reason: err.reason.to_string(),
+            }
+            .fail(),
+            msg => ctx::UnexpectedResponseSnafu { key: msg.key() }.fail(),
+        }
+    }
+
+    /// Subscribes to the Track `name` in `namespace`.
+    ///
+    /// Waits for the publisher to accept the
+    /// Subscription and returns a [Subscription]
+    /// yielding the Objects of the Track.
     pub async fn subscribe<S, N>(
         &self,
         namespace: S,
         name: N,
         config: Option<SubscribeConfig>,
-    ) -> Result<(), ClientError>
+    ) -> Result<Subscription, ClientError>
     where
         S: Into<Namespace>,
         N: Into<Name>,
     {
+        let link = self.link();
+        let request_id = link.next_id().await?;
         let msg = Subscribe::from_config()
-            .id(self.next_id().await?)
+            .id(request_id.clone())
             .namespace(namespace)
             .name(name)
             .config(config.unwrap_or_default())
             .build();
 
-        self.control_stream
-            .send(ControlMessage::Subscribe(msg))
+        let (tx, rx) = mpsc::channel(OBJECT_BUFFER);
+        link.routes
+            .pending_tracks
+            .insert(request_id.number(), tx.clone());
+
+        let res = link
+            .request(&request_id, ControlMessage::Subscribe(msg.clone()))
+            .await;
+        let err = match res {
+            Ok(ControlMessage::SubscribeOk(ok)) => {
+                let active = Arc::new(RwLock::new(Active {
+                    ok,
+                    control_stream: link.control_stream.clone(),
+                    routes: link.routes.clone(),
+                }));
+                let done = Arc::default();
+                link.routes.subscriptions.insert(
+                    request_id.number(),
+                    Resumable {
+                        subscribe: msg,
+                        objects: tx,
+                        active: Arc::downgrade(&active),
+                        done: Arc::clone(&done),
+                    },
+                );
+                subscription::keep_alive(&active, link.timeouts());
+                return Ok(Subscription::new(active, rx, done));
+            }
+            Ok(ControlMessage::SubscribeError(err)) => ctx::SubscribeRejectedSnafu {
+                code: err.code,
+                reason: err.reason.to_string(),
+            }
+            .build(),
+            Ok(msg) => ctx::UnexpectedResponseSnafu { key: msg.key() }.build(),
+            Err(err) => err,
+        };
+
+        link.routes
+            .pending_tracks
+            .remove(&request_id.number::<u64>());
+        Err(err)
+    }
+
+    /// Sends `update` for the active Subscription
+    /// with its Request ID.
+    ///
+    /// Narrows the Subscription, changes its
+    /// priority and pauses or resumes forwarding.
+    /// Widening it is a protocol violation that
+    /// closes the session.
+    pub async fn update_subscription(&self, update: SubscribeUpdate) -> Result<(), ClientError> {
+        let link = self.link();
+        subscription::send_update(&link.routes, &link.control_stream, update).await
+    }
+
+    /// Queries the status of the Track `name` in
+    /// `namespace` without subscribing to it.
+    ///
+    /// The returned [TrackStatusOk] holds the
+    /// Largest Location, whether content exists,
+    /// the Group Order and the Expiry.
+    pub async fn track_status<S, N>(
+        &self,
+        namespace: S,
+        name: N,
+    ) -> Result<TrackStatusOk, ClientError>
+    where
+        S: Into<Namespace>,
+        N: Into<Name>,
+    {
+        let link = self.link();
+        let request_id = link.next_id().await?;
+        let msg: TrackStatus = Subscribe::from_config()
+            .id(request_id.clone())
+            .namespace(namespace)
+            .name(name)
+            .config(SubscribeConfig::default())
+            .build();
+
+        match link
+            .request(&request_id, ControlMessage::TrackStatus(msg))
+            .await?
+        {
+            ControlMessage::TrackStatusOk(ok) => Ok(ok),
+            ControlMessage::TrackStatusError(err) => ctx::TrackStatusRejectedSnafu {
+                code: err.code,
+                reason: err.reason.to_string(),
+            }
+            .fail(),
+            msg => ctx::UnexpectedResponseSnafu { key: msg.key() }.fail(),
+        }
+    }
+
+    /// Requests the Objects within the range of
+    /// `standalone` and returns the [FetchStream]
+    /// they are received on.
+    pub async fn fetch(
+        &self,
+        standalone: StandaloneFetch,
+        group_order: GroupOrder,
+    ) -> Result<FetchStream, ClientError> {
+        self.request_fetch(group_order, |request_id| {
+            Fetch::builder()
+                .id(request_id)
+                .sub_prio(DEFAULT_PRIORITY)
+                .group_order(group_order)
+                .standalone_fetch(standalone)
+                .build()
+        })
+        .await
+    }
+
+    /// Requests the Objects of the `groups` Groups
+    /// preceding the start of `subscription`, up to
+    /// the Object it starts after.
+    ///
+    /// Together with the [Subscription] no Object
+    /// is missed or received twice.
+    pub async fn relative_joining_fetch<G>(
+        &self,
+        subscription: &Subscription,
+        groups: G,
+        group_order: GroupOrder,
+    ) -> Result<FetchStream, ClientError>
+    where
+        G: Into<x!(i)>,
+    {
+        let joining = JoiningFetch::builder()
+            .id(subscription.request_id())
+            .start(groups)
+            .build();
+        self.request_fetch(group_order, |request_id| {
+            Fetch::builder()
+                .id(request_id)
+                .sub_prio(DEFAULT_PRIORITY)
+                .group_order(group_order)
+                .relative_joining_fetch(joining)
+                .build()
+        })
+        .await
+    }
+
+    /// Requests the Objects from the start of the
+    /// Group `start_group` up to the Object
+    /// `subscription` starts after.
+    ///
+    /// Together with the [Subscription] no Object
+    /// is missed or received twice.
+    pub async fn absolute_joining_fetch<G>(
+        &self,
+        subscription: &Subscription,
+        start_group: G,
+        group_order: GroupOrder,
+    ) -> Result<FetchStream, ClientError>
+    where
+        G: Into<x!(i)>,
+    {
+        let joining = JoiningFetch::builder()
+            .id(subscription.request_id())
+            .start(start_group)
+            .build();
+        self.request_fetch(group_order, |request_id| {
+            Fetch::builder()
+                .id(request_id)
+                .sub_prio(DEFAULT_PRIORITY)
+                .group_order(group_order)
+                .absolute_joining_fetch(joining)
+                .build()
+        })
+        .await
+    }
+
+    /// Sends the [Fetch] `build` creates for the
+    /// next Request ID and returns the
+    /// [FetchStream] its Objects are received on.
+    async fn request_fetch<B>(
+        &self,
+        group_order: GroupOrder,
+        build: B,
+    ) -> Result<FetchStream, ClientError>
+    where
+        B: FnOnce(x!(i)) -> Fetch,
+    {
+        let link = self.link();
+        let request_id = link.next_id().await?;
+        let msg = build(request_id.clone());
+
+        let (tx, rx) = oneshot::channel();
+        link.routes.fetches.insert(request_id.number(), tx);
+
+        match link
+            .request(&request_id, ControlMessage::Fetch(msg))
+            .await?
+        {
+            ControlMessage::FetchOk(_) => (),
+            msg => {
+                link.routes.fetches.remove(&request_id.number::<u64>());
+                return match msg {
+                    ControlMessage::FetchError(err) => ctx::FetchRejectedSnafu {
+                        code: err.code,
+                        reason: err.reason.to_string(),
+                    }
+                    .fail(),
+                    msg => ctx::UnexpectedResponseSnafu { key: msg.key() }.fail(),
+                };
+            }
+        }
+
+        let (stream, buf) = link.fetch_stream(&request_id, rx).await?;
+
+        FetchStream::new(stream, buf, group_order)
             .await
-            .context(ctx::ControlStreamSnafu)?;
+            .context(ctx::FetchSnafu)
+    }
 
-        // TODO return a type to receive Objects from the subscription
-        Ok(())
+    /// Opens the stream to respond to the Fetch
+    /// with `request_id`.
+    ///
+    /// Use [FetchWriter::serve] to send the
+    /// requested Objects.
+    pub async fn fetch_writer<I>(&self, request_id: I) -> Result<FetchWriter, ClientError>
+    where
+        I: Into<x!(i)>,
+    {
+        FetchWriter::open(&self.link().transport, request_id.into())
+            .await
+            .context(ctx::FetchSnafu)
     }
 
-    async fn next_id(&self) -> Result<x!(i), ClientError> {
-        self.request_id
-            .write()
+    /// Receives the next Object sent as datagram
+    /// which does not belong to a [Subscription].
+    ///
+    /// The Track of the Object is identified by
+    /// its [track_alias](ObjectDatagram::track_alias).
+    pub async fn recv_datagram(&self) -> Result<ObjectDatagram, ClientError> {
+        self.datagrams
+            .lock()
+            .await
+            .recv()
             .await
-            .get()
-            .context(ctx::RequestLimitReachedSnafu)
+            .context(ctx::SessionClosedSnafu)
+    }
+
+    /// The Link of the current session.
+    fn link(&self) -> Arc<Link> {
+        self.links.current()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if let Some(reconnect) = &self.reconnect {
+            reconnect.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::transport::memory,
+        core::{net::SocketAddr, time::Duration},
+    };
+
+    #[tokio::test]
+    async fn subscribe_timeout_test() {
+        // completes the setup but never responds
+        let endpoint = memory::Endpoint::new(SocketAddr::from(([127, 0, 0, 1], 4443)));
+        let connector = endpoint.connector();
+        tokio::spawn(async move {
+            let conn = Connection::Memory(Box::new(endpoint.accept().await.unwrap()));
+            let control_stream = ControlStream::accept(&conn, Timeouts::SETUP, 0)
+                .await
+                .unwrap();
+            while control_stream.recv().await.is_ok() {}
+        });
+
+        let timeout = Duration::from_millis(20);
+        let client = Client::memory_builder()
+            .connect(connector)
+            .timeouts(Timeouts::builder().request(timeout).build())
+            .build()
+            .await
+            .unwrap();
+
+        let res = client.subscribe(["chat"], "text", None).await;
+        assert_eq!(res.err(), Some(ClientError::RequestTimeout { timeout }));
+        assert!(client.link().routes.pending_tracks.is_empty());
     }
 }
@@ -0,0 +1,137 @@
+use {
+    super::{ClientError, ctx, routes::Routes},
+    crate::{
+        ControlStream,
+        types::{
+            Namespace,
+            message::{ControlMessage, UnsubscribeNamespace},
+        },
+    },
+    core::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+    snafu::ResultExt,
+    std::sync::Arc,
+    tokio::{runtime::Handle, sync::mpsc},
+    tracing::warn,
+    varint::{VarIntNumber, x},
+};
+
+/// ## Namespace Event
+///
+/// A change to the Namespaces matching a
+/// [NamespaceSubscription].
+#[derive(Debug, PartialEq, Clone)]
+pub enum NamespaceEvent {
+    /// The peer published the Namespace.
+    Published(Namespace),
+
+    /// The peer withdrew the Namespace.
+    Done(Namespace),
+}
+
+/// ## Namespace Subscription
+///
+/// An active subscription to a Namespace prefix,
+/// created by [Client::subscribe_namespace](super::Client::subscribe_namespace).
+///
+/// Yields a [NamespaceEvent] as a [Stream] for
+/// every Namespace starting with the prefix that
+/// is published or withdrawn. Dropping it
+/// unsubscribes from the prefix.
+pub struct NamespaceSubscription {
+    request_id: x!(i),
+    prefix: Namespace,
+    control_stream: Arc<ControlStream>,
+    routes: Arc<Routes>,
+    events: mpsc::UnboundedReceiver<NamespaceEvent>,
+    active: bool,
+}
+
+impl NamespaceSubscription {
+    pub(super) fn new(
+        request_id: x!(i),
+        prefix: Namespace,
+        control_stream: Arc<ControlStream>,
+        routes: Arc<Routes>,
+        events: mpsc::UnboundedReceiver<NamespaceEvent>,
+    ) -> Self {
+        Self {
+            request_id,
+            prefix,
+            control_stream,
+            routes,
+            events,
+            active: true,
+        }
+    }
+
+    /// The Request ID of the
+    /// [SubscribeNamespace](crate::types::message::SubscribeNamespace).
+    pub fn request_id(&self) -> &x!(i) {
+        &self.request_id
+    }
+
+    /// The subscribed Namespace prefix.
+    pub fn prefix(&self) -> &Namespace {
+        &self.prefix
+    }
+
+    /// Receives the next [NamespaceEvent].
+    ///
+    /// None once the session is closed.
+    pub async fn recv(&mut self) -> Option<NamespaceEvent> {
+        self.events.recv().await
+    }
+
+    /// Unsubscribes from the prefix.
+    pub async fn unsubscribe(mut self) -> Result<(), ClientError> {
+        self.active = false;
+
+        let msg = self.remove_route();
+        self.control_stream
+            .send(msg)
+            .await
+            .context(ctx::ControlStreamSnafu)
+    }
+
+    /// Stops routing events to this subscription
+    /// and returns the message to unsubscribe.
+    fn remove_route(&self) -> ControlMessage {
+        self.routes
+            .namespaces
+            .remove(&self.request_id.number::<u64>());
+        ControlMessage::UnsubscribeNamespace(UnsubscribeNamespace::new(self.prefix.clone()))
+    }
+}
+
+impl Stream for NamespaceSubscription {
+    type Item = NamespaceEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Drop for NamespaceSubscription {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        let msg = self.remove_route();
+
+        let Ok(runtime) = Handle::try_current() else {
+            return;
+        };
+
+        let control_stream = self.control_stream.clone();
+        runtime.spawn(async move {
+            if let Err(err) = control_stream.send(msg).await {
+                warn!(%err, "failed to unsubscribe namespace");
+            }
+        });
+    }
+}
@@ -0,0 +1,88 @@
+use {
+    super::{ClientError, ctx},
+    crate::{
+        ControlStream,
+        types::{
+            Namespace,
+            message::{ControlMessage, PublishNamespaceDone},
+        },
+    },
+    snafu::ResultExt,
+    std::sync::Arc,
+    tokio::runtime::Handle,
+    tracing::warn,
+    varint::x,
+};
+
+/// ## Published Namespace
+///
+/// A Namespace the peer accepted from
+/// [Client::publish_namespace](super::Client::publish_namespace).
+///
+/// Dropping it withdraws the Namespace
+/// with a [PublishNamespaceDone].
+pub struct PublishedNamespace {
+    request_id: x!(i),
+    namespace: Namespace,
+    control_stream: Arc<ControlStream>,
+    active: bool,
+}
+
+impl PublishedNamespace {
+    pub(super) fn new(
+        request_id: x!(i),
+        namespace: Namespace,
+        control_stream: Arc<ControlStream>,
+    ) -> Self {
+        Self {
+            request_id,
+            namespace,
+            control_stream,
+            active: true,
+        }
+    }
+
+    /// The Request ID of the
+    /// [PublishNamespace](crate::types::message::PublishNamespace).
+    pub fn request_id(&self) -> &x!(i) {
+        &self.request_id
+    }
+
+    /// The published Namespace.
+    pub fn namespace(&self) -> &Namespace {
+        &self.namespace
+    }
+
+    /// Withdraws the Namespace.
+    pub async fn done(mut self) -> Result<(), ClientError> {
+        self.active = false;
+
+        self.control_stream
+            .send(ControlMessage::PublishNamespaceDone(
+                PublishNamespaceDone::new(self.namespace.clone()),
+            ))
+            .await
+            .context(ctx::ControlStreamSnafu)
+    }
+}
+
+impl Drop for PublishedNamespace {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        let Ok(runtime) = Handle::try_current() else {
+            return;
+        };
+
+        let control_stream = self.control_stream.clone();
+        let msg =
+            ControlMessage::PublishNamespaceDone(PublishNamespaceDone::new(self.namespace.clone()));
+        runtime.spawn(async move {
+            if let Err(err) = control_stream.send(msg).await {
+                warn!(%err, "failed to withdraw namespace");
+            }
+        });
+    }
+}
@@ -0,0 +1,681 @@
+use {
+    super::{
+        namespace_subscription::NamespaceEvent, subscription::Resumable,
+        track_publisher::Delivery,
+    },
+    crate::{
+        ControlStream,
+        subgroup::SubgroupReader,
+        transport::{Connection, RecvStream, error::DatagramError},
+        types::{
+            Namespace, RequestId, RequestWindow,
+            data::{FetchHeader, FetchHeaderType, Object, ObjectDatagram},
+            error_code::{self, Termination},
+            message::{
+                ControlMessage, FetchError, Goaway, MaxRequestId, PublishDone, PublishError,
+                PublishNamespaceError, PublishNamespaceOk, SubscribeError, SubscribeUpdate,
+                TrackStatusError,
+            },
+        },
+    },
+    bytes::BytesMut,
+    core::time::Duration,
+    dashmap::DashMap,
+    std::sync::{Arc, Mutex, OnceLock},
+    tokio::sync::{Notify, mpsc, oneshot, watch},
+    tracing::{debug, info, warn},
+    url::Url,
+    varint::{VarIntNumber, x},
+};
+
+/// Number of Objects buffered per Subscription.
+pub(super) const OBJECT_BUFFER: usize = 64;
+
+/// ## Routes
+///
+/// Hands the control messages, streams and
+/// datagrams received by the dispatcher of a
+/// [Client](super::Client) to whoever awaits them.
+pub(super) struct Routes {
+    /// Request IDs of outgoing Requests.
+    pub request_id: Mutex<RequestId>,
+
+    /// Notified whenever the peer grants new
+    /// Request IDs.
+    pub credit: Notify,
+
+    /// Request IDs of incoming Requests.
+    requests: Mutex<RequestWindow>,
+
+    /// Pending Requests by Request ID.
+    pub responses: DashMap<u64, oneshot::Sender<ControlMessage>>,
+
+    /// Subscriptions awaiting their SubscribeOk
+    /// by Request ID.
+    pub pending_tracks: DashMap<u64, mpsc::Sender<Object>>,
+
+    /// Active Subscriptions by Track Alias.
+    pub tracks: DashMap<u64, mpsc::Sender<Object>>,
+
+    /// Number of Subgroup streams received
+    /// completely by Track Alias.
+    pub drained: DashMap<u64, watch::Sender<u64>>,
+
+    /// Active Subscriptions by Request ID, to
+    /// re-issue them on a new session.
+    pub subscriptions: DashMap<u64, Resumable>,
+
+    /// Fetches awaiting their stream by Request ID.
+    pub fetches: DashMap<u64, oneshot::Sender<(RecvStream, BytesMut)>>,
+
+    /// Delivery of published Tracks by the
+    /// Request ID of their Publish.
+    pub publishes: DashMap<u64, watch::Sender<Delivery>>,
+
+    /// Namespace subscriptions by Request ID.
+    pub namespaces: DashMap<u64, (Namespace, mpsc::UnboundedSender<NamespaceEvent>)>,
+
+    /// Set once the peer sent a Goaway, with
+    /// its migration URL if any.
+    goaway: OnceLock<Option<Url>>,
+}
+
+impl Routes {
+    /// Creates the Routes of a Client the peer
+    /// granted Request IDs below `max_request_id`.
+    pub fn new(max_request_id: u64) -> Self {
+        let mut request_id = RequestId::new_client();
+        request_id.set_max(max_request_id);
+
+        Self {
+            request_id: Mutex::new(request_id),
+            credit: Notify::new(),
+            requests: Mutex::new(RequestWindow::new_server()),
+            responses: DashMap::new(),
+            pending_tracks: DashMap::new(),
+            tracks: DashMap::new(),
+            drained: DashMap::new(),
+            subscriptions: DashMap::new(),
+            fetches: DashMap::new(),
+            publishes: DashMap::new(),
+            namespaces: DashMap::new(),
+            goaway: OnceLock::new(),
+        }
+    }
+
+    /// Drops all routes, which ends every pending
+    /// Request and active Subscription.
+    pub fn close(&self) {
+        self.responses.clear();
+        self.pending_tracks.clear();
+        self.tracks.clear();
+        self.drained.clear();
+        self.subscriptions.clear();
+        self.fetches.clear();
+        self.publishes.clear();
+        self.namespaces.clear();
+        // wake everyone waiting for Request IDs
+        self.credit.notify_waiters();
+    }
+
+    /// Whether the peer sent a Goaway, after which
+    /// no new Requests are sent.
+    pub fn is_going_away(&self) -> bool {
+        self.goaway.get().is_some()
+    }
+
+    /// Accepts the first Goaway of the peer.
+    ///
+    /// Returns its migration URL or the code to
+    /// close the session with when the URL is
+    /// invalid or the peer sent a Goaway before.
+    fn go_away(&self, msg: &Goaway) -> Result<Option<Url>, Termination> {
+        let url = msg.migration_url().map_err(|err| {
+            warn!(%err, "received invalid goaway");
+            Termination::ProtocolViolation
+        })?;
+
+        self.goaway
+            .set(url.clone())
+            .map_err(|_| Termination::ProtocolViolation)?;
+        Ok(url)
+    }
+
+    /// Applies the Request ID flow control of `msg`.
+    ///
+    /// Returns the [MaxRequestId] to send, if any,
+    /// or the code to close the session with.
+    fn flow_control(&self, msg: &ControlMessage) -> Result<Option<MaxRequestId>, Termination> {
+        let mut requests = self.requests.lock().expect("request window lock poisoned");
+
+        match msg {
+            ControlMessage::MaxRequestId(msg) => {
+                let raised = self
+                    .request_id
+                    .lock()
+                    .expect("request ID lock poisoned")
+                    .set_max(msg.request_id.number());
+                if !raised {
+                    return Err(Termination::ProtocolViolation);
+                }
+                self.credit.notify_waiters();
+                Ok(None)
+            }
+            ControlMessage::RequestsBlocked(_) => Ok(requests.grant().map(MaxRequestId::new)),
+            msg if msg.is_request() => {
+                let request_id = msg
+                    .request_id()
+                    .map(VarIntNumber::number)
+                    .unwrap_or_default();
+                requests.accept(request_id)?;
+                Ok(requests.grant().map(MaxRequestId::new))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Applies `msg` to the published Track it
+    /// updates.
+    fn update_publish(&self, msg: &SubscribeUpdate) -> Result<(), &'static str> {
+        let request_id = msg.request_id.number::<u64>();
+        let Some(delivery) = self.publishes.get(&request_id) else {
+            debug!(request_id, "subscribe update for unknown track");
+            return Ok(());
+        };
+
+        let mut res = Ok(());
+        delivery.send_if_modified(|delivery| {
+            res = delivery.update(msg);
+            res.is_ok()
+        });
+        if res.is_ok() {
+            debug!(request_id, forward = ?msg.forward, "peer updated published track");
+        }
+        res
+    }
+
+    /// Routes `msg` to whoever awaits it.
+    ///
+    /// Returns the response to send, if any.
+    fn route_message(&self, msg: ControlMessage) -> Option<ControlMessage> {
+        match msg {
+            ControlMessage::PublishNamespace(msg) => {
+                let event = NamespaceEvent::Published(msg.namespace.clone());
+                let response = if self.route_namespace(&msg.namespace, event) {
+                    ControlMessage::PublishNamespaceOk(PublishNamespaceOk::new(msg.request_id))
+                } else {
+                    ControlMessage::PublishNamespaceError(PublishNamespaceError::new(
+                        msg.request_id,
+                        error_code::PublishNamespace::Uninterested,
+                        "no subscription to the namespace",
+                    ))
+                };
+                return Some(response);
+            }
+            // Tracks are only published with a Publish,
+            // their status is known to the peer
+            ControlMessage::TrackStatus(msg) => {
+                return Some(ControlMessage::TrackStatusError(TrackStatusError::new(
+                    msg.request_id,
+                    error_code::TrackStatus::NotSupported,
+                    "track status is not supported",
+                )));
+            }
+            // Objects are only sent on the Tracks published
+            // with a Publish, unanswered Requests would
+            // time out at the peer
+            ControlMessage::Subscribe(msg) => {
+                return Some(ControlMessage::SubscribeError(SubscribeError::new(
+                    msg.request_id,
+                    error_code::Subscribe::NotSupported,
+                    "subscribe is not supported",
+                )));
+            }
+            ControlMessage::Fetch(msg) => {
+                return Some(ControlMessage::FetchError(FetchError::new(
+                    msg.request_id,
+                    error_code::Fetch::NotSupported,
+                    "fetch is not supported",
+                )));
+            }
+            ControlMessage::Publish(msg) => {
+                return Some(ControlMessage::PublishError(PublishError::new(
+                    msg.request_id,
+                    error_code::Publish::NotSupported,
+                    "publish is not supported",
+                )));
+            }
+            ControlMessage::PublishNamespaceDone(msg) => {
+                let event = NamespaceEvent::Done(msg.namespace.clone());
+                self.route_namespace(&msg.namespace, event);
+                return None;
+            }
+            _ => (),
+        }
+
+        let Some(request_id) = msg.request_id().filter(|_| msg.is_response()) else {
+            debug!(key = msg.key(), "ignoring control message");
+            return None;
+        };
+        let request_id = request_id.number::<u64>();
+
+        if let Some((_, tx)) = self.pending_tracks.remove(&request_id)
+            && let ControlMessage::SubscribeOk(ok) = &msg
+        {
+            self.tracks.insert(ok.alias.number(), tx);
+        }
+
+        match self.responses.remove(&request_id) {
+            Some((_, tx)) => {
+                let _ = tx.send(msg);
+            }
+            None => warn!(request_id, "received response to unknown request"),
+        }
+        None
+    }
+
+    /// Sends `event` to every Namespace subscription
+    /// whose prefix `namespace` starts with.
+    ///
+    /// Returns whether there was any.
+    fn route_namespace(&self, namespace: &Namespace, event: NamespaceEvent) -> bool {
+        let mut routed = false;
+        for entry in self.namespaces.iter() {
+            let (prefix, tx) = entry.value();
+            if namespace.starts_with(prefix) {
+                routed |= tx.send(event.clone()).is_ok();
+            }
+        }
+        routed
+    }
+
+    /// Receives the number of Subgroup streams
+    /// of the Track with `alias` received
+    /// completely.
+    fn drained(&self, alias: u64) -> watch::Receiver<u64> {
+        self.drained
+            .entry(alias)
+            .or_insert_with(|| watch::Sender::new(0))
+            .subscribe()
+    }
+
+    /// Counts a Subgroup stream of the Track with
+    /// `alias` that was received completely.
+    fn stream_drained(&self, alias: u64) {
+        if !self.tracks.contains_key(&alias) {
+            return;
+        }
+        self.drained
+            .entry(alias)
+            .or_insert_with(|| watch::Sender::new(0))
+            .send_modify(|drained| *drained += 1);
+    }
+
+    fn route_datagram(&self, datagram: ObjectDatagram, unrouted: &mpsc::Sender<ObjectDatagram>) {
+        let alias = datagram.track_alias.number::<u64>();
+
+        // datagrams are unreliable anyway, so they are
+        // dropped instead of blocking the dispatcher
+        let sent = match self.tracks.get(&alias) {
+            Some(tx) => tx.try_send(Object::from(datagram)).is_ok(),
+            None => unrouted.try_send(datagram).is_ok(),
+        };
+        if !sent {
+            debug!(alias, "dropped datagram");
+        }
+    }
+}
+
+/// Receives everything the peer sends outside of
+/// explicit Requests and routes it via `routes`.
+///
+/// Returns once the connection is closed.
+pub(super) async fn dispatch(
+    transport: Arc<Connection>,
+    control_stream: Arc<ControlStream>,
+    routes: Arc<Routes>,
+    datagrams: mpsc::Sender<ObjectDatagram>,
+    goaway: watch::Sender<Option<Url>>,
+    data_stream_timeout: Duration,
+) {
+    loop {
+        tokio::select! {
+            msg = control_stream.recv() => match msg {
+                Ok(ControlMessage::GoAway(msg)) => match routes.go_away(&msg) {
+                    Ok(url) => {
+                        info!(?url, "peer asked to go away");
+                        goaway.send_replace(url);
+                    }
+                    Err(code) => {
+                        warn!(?code, "peer sent an unexpected goaway");
+                        transport.close(code);
+                        break;
+                    }
+                },
+                Ok(ControlMessage::PublishDone(msg)) => {
+                    tokio::spawn(complete(routes.clone(), msg, data_stream_timeout));
+                }
+                Ok(ControlMessage::SubscribeUpdate(msg)) => {
+                    if let Err(reason) = routes.update_publish(&msg) {
+                        warn!(reason, "peer widened a published track");
+                        transport.close(Termination::ProtocolViolation);
+                        break;
+                    }
+                }
+                Ok(msg) => {
+                    match routes.flow_control(&msg) {
+                        Ok(None) => (),
+                        Ok(Some(max)) => {
+                            if let Err(err) = control_stream.send(ControlMessage::MaxRequestId(max)).await {
+                                debug!(%err, "control stream closed");
+                                break;
+                            }
+                        }
+                        Err(code) => {
+                            warn!(?code, key = msg.key(), "peer violated request flow control");
+                            transport.close(code);
+                            break;
+                        }
+                    }
+                    if let Some(response) = routes.route_message(msg)
+                        && let Err(err) = control_stream.send(response).await
+                    {
+                        debug!(%err, "control stream closed");
+                        break;
+                    }
+                }
+                Err(err) if err.is_malformed() => {
+                    warn!(%err, "peer sent a malformed control message");
+                    transport.close(Termination::ProtocolViolation);
+                    break;
+                }
+                Err(err) => {
+                    debug!(%err, "control stream closed");
+                    break;
+                }
+            },
+            uni = transport.accept_uni() => match uni {
+                Ok(stream) => {
+                    tokio::spawn(route_stream(
+                        stream,
+                        routes.clone(),
+                        transport.clone(),
+                        data_stream_timeout,
+                    ));
+                }
+                Err(err) => {
+                    debug!(%err, "connection closed");
+                    break;
+                }
+            },
+            datagram = transport.recv_object_datagram() => match datagram {
+                Ok(datagram) => routes.route_datagram(datagram, &datagrams),
+                Err(err @ DatagramError::Decode { .. }) => warn!(%err, "dropped malformed datagram"),
+                Err(err) => {
+                    debug!(%err, "connection closed");
+                    break;
+                }
+            },
+        }
+    }
+
+    routes.close();
+}
+
+/// Ends the Subscription the [PublishDone] `msg`
+/// is for once all streams it counts were
+/// received, or after `timeout`.
+async fn complete(routes: Arc<Routes>, msg: PublishDone, timeout: Duration) {
+    let request_id = msg.request_id.number::<u64>();
+    let alias = routes
+        .subscriptions
+        .get(&request_id)
+        .and_then(|subscription| subscription.active.upgrade())
+        .map(|active| {
+            let active = active.read().expect("subscription lock poisoned");
+            active.ok.alias.number::<u64>()
+        });
+    let Some(alias) = alias else {
+        debug!(request_id, "publish done for unknown subscription");
+        return;
+    };
+
+    let stream_count = msg.stream_count.number::<u64>();
+    let mut drained = routes.drained(alias);
+    let all = drained.wait_for(|drained| *drained >= stream_count);
+    if tokio::time::timeout(timeout, all).await.is_err() {
+        warn!(
+            request_id,
+            stream_count,
+            ?timeout,
+            "streams did not arrive before the publish done"
+        );
+    }
+
+    debug!(request_id, code = ?msg.code, "subscription done");
+    routes.tracks.remove(&alias);
+    routes.drained.remove(&alias);
+    // the Objects end once its last sender is gone
+    if let Some((_, subscription)) = routes.subscriptions.remove(&request_id) {
+        let _ = subscription.done.set(msg);
+    }
+}
+
+/// An incoming unidirectional stream after
+/// its header was received.
+enum DataStream {
+    /// The stream of the Fetch with this
+    /// Request ID.
+    Fetch(u64, RecvStream, BytesMut),
+    Subgroup(Box<SubgroupReader>),
+}
+
+/// Routes an incoming unidirectional stream by
+/// its header type.
+///
+/// Closes the session with
+/// [DataStreamTimeout](Termination::DataStreamTimeout)
+/// unless the header arrives within `timeout`.
+async fn route_stream(
+    stream: RecvStream,
+    routes: Arc<Routes>,
+    transport: Arc<Connection>,
+    timeout: Duration,
+) {
+    let stream = match tokio::time::timeout(timeout, read_header(stream)).await {
+        Ok(Some(stream)) => stream,
+        Ok(None) => return,
+        Err(_) => {
+            warn!(?timeout, "peer took too long to send the stream header");
+            transport.close(Termination::DataStreamTimeout);
+            return;
+        }
+    };
+
+    let mut reader = match stream {
+        DataStream::Fetch(request_id, stream, buf) => {
+            match routes.fetches.remove(&request_id) {
+                Some((_, tx)) => {
+                    let _ = tx.send((stream, buf));
+                }
+                None => warn!(request_id, "received fetch stream for unknown request"),
+            }
+            return;
+        }
+        DataStream::Subgroup(reader) => *reader,
+    };
+
+    let alias = reader.header().track_alias.number::<u64>();
+    let Some(tx) = routes.tracks.get(&alias).map(|tx| tx.clone()) else {
+        debug!(alias, "received subgroup stream for unknown track");
+        return;
+    };
+
+    loop {
+        match reader.next().await {
+            Ok(Some(object)) => {
+                if tx.send(object).await.is_err() {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                warn!(%err, alias, "failed to read object");
+                break;
+            }
+        }
+    }
+    routes.stream_drained(alias);
+}
+
+/// Reads the header of `stream`.
+///
+/// None if the stream ended or is malformed.
+async fn read_header(mut stream: RecvStream) -> Option<DataStream> {
+    let mut buf = BytesMut::new();
+
+    let typ = match stream.peek_buffered::<x!(i)>(&mut buf).await {
+        Ok(typ) => typ?,
+        Err(err) => {
+            warn!(%err, "failed to read stream type");
+            return None;
+        }
+    };
+
+    if typ == FetchHeaderType::Fetch.key() {
+        let header = match stream.peek_buffered::<FetchHeader>(&mut buf).await {
+            Ok(header) => header?,
+            Err(err) => {
+                warn!(%err, "failed to read fetch header");
+                return None;
+            }
+        };
+        return Some(DataStream::Fetch(header.request_id.number(), stream, buf));
+    }
+
+    match SubgroupReader::new(stream, buf).await {
+        Ok(reader) => Some(DataStream::Subgroup(Box::new(reader))),
+        Err(err) => {
+            warn!(%err, "failed to read subgroup header");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::types::{
+            GroupOrder, Location,
+            message::{Publish, PublishOk, Subscribe},
+        },
+    };
+
+    #[test]
+    fn go_away_test() {
+        let routes = Routes::new(0);
+        assert!(!routes.is_going_away());
+
+        let invalid = Goaway::new("not a url");
+        assert_eq!(
+            routes.go_away(&invalid),
+            Err(Termination::ProtocolViolation)
+        );
+        assert!(!routes.is_going_away());
+
+        let url = Url::parse("https://relay.example.com/moq").unwrap();
+        let goaway = Goaway::with_url(Some(&url)).unwrap();
+        assert_eq!(routes.go_away(&goaway), Ok(Some(url)));
+        assert!(routes.is_going_away());
+
+        // only a single Goaway is allowed
+        let goaway = Goaway::with_url(None).unwrap();
+        assert_eq!(routes.go_away(&goaway), Err(Termination::ProtocolViolation));
+    }
+
+    #[test]
+    fn unsupported_request_test() {
+        let routes = Routes::new(0);
+
+        let subscribe = Subscribe::builder()
+            .id(1u8)
+            .namespace(["chat"])
+            .name("text")
+            .sub_prio(0)
+            .group_order(GroupOrder::Ascending)
+            .forward(true)
+            .with_largest_object()
+            .build();
+        assert_eq!(
+            routes.route_message(ControlMessage::Subscribe(subscribe)),
+            Some(ControlMessage::SubscribeError(SubscribeError::new(
+                1u8,
+                error_code::Subscribe::NotSupported,
+                "subscribe is not supported",
+            )))
+        );
+
+        let publish = Publish::builder()
+            .id(3u8)
+            .namespace(["chat"])
+            .name("text")
+            .alias(0u8)
+            .group_order(GroupOrder::Ascending)
+            .forward(true)
+            .build();
+        assert!(matches!(
+            routes.route_message(ControlMessage::Publish(publish)),
+            Some(ControlMessage::PublishError(PublishError {
+                code: error_code::Publish::NotSupported,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn update_publish_test() {
+        let routes = Routes::new(0);
+        let ok = PublishOk::builder()
+            .id(1u8)
+            .forward(true)
+            .sub_prio(0)
+            .group_order(GroupOrder::Ascending)
+            .with_absolute_range(Location::new(0u8, 0u8), 3u8)
+            .build();
+        let (tx, delivery) = watch::channel(Delivery::new(&ok));
+        routes.publishes.insert(1, tx);
+        let update = |group: u8, object: u8, end_group: u8| {
+            SubscribeUpdate::builder()
+                .id(1u8)
+                .start(group, object)
+                .end_group(end_group)
+                .subscriber_priority(7)
+                .forward(true)
+                .build()
+        };
+
+        // ends after Group 2
+        assert_eq!(routes.update_publish(&update(1, 2, 3)), Ok(()));
+        assert_eq!(delivery.borrow().priority, 7);
+        assert!(!delivery.borrow().accepts(1, 1));
+        assert!(delivery.borrow().accepts(1, 2));
+        assert!(delivery.borrow().accepts(2, 0));
+        assert!(!delivery.borrow().accepts(3, 0));
+
+        assert!(routes.update_publish(&update(1, 1, 3)).is_err());
+        assert!(routes.update_publish(&update(1, 2, 4)).is_err());
+        assert!(routes.update_publish(&update(1, 2, 0)).is_err());
+        assert!(delivery.borrow().accepts(1, 2));
+
+        // unknown Tracks are ignored
+        let unknown = SubscribeUpdate::builder()
+            .id(5u8)
+            .start(0u8, 0u8)
+            .end_group(0u8)
+            .subscriber_priority(0)
+            .forward(true)
+            .build();
+        assert_eq!(routes.update_publish(&unknown), Ok(()));
+    }
+}
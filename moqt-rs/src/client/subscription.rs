@@ -0,0 +1,357 @@
+use {
+    super::{ClientError, ctx, routes::Routes},
+    crate::{
+        ControlStream, Timeouts,
+        types::{
+            FilterType, Location,
+            data::Object,
+            message::{
+                ControlMessage, PublishDone, Subscribe, SubscribeOk, SubscribeUpdate, Unsubscribe,
+            },
+        },
+    },
+    core::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+    snafu::{OptionExt, ResultExt, ensure},
+    std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard, Weak},
+    tokio::{runtime::Handle, sync::mpsc, time::Instant},
+    tracing::{trace, warn},
+    varint::{VarIntNumber, x},
+};
+
+/// ## Subscription
+///
+/// An active Subscription to a Track, created by
+/// [Client::subscribe](super::Client::subscribe).
+///
+/// Yields the Objects of the Track as a [Stream].
+/// Dropping the Subscription unsubscribes from
+/// the Track.
+///
+/// When the Client migrates to a new session,
+/// the Subscription is re-issued on it and keeps
+/// yielding Objects.
+///
+/// Once the publisher ended the Subscription and
+/// all its streams were received, it yields no
+/// more Objects and [done](Self::done) reports
+/// the final status.
+pub struct Subscription {
+    link: Arc<RwLock<Active>>,
+    objects: mpsc::Receiver<Object>,
+    done: Arc<OnceLock<PublishDone>>,
+    active: bool,
+}
+
+/// The session a [Subscription] is active on.
+pub(super) struct Active {
+    pub ok: SubscribeOk,
+    pub control_stream: Arc<ControlStream>,
+    pub routes: Arc<Routes>,
+}
+
+/// ## Resumable Subscription
+///
+/// Everything it takes to re-issue a
+/// [Subscription] on a new session.
+#[derive(Clone)]
+pub(super) struct Resumable {
+    pub subscribe: Subscribe,
+    pub objects: mpsc::Sender<Object>,
+    /// Gone once the Subscription is dropped.
+    pub active: Weak<RwLock<Active>>,
+    /// Set once the publisher ended it.
+    pub done: Arc<OnceLock<PublishDone>>,
+}
+
+impl Subscription {
+    pub(super) fn new(
+        link: Arc<RwLock<Active>>,
+        objects: mpsc::Receiver<Object>,
+        done: Arc<OnceLock<PublishDone>>,
+    ) -> Self {
+        Self {
+            link,
+            objects,
+            done,
+            active: true,
+        }
+    }
+
+    /// The Request ID of this Subscription.
+    pub fn request_id(&self) -> x!(i) {
+        self.link().ok.request_id.clone()
+    }
+
+    /// The Track Alias the publisher assigned to
+    /// this Subscription.
+    pub fn track_alias(&self) -> x!(i) {
+        self.link().ok.alias.clone()
+    }
+
+    /// The [SubscribeOk] the publisher accepted
+    /// this Subscription with.
+    pub fn subscribe_ok(&self) -> SubscribeOk {
+        self.link().ok.clone()
+    }
+
+    /// The [PublishDone] the publisher ended this
+    /// Subscription with, once all its streams
+    /// were received.
+    pub fn done(&self) -> Option<&PublishDone> {
+        self.done.get()
+    }
+
+    /// Receives the next Object.
+    ///
+    /// Yields None once the Subscription has ended.
+    pub async fn recv(&mut self) -> Option<Object> {
+        self.objects.recv().await
+    }
+
+    /// Narrows this Subscription to start at `start_group`
+    /// and `start_object` and to end with `end_group`,
+    /// or to stay open-ended without.
+    ///
+    /// Also sets a new `subscriber_priority` and
+    /// pauses or resumes forwarding with `forward`.
+    ///
+    /// Widening the Subscription is a protocol
+    /// violation that closes the session.
+    pub async fn update<G, O, E>(
+        &self,
+        start_group: G,
+        start_object: O,
+        end_group: Option<E>,
+        subscriber_priority: u8,
+        forward: bool,
+    ) -> Result<(), ClientError>
+    where
+        G: Into<x!(i)>,
+        O: Into<x!(i)>,
+        E: Into<x!(i)>,
+    {
+        let (request_id, control_stream, routes) = {
+            let link = self.link();
+            (
+                link.ok.request_id.clone(),
+                link.control_stream.clone(),
+                link.routes.clone(),
+            )
+        };
+        // the end Group is sent plus 1, 0 is open-ended
+        let end_group = match end_group {
+            Some(end_group) => end_group
+                .into()
+                .number::<u64>()
+                .checked_add(1)
+                .and_then(|end_group| <x!(i)>::try_from(end_group).ok())
+                .context(ctx::IdOverflowSnafu)?,
+            None => 0u8.into(),
+        };
+        let msg = SubscribeUpdate::builder()
+            .id(request_id)
+            .start(start_group, start_object)
+            .end_group(end_group)
+            .subscriber_priority(subscriber_priority)
+            .forward(forward)
+            .build();
+
+        send_update(&routes, &control_stream, msg).await
+    }
+
+    /// Ends this Subscription.
+    pub async fn unsubscribe(mut self) -> Result<(), ClientError> {
+        self.active = false;
+        let (msg, control_stream) = self.remove_routes();
+        if self.done().is_some() {
+            return Ok(());
+        }
+
+        control_stream
+            .send(msg)
+            .await
+            .context(ctx::ControlStreamSnafu)
+    }
+
+    /// Removes the routes of this Subscription and
+    /// returns the Unsubscribe to send.
+    fn remove_routes(&self) -> (ControlMessage, Arc<ControlStream>) {
+        let link = self.link();
+        let alias = link.ok.alias.number::<u64>();
+        link.routes.tracks.remove(&alias);
+        link.routes.drained.remove(&alias);
+        link.routes
+            .subscriptions
+            .remove(&link.ok.request_id.number::<u64>());
+
+        let msg = ControlMessage::Unsubscribe(Unsubscribe::new(link.ok.request_id.clone()));
+        (msg, link.control_stream.clone())
+    }
+
+    fn link(&self) -> RwLockReadGuard<'_, Active> {
+        self.link.read().expect("subscription lock poisoned")
+    }
+}
+
+impl Resumable {
+    /// The [SubscribeUpdate] refreshing the
+    /// Subscription with `request_id` without
+    /// changing it.
+    ///
+    /// Relative filters start after the `largest`
+    /// Location of its [SubscribeOk].
+    fn refresh(&self, request_id: x!(i), largest: Option<&Location>) -> Option<SubscribeUpdate> {
+        let subscribe = &self.subscribe;
+        let start = subscribe
+            .filter_type
+            .resolve_start(subscribe.start_location.as_ref(), largest)?;
+        // the end Group is sent plus 1, 0 is open-ended
+        let end_group = subscribe
+            .end_group
+            .as_ref()
+            .and_then(|end_group| end_group.number::<u64>().checked_add(1))
+            .and_then(|end_group| <x!(i)>::try_from(end_group).ok())
+            .unwrap_or_else(|| 0u8.into());
+
+        Some(SubscribeUpdate {
+            request_id,
+            start_location: start,
+            end_group,
+            subscriber_priority: subscribe.subscriber_priority.clone(),
+            forward: subscribe.forward,
+            parameters: Default::default(),
+        })
+    }
+
+    /// Applies `update` to the Subscribe
+    /// re-issued on a new session.
+    fn narrow(&mut self, update: &SubscribeUpdate) {
+        let subscribe = &mut self.subscribe;
+        subscribe.subscriber_priority = update.subscriber_priority.clone();
+        subscribe.forward = update.forward;
+        subscribe.start_location = Some(update.start_location.clone());
+
+        let end_group = update
+            .end_group
+            .number::<u64>()
+            .checked_sub(1)
+            .and_then(|end_group| <x!(i)>::try_from(end_group).ok());
+        subscribe.filter_type = match end_group {
+            Some(_) => FilterType::AbsoluteRange,
+            None => FilterType::AbsoluteStart,
+        };
+        subscribe.end_group = end_group;
+    }
+}
+
+/// Sends `update` for the active Subscription
+/// with its Request ID, which is re-issued with
+/// the update applied on a new session once it
+/// was sent.
+pub(super) async fn send_update(
+    routes: &Routes,
+    control_stream: &ControlStream,
+    update: SubscribeUpdate,
+) -> Result<(), ClientError> {
+    let request_id = update.request_id.number::<u64>();
+    ensure!(
+        routes.subscriptions.contains_key(&request_id),
+        ctx::UnknownSubscriptionSnafu { request_id }
+    );
+
+    control_stream
+        .send(ControlMessage::SubscribeUpdate(update.clone()))
+        .await
+        .context(ctx::ControlStreamSnafu)?;
+    if let Some(mut subscription) = routes.subscriptions.get_mut(&request_id) {
+        subscription.narrow(&update);
+    }
+    Ok(())
+}
+
+/// Refreshes the Subscription `active` before it
+/// expires, as given by its [SubscribeOk], with
+/// the refresh margin of `timeouts`.
+///
+/// Stops once the Subscription is dropped, ended
+/// or re-issued on a new session.
+pub(super) fn keep_alive(active: &Arc<RwLock<Active>>, timeouts: &Timeouts) {
+    let (request_id, routes, expires, largest) = {
+        let link = active.read().expect("subscription lock poisoned");
+        (
+            link.ok.request_id.clone(),
+            link.routes.clone(),
+            link.ok.expires,
+            link.ok.largest_location.clone(),
+        )
+    };
+    let Some(period) = timeouts.refresh_after(expires) else {
+        return;
+    };
+
+    let active = Arc::downgrade(active);
+    tokio::spawn(async move {
+        let mut refresh = tokio::time::interval_at(Instant::now() + period, period);
+        loop {
+            refresh.tick().await;
+            let Some(control_stream) = active.upgrade().and_then(|active| {
+                let link = active.read().expect("subscription lock poisoned");
+                let current = Arc::ptr_eq(&link.routes, &routes);
+                current.then(|| link.control_stream.clone())
+            }) else {
+                return;
+            };
+            let Some(update) = routes
+                .subscriptions
+                .get(&request_id.number::<u64>())
+                .and_then(|subscription| {
+                    subscription.refresh(request_id.clone(), largest.as_ref())
+                })
+            else {
+                return;
+            };
+
+            trace!(?request_id, "refreshing subscription");
+            let msg = ControlMessage::SubscribeUpdate(update);
+            if let Err(err) = control_stream.send(msg).await {
+                warn!(%err, ?request_id, "failed to refresh subscription");
+                return;
+            }
+        }
+    });
+}
+
+impl Stream for Subscription {
+    type Item = Object;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.objects.poll_recv(cx)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        let (msg, control_stream) = self.remove_routes();
+        if self.done().is_some() {
+            return;
+        }
+
+        let Ok(runtime) = Handle::try_current() else {
+            return;
+        };
+
+        runtime.spawn(async move {
+            if let Err(err) = control_stream.send(msg).await {
+                warn!(%err, "failed to unsubscribe");
+            }
+        });
+    }
+}
@@ -0,0 +1,171 @@
+use {
+    super::{ClientError, ctx},
+    crate::ALPN,
+    rustls::{
+        DigitallySignedStruct, RootCertStore, SignatureScheme,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{self, CryptoProvider},
+        pki_types::{CertificateDer, ServerName, UnixTime, pem::PemObject},
+    },
+    std::{net::IpAddr, path::PathBuf, sync::Arc},
+};
+
+/// ## Server Certificate Verification
+///
+/// How a native QUIC [Client](super::Client)
+/// verifies the certificate of the relay.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TlsVerification {
+    /// Trust the root certificates of the
+    /// operating system.
+    #[default]
+    NativeRoots,
+
+    /// Trust the certificates of a PEM file,
+    /// e.g. a custom CA.
+    CaFile(PathBuf),
+
+    /// Accept any certificate, including self
+    /// signed ones, of a relay on localhost.
+    ///
+    /// Only meant for local development!
+    InsecureLocalhost,
+}
+
+impl TlsVerification {
+    /// Builds the QUIC client configuration
+    /// negotiating the MOQT [ALPN].
+    pub(crate) fn quic_config(&self) -> Result<quic::ClientConfig, ClientError> {
+        let provider = Arc::new(crypto::ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|err| ClientError::Tls {
+                msg: err.to_string(),
+            })?;
+
+        let mut config = match self {
+            Self::NativeRoots => {
+                let native = rustls_native_certs::load_native_certs();
+                let mut roots = RootCertStore::empty();
+                let (added, _) = roots.add_parsable_certificates(native.certs);
+                snafu::ensure!(
+                    added > 0,
+                    ctx::TlsSnafu {
+                        msg: "no native root certificates found"
+                    }
+                );
+                builder.with_root_certificates(roots)
+            }
+            Self::CaFile(path) => {
+                let mut roots = RootCertStore::empty();
+                let certs = CertificateDer::pem_file_iter(path)
+                    .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                    .map_err(|err| ClientError::Tls {
+                        msg: format!("failed to read {}: {err}", path.display()),
+                    })?;
+                for cert in certs {
+                    roots.add(cert).map_err(|err| ClientError::Tls {
+                        msg: err.to_string(),
+                    })?;
+                }
+                builder.with_root_certificates(roots)
+            }
+            Self::InsecureLocalhost => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(LocalhostVerifier { provider })),
+        }
+        .with_no_client_auth();
+        config.alpn_protocols = vec![ALPN.to_vec()];
+
+        let crypto = quic::crypto::rustls::QuicClientConfig::try_from(config)
+            .expect("CipherSuite::TLS13_AES_128_GCM_SHA256 missing");
+        Ok(quic::ClientConfig::new(Arc::new(crypto)))
+    }
+}
+
+/// Accepts any certificate for localhost while
+/// still checking the handshake signatures.
+#[derive(Debug)]
+struct LocalhostVerifier {
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for LocalhostVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if is_localhost(server_name) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "refusing to skip verification for {}",
+                server_name.to_str()
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+fn is_localhost(server_name: &ServerName<'_>) -> bool {
+    match server_name {
+        ServerName::DnsName(name) => name.as_ref().eq_ignore_ascii_case("localhost"),
+        ServerName::IpAddress(addr) => IpAddr::from(*addr).is_loopback(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_localhost_test() {
+        for name in ["localhost", "LocalHost", "127.0.0.1", "::1"] {
+            let name = ServerName::try_from(name).unwrap();
+            assert!(is_localhost(&name), "{name:?}");
+        }
+
+        for name in ["example.com", "10.0.0.1", "localhost.example.com"] {
+            let name = ServerName::try_from(name).unwrap();
+            assert!(!is_localhost(&name), "{name:?}");
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use {
+    super::{ClientError, DatagramPublisher, ctx, routes::Routes},
+    crate::{
+        ControlStream, SubgroupWriter,
+        transport::Connection,
+        types::{
+            Forward, Location,
+            data::{ObjectPayload, ObjectStatus, SubgroupHeader},
+            error_code,
+            message::{ControlMessage, PublishDone, PublishOk, SubscribeUpdate},
+        },
+    },
+    snafu::{OptionExt, ResultExt},
+    std::sync::Arc,
+    tokio::{runtime::Handle, sync::watch},
+    tracing::{trace, warn},
+    varint::{VarIntNumber, x},
+};
+
+/// ## Track Publisher
+///
+/// Publishes the Objects of a Track accepted by
+/// [Client::publish](super::Client::publish).
+///
+/// Every Group is sent on its own Subgroup
+/// stream, Objects are numbered consecutively
+/// starting at 0 within each Group.
+///
+/// While the peer disabled forwarding in its
+/// [PublishOk] or a later [SubscribeUpdate],
+/// or for Objects outside the range it asked
+/// for, Group and Object IDs advance as usual
+/// but nothing is sent.
+///
+/// Finishing or dropping it ends the Track with
+/// a [PublishDone].
+pub struct TrackPublisher {
+    ok: PublishOk,
+    track_alias: x!(i),
+    publisher_priority: u8,
+    transport: Arc<Connection>,
+    control_stream: Arc<ControlStream>,
+    routes: Arc<Routes>,
+    delivery: watch::Receiver<Delivery>,
+    group: Option<u64>,
+    next_object: u64,
+    writer: Option<SubgroupWriter>,
+    /// Number of Subgroup streams opened so far.
+    streams: u64,
+    active: bool,
+}
+
+impl TrackPublisher {
+    pub(super) fn new(
+        ok: PublishOk,
+        track_alias: x!(i),
+        publisher_priority: u8,
+        transport: Arc<Connection>,
+        control_stream: Arc<ControlStream>,
+        routes: Arc<Routes>,
+    ) -> Self {
+        let (tx, delivery) = watch::channel(Delivery::new(&ok));
+        routes.publishes.insert(ok.request_id.number(), tx);
+
+        Self {
+            ok,
+            track_alias,
+            publisher_priority,
+            transport,
+            control_stream,
+            routes,
+            delivery,
+            group: None,
+            next_object: 0,
+            writer: None,
+            streams: 0,
+            active: true,
+        }
+    }
+
+    /// The Request ID of the
+    /// [Publish](crate::types::message::Publish).
+    pub fn request_id(&self) -> &x!(i) {
+        &self.ok.request_id
+    }
+
+    /// The Track Alias of the published Track.
+    pub fn track_alias(&self) -> &x!(i) {
+        &self.track_alias
+    }
+
+    /// The [PublishOk] the peer accepted the
+    /// Track with.
+    pub fn publish_ok(&self) -> &PublishOk {
+        &self.ok
+    }
+
+    /// Whether Objects are sent to the peer.
+    pub fn is_forwarding(&self) -> bool {
+        self.delivery.borrow().forward == Forward::Enabled
+    }
+
+    /// The Subscriber Priority the peer asked for
+    /// last.
+    pub fn subscriber_priority(&self) -> u8 {
+        self.delivery.borrow().priority
+    }
+
+    /// Whether the Object at `group` and `object`
+    /// is sent to the peer.
+    fn sends(&self, group: u64, object: u64) -> bool {
+        self.delivery.borrow().accepts(group, object)
+    }
+
+    /// Creates a [DatagramPublisher] which delivers
+    /// Objects of this Track as datagrams instead
+    /// of on Subgroup streams.
+    ///
+    /// The Group and Object IDs of those Objects
+    /// are chosen by the caller.
+    pub fn datagram_publisher(&self) -> DatagramPublisher {
+        DatagramPublisher::new(
+            self.transport.clone(),
+            self.track_alias.clone(),
+            self.publisher_priority,
+            self.delivery.clone(),
+        )
+    }
+
+    /// Ends the current Group and starts the next
+    /// one on a new Subgroup stream.
+    ///
+    /// Returns the ID of the new Group.
+    pub async fn new_group(&mut self) -> Result<u64, ClientError> {
+        if let Some(writer) = self.writer.take() {
+            writer.finish().await.context(ctx::SubgroupSnafu)?;
+        }
+
+        let group = match self.group {
+            Some(group) => group.checked_add(1).context(ctx::IdOverflowSnafu)?,
+            None => 0,
+        };
+        self.group = Some(group);
+        self.next_object = 0;
+
+        if self.sends(group, 0) {
+            self.open_writer(group).await?;
+        }
+
+        trace!(group, alias = ?self.track_alias, "started group");
+
+        Ok(group)
+    }
+
+    /// Sends the next Object of the current Group.
+    ///
+    /// Starts the first Group if none was started
+    /// yet. Returns the ID of the Object.
+    pub async fn write_object<P>(&mut self, payload: P) -> Result<u64, ClientError>
+    where
+        P: Into<ObjectPayload>,
+    {
+        if self.group.is_none() {
+            self.new_group().await?;
+        }
+
+        let object = self.next_object;
+        let object_id = <x!(i)>::try_from(object)
+            .ok()
+            .context(ctx::IdOverflowSnafu)?;
+
+        // the peer may pause, resume or narrow
+        // forwarding in the middle of a Group
+        let group = self.group.expect("a group was started");
+        match (self.sends(group, object), &self.writer) {
+            (true, None) => {
+                self.open_writer(group).await?;
+            }
+            (false, Some(_)) => {
+                let writer = self.writer.take().expect("writer exists");
+                writer.finish().await.context(ctx::SubgroupSnafu)?;
+            }
+            _ => (),
+        }
+
+        if let Some(writer) = &mut self.writer {
+            writer
+                .write(object_id, payload)
+                .await
+                .context(ctx::SubgroupSnafu)?;
+        }
+
+        self.next_object = object.checked_add(1).context(ctx::IdOverflowSnafu)?;
+
+        Ok(object)
+    }
+
+    /// Opens the Subgroup stream for `group`.
+    async fn open_writer(&mut self, group: u64) -> Result<(), ClientError> {
+        let group_id = <x!(i)>::try_from(group)
+            .ok()
+            .context(ctx::IdOverflowSnafu)?;
+        let header = SubgroupHeader::builder()
+            .alias(self.track_alias.clone())
+            .group(group_id)
+            .with_zero_subgroup()
+            .priority(self.publisher_priority)
+            .build();
+
+        self.writer = Some(
+            SubgroupWriter::open(&self.transport, header)
+                .await
+                .context(ctx::SubgroupSnafu)?,
+        );
+        self.streams += 1;
+        Ok(())
+    }
+
+    /// The [PublishDone] ending the Track.
+    fn publish_done(&self) -> ControlMessage {
+        let stream_count = <x!(i)>::try_from(self.streams).expect("stream count fits a varint");
+        ControlMessage::PublishDone(PublishDone::new(
+            self.ok.request_id.clone(),
+            error_code::PublishDone::TrackEnded,
+            stream_count,
+            "the track ended",
+        ))
+    }
+
+    /// Ends the Track.
+    ///
+    /// Sends an Object with [ObjectStatus::EndOfTrack],
+    /// finishes the current Subgroup stream and
+    /// sends a [PublishDone].
+    pub async fn finish(mut self) -> Result<(), ClientError> {
+        self.write_object(ObjectStatus::EndOfTrack).await?;
+
+        if let Some(writer) = self.writer.take() {
+            writer.finish().await.context(ctx::SubgroupSnafu)?;
+        }
+
+        self.active = false;
+        self.control_stream
+            .send(self.publish_done())
+            .await
+            .context(ctx::ControlStreamSnafu)
+    }
+}
+
+impl Drop for TrackPublisher {
+    fn drop(&mut self) {
+        self.routes
+            .publishes
+            .remove(&self.ok.request_id.number::<u64>());
+        if !self.active {
+            return;
+        }
+
+        let Ok(runtime) = Handle::try_current() else {
+            return;
+        };
+
+        let control_stream = self.control_stream.clone();
+        let msg = self.publish_done();
+        runtime.spawn(async move {
+            if let Err(err) = control_stream.send(msg).await {
+                warn!(%err, "failed to end track");
+            }
+        });
+    }
+}
+
+/// ## Delivery
+///
+/// Which Objects of a published Track the peer
+/// asked for, as set by its [PublishOk] and
+/// narrowed by any [SubscribeUpdate].
+#[derive(Debug, Clone)]
+pub(super) struct Delivery {
+    /// Whether Objects are forwarded.
+    pub forward: Forward,
+    /// None while starting at the next Object.
+    pub start: Option<Location>,
+    /// None while open-ended.
+    pub end_group: Option<u64>,
+    /// The Subscriber Priority.
+    pub priority: u8,
+}
+
+impl Delivery {
+    /// The Delivery the peer accepted the Track
+    /// with in `ok`.
+    pub fn new(ok: &PublishOk) -> Self {
+        Self {
+            forward: ok.forward,
+            start: ok.start_location.clone(),
+            end_group: ok.end_group.as_ref().map(VarIntNumber::number),
+            priority: ok.subscriber_priority.number(),
+        }
+    }
+
+    /// Whether the Object at `group` and `object`
+    /// is forwarded.
+    pub fn accepts(&self, group: u64, object: u64) -> bool {
+        self.forward == Forward::Enabled
+            && self.start.as_ref().is_none_or(|start| {
+                (start.group::<u64>(), start.object::<u64>()) <= (group, object)
+            })
+            && self.end_group.is_none_or(|end_group| group <= end_group)
+    }
+
+    /// Narrows the Delivery with `update`, as
+    /// checked by [SubscribeUpdate::narrows].
+    pub fn update(&mut self, update: &SubscribeUpdate) -> Result<(), &'static str> {
+        update.narrows(self.start.as_ref(), self.end_group)?;
+
+        self.start = Some(update.start_location.clone());
+        self.end_group = update.final_group();
+        self.priority = update.subscriber_priority.number();
+        self.forward = update.forward;
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
+use core::time::Duration;
+
 use snafu::Snafu;
 
-use crate::transport::error::{ConnectionError, DecodeError, EncodeError, RecvError, SendError};
+use crate::transport::error::{ConnectionError, DecodeError, EncodeError};
 
 #[derive(Debug, Snafu, Clone, PartialEq, Eq)]
 #[snafu(visibility(pub), module(ctx))]
@@ -22,4 +24,21 @@ pub enum ControlStreamError {
 
     #[snafu(display("MOQT Protocol violated"))]
     ProtocolViolation,
+
+    #[snafu(display("MOQT handshake did not complete within {timeout:?}"))]
+    Timeout { timeout: Duration },
+}
+
+impl ControlStreamError {
+    /// Whether the peer sent a malformed Message,
+    /// which closes the session with
+    /// [ProtocolViolation](crate::types::error_code::Termination::ProtocolViolation).
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            Self::Recv {
+                source: DecodeError::VarInt { .. }
+            }
+        )
+    }
 }
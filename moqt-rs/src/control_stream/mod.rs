@@ -1,5 +1,7 @@
 mod error;
+mod reader;
 
+use core::time::Duration;
 use std::sync::Arc;
 
 pub use error::ControlStreamError;
@@ -11,40 +13,90 @@ use varint::VarIntNumber;
 use crate::{
     DRAFT_VERSION, SUPPORTED_VERSION,
     types::{
+        RequestWindow, Token,
         error_code::Termination,
         message::{ClientSetup, ControlMessage, ServerSetup},
     },
 };
 
 use {
-    crate::transport::{Connection, RecvStream, SendStream},
+    crate::transport::{Connection, SendStream},
     error::ctx,
+    reader::ControlReader,
 };
 
 pub struct ControlStream {
     tx: Arc<Mutex<SendStream>>,
-    rx: Arc<Mutex<RecvStream>>,
+    rx: Arc<Mutex<ControlReader>>,
+    peer_max_request_id: u64,
+    auth_token_cache_size: u64,
+    setup_token: Option<Token>,
 }
 
 impl ControlStream {
     /// Opens the ControlStream (the one and only bidirectional Stream) and performs
     /// the MOQT handshake with it.
+    ///
+    /// Closes the connection with
+    /// [ControlMessageTimeout](Termination::ControlMessageTimeout)
+    /// unless the handshake completes within `timeout`.
     #[tracing::instrument(skip(conn), err)]
-    pub async fn open(conn: &Connection) -> Result<Self, ControlStreamError> {
+    pub async fn open(conn: &Connection, timeout: Duration) -> Result<Self, ControlStreamError> {
+        let setup = ClientSetup::builder()
+            .version(DRAFT_VERSION)
+            .max_request_id(RequestWindow::new_server().max())
+            .build();
+        Self::open_with(conn, setup, timeout).await
+    }
+
+    /// Opens the ControlStream like [open](Self::open),
+    /// sending `setup` to initiate the handshake.
+    ///
+    /// Native QUIC sessions use this to send the
+    /// Path and Authority Parameters.
+    #[tracing::instrument(skip(conn), err)]
+    pub async fn open_with(
+        conn: &Connection,
+        setup: ClientSetup,
+        timeout: Duration,
+    ) -> Result<Self, ControlStreamError> {
+        handshake(conn, timeout, Self::initiate(conn, setup)).await
+    }
+
+    /// Accepts the ControlStream (the one and only bidirectional Stream) and performs
+    /// the MOQT handshake with it.
+    ///
+    /// Advertises `auth_token_cache_size` as the
+    /// maximum size of the Authorization Tokens
+    /// the client may register.
+    ///
+    /// Closes the connection with
+    /// [ControlMessageTimeout](Termination::ControlMessageTimeout)
+    /// unless the handshake completes within `timeout`.
+    #[tracing::instrument(skip(conn), err)]
+    pub async fn accept(
+        conn: &Connection,
+        timeout: Duration,
+        auth_token_cache_size: u64,
+    ) -> Result<Self, ControlStreamError> {
+        handshake(conn, timeout, Self::respond(conn, auth_token_cache_size)).await
+    }
+
+    /// Sends `setup` and awaits the ServerSetup.
+    async fn initiate(conn: &Connection, setup: ClientSetup) -> Result<Self, ControlStreamError> {
         let (tx, rx) = conn.open_bi().await.context(ctx::ConnectionSnafu)?;
         debug!("opened ControlStream");
         trace!("initiating MOQT handshake");
 
-        let this = Self {
+        let mut this = Self {
             tx: Arc::new(Mutex::new(tx)),
-            rx: Arc::new(Mutex::new(rx)),
+            rx: Arc::new(Mutex::new(ControlReader::new(rx))),
+            peer_max_request_id: 0,
+            auth_token_cache_size: 0,
+            setup_token: None,
         };
 
-        this.send(ControlMessage::ClientSetup(
-            // TODO any parameters? probably for the QUIC specific ones
-            ClientSetup::builder().version(DRAFT_VERSION).build(),
-        ))
-        .await?;
+        this.send(ControlMessage::ClientSetup(setup)).await?;
         debug!("ClientSetup sent");
 
         let msg = match this.recv().await? {
@@ -69,21 +121,29 @@ impl ControlStream {
             }
         );
 
+        this.peer_max_request_id = msg.max_request_id().copied().unwrap_or_default();
+        this.auth_token_cache_size = msg.max_auth_token_cache_size().copied().unwrap_or_default();
+
         debug!(version = selected_version, "established MOQT connection");
 
         Ok(this)
     }
 
-    /// Accepts the ControlStream (the one and only bidirectional Stream) and performs
-    /// the MOQT handshake with it.
-    #[tracing::instrument(skip(conn), err)]
-    pub async fn accept(conn: &Connection) -> Result<Self, ControlStreamError> {
+    /// Awaits the ClientSetup and responds with
+    /// the ServerSetup.
+    async fn respond(
+        conn: &Connection,
+        auth_token_cache_size: u64,
+    ) -> Result<Self, ControlStreamError> {
         let (tx, rx) = conn.accept_bi().await.context(ctx::ConnectionSnafu)?;
         debug!("accepted ControlStream");
 
-        let this = Self {
+        let mut this = Self {
             tx: Arc::new(Mutex::new(tx)),
-            rx: Arc::new(Mutex::new(rx)),
+            rx: Arc::new(Mutex::new(ControlReader::new(rx))),
+            peer_max_request_id: 0,
+            auth_token_cache_size: 0,
+            setup_token: None,
         };
 
         let msg = match this.recv().await? {
@@ -111,9 +171,16 @@ impl ControlStream {
         )?;
         debug!(version = %supported_version, "version negotiated");
 
+        this.peer_max_request_id = msg.max_request_id().copied().unwrap_or_default();
+        this.auth_token_cache_size = auth_token_cache_size;
+        this.setup_token = msg.auth_token().cloned();
+
         this.send(ControlMessage::ServerSetup(
-            // TODO any parameters?
-            ServerSetup::builder().version(supported_version).build(),
+            ServerSetup::builder()
+                .version(supported_version)
+                .max_request_id(RequestWindow::new_client().max())
+                .max_auth_token_cache_size(auth_token_cache_size)
+                .build(),
         ))
         .await?;
         debug!("ClientServerSetup sent");
@@ -121,6 +188,25 @@ impl ControlStream {
         Ok(this)
     }
 
+    /// The maximum Request ID the peer granted
+    /// in its setup message.
+    pub fn peer_max_request_id(&self) -> u64 {
+        self.peer_max_request_id
+    }
+
+    /// The maximum size of the Authorization Tokens
+    /// the client may register, as advertised
+    /// by the server.
+    pub fn auth_token_cache_size(&self) -> u64 {
+        self.auth_token_cache_size
+    }
+
+    /// The Authorization Token the client sent
+    /// with its setup message.
+    pub fn setup_token(&self) -> Option<&Token> {
+        self.setup_token.as_ref()
+    }
+
     /// Sends a [ControlMessage].
     #[tracing::instrument(skip(self), err)]
     pub async fn send(&self, msg: ControlMessage) -> Result<(), ControlStreamError> {
@@ -130,6 +216,8 @@ impl ControlStream {
     }
 
     /// Receives a [ControlMessage].
+    ///
+    /// This operation is cancel-safe.
     #[tracing::instrument(skip(self), err)]
     pub async fn recv(&self) -> Result<ControlMessage, ControlStreamError> {
         let mut lock = self.rx.lock().await;
@@ -137,3 +225,23 @@ impl ControlStream {
         lock.recv().await.context(ctx::RecvSnafu)
     }
 }
+
+/// Awaits the `setup` of the ControlStream on
+/// `conn` for at most `timeout`.
+async fn handshake<F>(
+    conn: &Connection,
+    timeout: Duration,
+    setup: F,
+) -> Result<ControlStream, ControlStreamError>
+where
+    F: Future<Output = Result<ControlStream, ControlStreamError>>,
+{
+    match tokio::time::timeout(timeout, setup).await {
+        Ok(res) => res,
+        Err(_) => {
+            error!(?timeout, "MOQT handshake timed out");
+            conn.close(Termination::ControlMessageTimeout);
+            ctx::TimeoutSnafu { timeout }.fail()
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use {
+    crate::{
+        transport::{RecvStream, error::DecodeError},
+        types::message::ControlMessage,
+    },
+    bytes::{Buf, BytesMut},
+    varint::{VarInt, core::ReferenceReader},
+};
+
+/// Size of the chunks read from the stream.
+const CHUNK_SIZE: usize = 4096;
+
+/// Length of the Message Length field.
+const LENGTH_LEN: usize = 2;
+
+/// ## Control Reader
+///
+/// Reads whole [ControlMessage]s from the receiving
+/// half of the ControlStream.
+///
+/// A Message may arrive in several reads and a read
+/// may hold several Messages, so data is buffered
+/// until the full Message Length has arrived.
+pub(crate) struct ControlReader {
+    rx: RecvStream,
+    /// Received data not decoded yet.
+    buf: BytesMut,
+    chunk: Box<[u8]>,
+}
+
+impl ControlReader {
+    pub fn new(rx: RecvStream) -> Self {
+        Self {
+            rx,
+            buf: BytesMut::new(),
+            chunk: vec![0; CHUNK_SIZE].into_boxed_slice(),
+        }
+    }
+
+    /// Receives the next [ControlMessage].
+    ///
+    /// Data following the Message is kept for the
+    /// next call.
+    ///
+    /// This operation is cancel-safe.
+    pub async fn recv(&mut self) -> Result<ControlMessage, DecodeError> {
+        loop {
+            if let Some(len) = frame_len(&self.buf)
+                && self.buf.len() >= len
+            {
+                let frame = self.buf.split_to(len);
+                return decode(&frame);
+            }
+
+            // the stream must not end within or before a Message
+            let len = self
+                .rx
+                .read(&mut self.chunk)
+                .await
+                .map_err(|source| DecodeError::Recv { source })?
+                .ok_or(DecodeError::EndOfStream)?;
+            self.buf.extend_from_slice(&self.chunk[..len]);
+        }
+    }
+}
+
+/// Length of the whole Message at the start of
+/// `buf`, once its Type and Length arrived.
+fn frame_len(buf: &[u8]) -> Option<usize> {
+    // the two most significant bits encode the length
+    // of the Message Type
+    let type_len = 1 << (buf.first()? >> 6);
+    let mut length = buf.get(type_len..type_len + LENGTH_LEN)?;
+
+    Some(type_len + LENGTH_LEN + usize::from(length.get_u16()))
+}
+
+/// Decodes a Message which must span all of `frame`.
+fn decode(frame: &[u8]) -> Result<ControlMessage, DecodeError> {
+    let mut reader = ReferenceReader::new(frame);
+    let (msg, bits) =
+        ControlMessage::decode(&mut reader, Some(frame.len() * 8)).map_err(|err| {
+            DecodeError::VarInt {
+                cause: err.to_string(),
+            }
+        })?;
+
+    let decoded = bits.div_ceil(8);
+    if decoded != frame.len() {
+        return Err(DecodeError::VarInt {
+            cause: format!(
+                "Message Length of {} bytes does not match the {decoded} bytes decoded",
+                frame.len(),
+            ),
+        });
+    }
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            transport::{
+                Connection, SendStream,
+                memory::{self, Conditions},
+            },
+            types::{
+                error_code,
+                message::{FetchError, MaxRequestId},
+            },
+        },
+        varint::{Writer, core::ReferenceWriter},
+    };
+
+    fn encode(msg: &ControlMessage) -> Vec<u8> {
+        let mut writer = ReferenceWriter::new();
+        msg.encode(&mut writer, Some(msg.len_bits().unwrap()))
+            .unwrap();
+        writer.finish().unwrap().to_vec()
+    }
+
+    /// Both ends of a stream, and the connection
+    /// to keep open.
+    async fn reader() -> (SendStream, ControlReader, [Connection; 2]) {
+        let (client, server) = memory::pair(
+            ([127, 0, 0, 1], 1).into(),
+            ([127, 0, 0, 1], 4443).into(),
+            &Conditions::default(),
+        );
+        let (client, server) = (
+            Connection::Memory(Box::new(client)),
+            Connection::Memory(Box::new(server)),
+        );
+
+        let tx = client.open_uni().await.unwrap();
+        let rx = server.accept_uni().await.unwrap();
+        (tx, ControlReader::new(rx), [client, server])
+    }
+
+    fn messages() -> [ControlMessage; 2] {
+        [
+            ControlMessage::FetchError(FetchError::new(
+                50u8,
+                error_code::Fetch::InvalidRange,
+                "error",
+            )),
+            ControlMessage::MaxRequestId(MaxRequestId::new(1u16 << 10)),
+        ]
+    }
+
+    #[tokio::test]
+    async fn split_message_test() {
+        let (mut tx, mut rx, _conn) = reader().await;
+        let [msg, _] = messages();
+        let bytes = encode(&msg);
+
+        for byte in &bytes {
+            tx.write(&[*byte]).await.unwrap();
+        }
+        assert_eq!(rx.recv().await.unwrap(), msg);
+    }
+
+    #[tokio::test]
+    async fn coalesced_messages_test() {
+        let (mut tx, mut rx, _conn) = reader().await;
+        let [first, second] = messages();
+        let bytes = [encode(&first), encode(&second)].concat();
+
+        // the second message is split across both writes
+        let (head, tail) = bytes.split_at(bytes.len() - 2);
+        tx.write(head).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), first);
+        tx.write(tail).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), second);
+    }
+
+    #[tokio::test]
+    async fn invalid_length_test() {
+        let (mut tx, mut rx, _conn) = reader().await;
+        let [msg, _] = messages();
+        let mut bytes = encode(&msg);
+        // claims one byte more than the message holds
+        bytes[2] += 1;
+        bytes.push(0);
+
+        tx.write(&bytes).await.unwrap();
+        assert!(matches!(rx.recv().await, Err(DecodeError::VarInt { .. })));
+    }
+
+    #[tokio::test]
+    async fn end_of_stream_test() {
+        let (mut tx, mut rx, _conn) = reader().await;
+        let [msg, _] = messages();
+        let bytes = encode(&msg);
+
+        tx.write(&bytes[..bytes.len() - 1]).await.unwrap();
+        tx.finish().await.unwrap();
+        assert_eq!(rx.recv().await, Err(DecodeError::EndOfStream));
+    }
+}
@@ -0,0 +1,32 @@
+use snafu::Snafu;
+
+use crate::transport::error::{ConnectionError, DecodeError, EncodeError, SendError};
+
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[snafu(visibility(pub), module(ctx))]
+pub enum FetchStreamError {
+    #[snafu(display("failed to open or accept the fetch stream"))]
+    Connection { source: ConnectionError },
+
+    #[snafu(display("failed to send on the fetch stream"))]
+    Send { source: EncodeError },
+
+    #[snafu(display("failed to finish the fetch stream"))]
+    Finish { source: SendError },
+
+    #[snafu(display("failed to receive on the fetch stream"))]
+    Recv { source: DecodeError },
+
+    #[snafu(display("fetch stream ended before the FETCH_HEADER"))]
+    MissingHeader,
+
+    #[snafu(display(
+        "object {group}:{object} violates the requested group order, previous was {previous_group}:{previous_object}"
+    ))]
+    OutOfOrder {
+        previous_group: u64,
+        previous_object: u64,
+        group: u64,
+        object: u64,
+    },
+}
@@ -0,0 +1,238 @@
+//! Serving and receiving the Objects
+//! requested by a [Fetch](crate::types::message::Fetch).
+
+mod error;
+mod stream;
+mod writer;
+
+pub use {error::FetchStreamError, stream::FetchStream, writer::FetchWriter};
+
+use {
+    crate::types::{
+        FetchType, GroupOrder, JoiningFetch, Location, StandaloneFetch, data::FetchObject,
+    },
+    core::cmp::Ordering,
+    varint::{VarIntNumber, x},
+};
+
+/// ## Fetch Range
+///
+/// The range of Objects requested by a
+/// [Fetch](crate::types::message::Fetch).
+///
+/// `end` is exclusive, an `end` Object ID of 0
+/// requests the entire end Group.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FetchRange {
+    /// ## Start
+    ///
+    /// The first requested Object.
+    pub start: Location,
+
+    /// ## End
+    ///
+    /// The Object following the last
+    /// requested Object.
+    pub end: Location,
+}
+
+impl FetchRange {
+    /// Creates a new [FetchRange] from `start` up
+    /// to (excluding) `end`.
+    pub fn new<S, E>(start: S, end: E) -> Self
+    where
+        S: Into<Location>,
+        E: Into<Location>,
+    {
+        Self {
+            start: start.into(),
+            end: end.into(),
+        }
+    }
+
+    /// Whether the Object at `location` is part of
+    /// this range.
+    pub fn contains(&self, location: &Location) -> bool {
+        let (group, object) = (location.group::<u64>(), location.object::<u64>());
+        let (start_group, start_object) = (self.start.group::<u64>(), self.start.object::<u64>());
+        let (end_group, end_object) = (self.end.group::<u64>(), self.end.object::<u64>());
+
+        let after_start = group > start_group || (group == start_group && object >= start_object);
+        let before_end =
+            group < end_group || (group == end_group && (end_object == 0 || object < end_object));
+
+        after_start && before_end
+    }
+
+    /// Creates the range of a [JoiningFetch] of
+    /// `fetch_type` joining a Subscription which
+    /// started after `largest`.
+    ///
+    /// The range ends with `largest`, a relative
+    /// Joining Fetch starts the given number of
+    /// Groups before it. None if the end exceeds
+    /// the maximum Location.
+    pub fn joining(
+        fetch_type: FetchType,
+        joining: &JoiningFetch,
+        largest: &Location,
+    ) -> Option<Self> {
+        let group = largest.group::<u64>();
+        let start = match fetch_type {
+            FetchType::RelativeJoining => group.saturating_sub(joining.start.number()),
+            _ => joining.start.number(),
+        };
+        let end_object = largest.object::<u64>().checked_add(1)?;
+        let id = |id: u64| <x!(i)>::try_from(id).ok();
+
+        Some(Self {
+            start: Location::new(id(start)?, 0u8),
+            end: Location::new(id(group)?, id(end_object)?),
+        })
+    }
+
+    /// Whether this range contains any Object,
+    /// i.e. `start` precedes `end`.
+    pub fn is_valid(&self) -> bool {
+        self.contains(&self.start)
+    }
+}
+
+impl From<&StandaloneFetch> for FetchRange {
+    fn from(value: &StandaloneFetch) -> Self {
+        Self {
+            start: value.start_location.clone(),
+            end: value.end_location.clone(),
+        }
+    }
+}
+
+/// ## Fetch Source
+///
+/// Provides the already published Objects of
+/// a Track to serve a [Fetch](crate::types::message::Fetch).
+pub trait FetchSource {
+    /// Returns all available Objects within `range`.
+    ///
+    /// The Objects may be returned in any order,
+    /// the [FetchWriter] sorts them according to
+    /// the requested [GroupOrder].
+    fn objects(&self, range: &FetchRange) -> Vec<FetchObject>;
+}
+
+impl FetchSource for [FetchObject] {
+    fn objects(&self, range: &FetchRange) -> Vec<FetchObject> {
+        self.iter()
+            .filter(|obj| range.contains(&obj.location()))
+            .cloned()
+            .collect()
+    }
+}
+
+impl FetchSource for Vec<FetchObject> {
+    fn objects(&self, range: &FetchRange) -> Vec<FetchObject> {
+        self.as_slice().objects(range)
+    }
+}
+
+/// Compares the Objects at `a` and `b` by the
+/// position they have on a Fetch stream.
+///
+/// Groups are ordered by `order`, Objects within
+/// a Group are always ascending. The publisher's
+/// [GroupOrder::Original] is ascending.
+fn cmp_locations(order: GroupOrder, a: &Location, b: &Location) -> Ordering {
+    let group = a.group::<u64>().cmp(&b.group());
+    let group = match order {
+        GroupOrder::Descending => group.reverse(),
+        GroupOrder::Original | GroupOrder::Ascending => group,
+    };
+
+    group.then_with(|| a.object::<u64>().cmp(&b.object()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_test() {
+        let range = FetchRange::new([2u8, 3u8], [4u8, 2u8]);
+
+        assert!(!range.contains(&Location::new(1u8, 9u8)));
+        assert!(!range.contains(&Location::new(2u8, 2u8)));
+        assert!(range.contains(&Location::new(2u8, 3u8)));
+        assert!(range.contains(&Location::new(3u8, 0u8)));
+        assert!(range.contains(&Location::new(4u8, 1u8)));
+        assert!(!range.contains(&Location::new(4u8, 2u8)));
+        assert!(!range.contains(&Location::new(5u8, 0u8)));
+
+        let whole_group = FetchRange::new([2u8, 0u8], [4u8, 0u8]);
+        assert!(whole_group.contains(&Location::new(4u8, 100u8)));
+        assert!(!whole_group.contains(&Location::new(5u8, 0u8)));
+    }
+
+    #[test]
+    fn joining_range_test() {
+        let largest = Location::new(5u8, 3u8);
+        let joining = JoiningFetch::builder().id(0u8).start(2u8).build();
+
+        let relative = FetchRange::joining(FetchType::RelativeJoining, &joining, &largest);
+        assert_eq!(relative, Some(FetchRange::new([3u8, 0u8], [5u8, 4u8])));
+        let absolute = FetchRange::joining(FetchType::AbsoluteJoining, &joining, &largest);
+        assert_eq!(absolute, Some(FetchRange::new([2u8, 0u8], [5u8, 4u8])));
+
+        // starts with the first Group at most
+        let joining = JoiningFetch::builder().id(0u8).start(9u8).build();
+        let relative = FetchRange::joining(FetchType::RelativeJoining, &joining, &largest);
+        assert_eq!(relative, Some(FetchRange::new([0u8, 0u8], [5u8, 4u8])));
+        let absolute = FetchRange::joining(FetchType::AbsoluteJoining, &joining, &largest).unwrap();
+        assert!(!absolute.is_valid());
+    }
+
+    #[test]
+    fn valid_range_test() {
+        assert!(FetchRange::new([2u8, 3u8], [2u8, 4u8]).is_valid());
+        assert!(FetchRange::new([2u8, 3u8], [2u8, 0u8]).is_valid());
+        assert!(FetchRange::new([2u8, 3u8], [3u8, 0u8]).is_valid());
+        assert!(!FetchRange::new([2u8, 3u8], [2u8, 3u8]).is_valid());
+        assert!(!FetchRange::new([3u8, 0u8], [2u8, 0u8]).is_valid());
+    }
+
+    #[test]
+    fn source_test() {
+        let objects: Vec<_> = [(1u8, 0u8), (2, 0), (2, 1), (3, 0)]
+            .into_iter()
+            .map(|(group, object)| {
+                FetchObject::builder()
+                    .group(group)
+                    .object(object)
+                    .priority(0)
+                    .build()
+            })
+            .collect();
+
+        let served = objects.objects(&FetchRange::new([2u8, 1u8], [3u8, 0u8]));
+        let served: Vec<_> = served.iter().map(FetchObject::location).collect();
+
+        assert_eq!(served, [Location::new(2u8, 1u8), Location::new(3u8, 0u8)]);
+    }
+
+    #[test]
+    fn order_test() {
+        let a = Location::new(1u8, 5u8);
+        let b = Location::new(2u8, 0u8);
+        let c = Location::new(2u8, 1u8);
+
+        assert_eq!(cmp_locations(GroupOrder::Ascending, &a, &b), Ordering::Less);
+        assert_eq!(cmp_locations(GroupOrder::Original, &a, &b), Ordering::Less);
+        assert_eq!(
+            cmp_locations(GroupOrder::Descending, &a, &b),
+            Ordering::Greater
+        );
+        assert_eq!(
+            cmp_locations(GroupOrder::Descending, &b, &c),
+            Ordering::Less
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use {
+    super::{FetchStreamError, cmp_locations, error::ctx},
+    crate::{
+        transport::RecvStream,
+        types::{
+            GroupOrder, Location,
+            data::{FetchHeader, FetchObject},
+        },
+    },
+    bytes::BytesMut,
+    core::cmp::Ordering,
+    snafu::{OptionExt, ResultExt, ensure},
+    varint::x,
+};
+
+/// ## Fetch Stream
+///
+/// Receives the Objects requested by a
+/// [Fetch](crate::types::message::Fetch).
+///
+/// Objects are yielded in the requested
+/// [GroupOrder], a publisher sending Objects
+/// out of order results in
+/// [FetchStreamError::OutOfOrder]. With
+/// [GroupOrder::Original] only the order of
+/// Objects within a Group is checked.
+pub struct FetchStream {
+    request_id: x!(i),
+    group_order: GroupOrder,
+    stream: RecvStream,
+    buf: BytesMut,
+    previous: Option<Location>,
+}
+
+impl FetchStream {
+    /// Reads the [FetchHeader] from `stream`.
+    ///
+    /// `buf` holds any data already read from
+    /// `stream`.
+    pub(crate) async fn new(
+        mut stream: RecvStream,
+        mut buf: BytesMut,
+        group_order: GroupOrder,
+    ) -> Result<Self, FetchStreamError> {
+        let header = stream
+            .recv_buffered::<FetchHeader>(&mut buf)
+            .await
+            .context(ctx::RecvSnafu)?
+            .context(ctx::MissingHeaderSnafu)?;
+
+        Ok(Self {
+            request_id: header.request_id,
+            group_order,
+            stream,
+            buf,
+            previous: None,
+        })
+    }
+
+    /// The Request ID of the Fetch this stream
+    /// responds to.
+    pub fn request_id(&self) -> &x!(i) {
+        &self.request_id
+    }
+
+    /// The [GroupOrder] Objects are yielded in.
+    pub fn group_order(&self) -> GroupOrder {
+        self.group_order
+    }
+
+    /// Receives the next Object.
+    ///
+    /// Yields None once the publisher has sent
+    /// all requested Objects.
+    pub async fn next(&mut self) -> Result<Option<FetchObject>, FetchStreamError> {
+        let Some(object) = self
+            .stream
+            .recv_buffered::<FetchObject>(&mut self.buf)
+            .await
+            .context(ctx::RecvSnafu)?
+        else {
+            return Ok(None);
+        };
+
+        let location = object.location();
+        if let Some(previous) = &self.previous {
+            let in_order = match self.group_order {
+                // the publisher decides on the order of Groups
+                GroupOrder::Original => {
+                    previous.group::<u64>() != location.group::<u64>()
+                        || previous.object::<u64>() < location.object::<u64>()
+                }
+                order => cmp_locations(order, previous, &location) == Ordering::Less,
+            };
+            ensure!(
+                in_order,
+                ctx::OutOfOrderSnafu {
+                    previous_group: previous.group::<u64>(),
+                    previous_object: previous.object::<u64>(),
+                    group: location.group::<u64>(),
+                    object: location.object::<u64>(),
+                }
+            );
+        }
+        self.previous = Some(location);
+
+        Ok(Some(object))
+    }
+}
@@ -0,0 +1,89 @@
+use {
+    super::{FetchRange, FetchSource, FetchStreamError, cmp_locations, error::ctx},
+    crate::{
+        transport::{Connection, SendStream},
+        types::{
+            GroupOrder,
+            data::{FetchHeader, FetchObject},
+        },
+    },
+    snafu::ResultExt,
+    tracing::trace,
+    varint::x,
+};
+
+/// ## Fetch Writer
+///
+/// Serves the Objects requested by a
+/// [Fetch](crate::types::message::Fetch) on a
+/// unidirectional stream starting with a
+/// [FetchHeader].
+pub struct FetchWriter {
+    request_id: x!(i),
+    stream: SendStream,
+}
+
+impl FetchWriter {
+    /// Opens a new stream and sends the [FetchHeader]
+    /// for the Fetch with `request_id`.
+    #[tracing::instrument(skip(conn), err)]
+    pub(crate) async fn open(
+        conn: &Connection,
+        request_id: x!(i),
+    ) -> Result<Self, FetchStreamError> {
+        let mut stream = conn.open_uni().await.context(ctx::ConnectionSnafu)?;
+
+        stream
+            .send(FetchHeader::builder().id(request_id.clone()).build())
+            .await
+            .context(ctx::SendSnafu)?;
+
+        Ok(Self { request_id, stream })
+    }
+
+    /// The Request ID of the Fetch this stream
+    /// responds to.
+    pub fn request_id(&self) -> &x!(i) {
+        &self.request_id
+    }
+
+    /// Sends a single Object.
+    ///
+    /// Objects must be sent in the requested
+    /// [GroupOrder].
+    pub async fn write(&mut self, object: FetchObject) -> Result<(), FetchStreamError> {
+        self.stream.send(object).await.context(ctx::SendSnafu)
+    }
+
+    /// Sends all Objects within `range` available
+    /// from `source`, ordered by `order`.
+    ///
+    /// Returns the number of Objects sent.
+    pub async fn serve<S>(
+        &mut self,
+        source: &S,
+        range: &FetchRange,
+        order: GroupOrder,
+    ) -> Result<usize, FetchStreamError>
+    where
+        S: FetchSource + ?Sized,
+    {
+        let mut objects = source.objects(range);
+        objects.sort_by(|a, b| cmp_locations(order, &a.location(), &b.location()));
+
+        let count = objects.len();
+        for object in objects {
+            self.write(object).await?;
+        }
+
+        trace!(count, request_id = ?self.request_id, "served fetch range");
+
+        Ok(count)
+    }
+
+    /// Finishes the stream, signaling that all
+    /// requested Objects have been sent.
+    pub async fn finish(mut self) -> Result<(), FetchStreamError> {
+        self.stream.finish().await.context(ctx::FinishSnafu)
+    }
+}
@@ -1,26 +1,47 @@
 mod client;
 mod control_stream;
+mod fetch;
 mod macro_helper;
+mod relay;
 mod server;
 mod session;
+mod subgroup;
+mod timeouts;
 pub mod transport;
 pub mod types;
 
 pub use {
-    client::{Client, ClientConfig},
+    client::{
+        Client, ClientConfig, DatagramPublisher, NamespaceEvent, NamespaceSubscription,
+        OnRequestsBlocked, PublishedNamespace, Subscription, TlsVerification, TrackPublisher,
+    },
     control_stream::ControlStream,
-    server::{Protocol, Server, ServerConfig},
-    session::Session,
+    fetch::{FetchRange, FetchSource, FetchStream, FetchWriter},
+    relay::DEFAULT_CACHE_SIZE,
+    server::{
+        Actions, AllowList, AuthRequest, Authorizer, Denial, GoawayTarget, KeySet, NamespaceInfo,
+        Protocol, RequestKind, Server, ServerConfig, SessionInfo, SignedClaims, SigningKey,
+        SubscriptionInfo, TrackInfo, Upstream, VerifyingKey,
+    },
+    session::{GOAWAY_TIMEOUT, Session},
+    subgroup::{SubgroupReader, SubgroupWriter},
+    timeouts::Timeouts,
 };
 
 /// the draft version this crate implements
 pub const DRAFT_VERSION: u32 = 0xFF00000E;
 pub const SUPPORTED_VERSION: &[u32] = &[DRAFT_VERSION];
+/// the ALPN of MOQT sessions over native QUIC
+pub const ALPN: &[u8] = b"moq-00";
 
 pub mod error {
     pub use super::{
-        client::ClientError, control_stream::ControlStreamError, server::ServerError,
-        session::SessionError,
+        client::ClientError,
+        control_stream::ControlStreamError,
+        fetch::FetchStreamError,
+        server::{AllowListError, KeySetError, ServerError, SignError},
+        session::{SessionError, SessionHandleError},
+        subgroup::SubgroupStreamError,
     };
 }
 
@@ -0,0 +1,319 @@
+use {
+    super::TrackKey,
+    crate::{
+        FetchRange,
+        types::{
+            Location,
+            data::{FetchObject, Object},
+        },
+    },
+    core::time::Duration,
+    std::{
+        collections::{BTreeMap, HashMap, VecDeque},
+        sync::{Mutex, MutexGuard},
+    },
+    tokio::time::Instant,
+    varint::VarIntNumber,
+};
+
+/// The default size of the [Cache] in bytes.
+pub const DEFAULT_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Group and Object ID, ordered like a [Location].
+type Position = (u64, u64);
+
+/// ## Cache
+///
+/// The Objects the [Relay](super::Relay) received
+/// per Track, to serve Fetches and Subscribers
+/// joining a Track.
+///
+/// Objects are evicted once the publisher's
+/// Max Cache Duration passed or, oldest first,
+/// once their payloads exceed the byte budget.
+pub(crate) struct Cache {
+    budget: usize,
+    inner: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    tracks: HashMap<TrackKey, TrackCache>,
+    /// All cached Objects, oldest first.
+    order: VecDeque<Entry>,
+    /// Payload bytes of all cached Objects.
+    bytes: usize,
+}
+
+#[derive(Default)]
+struct TrackCache {
+    /// The Max Cache Duration of the publisher,
+    /// None caches Objects indefinitely.
+    max_duration: Option<Duration>,
+    objects: BTreeMap<Position, Cached>,
+    /// The largest Location ever received, even
+    /// if its Object was evicted.
+    largest: Option<Location>,
+}
+
+struct Cached {
+    object: Object,
+    cached_at: Instant,
+    expires_at: Option<Instant>,
+}
+
+impl Cached {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// A cached Object in the order of arrival.
+struct Entry {
+    key: TrackKey,
+    position: Position,
+    cached_at: Instant,
+}
+
+impl Cache {
+    /// Creates a Cache keeping at most `budget`
+    /// payload bytes.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Sets the Max Cache Duration of the Track
+    /// with `key` for all Objects received after.
+    pub fn set_max_duration(&self, key: &TrackKey, max_duration: Option<Duration>) {
+        self.state()
+            .tracks
+            .entry(key.clone())
+            .or_default()
+            .max_duration = max_duration;
+    }
+
+    /// Caches `object` of the Track with `key`,
+    /// replacing a previous one at its Location.
+    pub fn insert(&self, key: &TrackKey, object: Object) {
+        let now = Instant::now();
+        let mut state = self.state();
+        let state = &mut *state;
+
+        let track = state.tracks.entry(key.clone()).or_default();
+        let location = object.location();
+        let position = position(&location);
+        if track
+            .largest
+            .as_ref()
+            .is_none_or(|largest| *largest < location)
+        {
+            track.largest = Some(location);
+        }
+
+        let size = object.payload.len();
+        if size > self.budget || track.max_duration == Some(Duration::ZERO) {
+            return;
+        }
+
+        let cached = Cached {
+            object,
+            cached_at: now,
+            expires_at: track.max_duration.map(|max| now + max),
+        };
+        if let Some(old) = track.objects.insert(position, cached) {
+            state.bytes -= old.object.payload.len();
+        }
+        state.bytes += size;
+        state.order.push_back(Entry {
+            key: key.clone(),
+            position,
+            cached_at: now,
+        });
+
+        state.evict(self.budget, now);
+    }
+
+    /// Returns all cached Objects of the Track with
+    /// `key` within `range`, ascending.
+    ///
+    /// None if nothing was ever received on the
+    /// Track.
+    pub fn objects(&self, key: &TrackKey, range: &FetchRange) -> Option<Vec<FetchObject>> {
+        let now = Instant::now();
+        let state = self.state();
+        let track = state.tracks.get(key)?;
+
+        let start = position(&range.start);
+        let objects = track
+            .objects
+            .range(start..)
+            .filter(|(_, cached)| !cached.is_expired(now))
+            .map(|(_, cached)| &cached.object)
+            .take_while(|object| range.contains(&object.location()) || before(range, object))
+            .filter(|object| range.contains(&object.location()))
+            .cloned()
+            .map(FetchObject::from)
+            .collect();
+        Some(objects)
+    }
+
+    /// The largest Location received on the Track
+    /// with `key`, if any.
+    pub fn largest(&self, key: &TrackKey) -> Option<Location> {
+        self.state().tracks.get(key)?.largest.clone()
+    }
+
+    /// The payload bytes of all cached Objects.
+    pub fn bytes(&self) -> usize {
+        self.state().bytes
+    }
+
+    fn state(&self) -> MutexGuard<'_, CacheState> {
+        self.inner.lock().expect("cache lock poisoned")
+    }
+}
+
+impl CacheState {
+    /// Removes expired Objects and the oldest ones
+    /// until at most `budget` bytes are cached.
+    fn evict(&mut self, budget: usize, now: Instant) {
+        while let Some(entry) = self.order.front() {
+            let cached = self
+                .tracks
+                .get(&entry.key)
+                .and_then(|track| track.objects.get(&entry.position))
+                // replaced Objects left a stale entry
+                .filter(|cached| cached.cached_at == entry.cached_at);
+
+            match cached {
+                Some(cached) if self.bytes <= budget && !cached.is_expired(now) => break,
+                Some(_) => {
+                    let entry = self.order.pop_front().expect("entry exists");
+                    let track = self.tracks.get_mut(&entry.key).expect("track exists");
+                    let cached = track
+                        .objects
+                        .remove(&entry.position)
+                        .expect("object exists");
+                    self.bytes -= cached.object.payload.len();
+                }
+                None => {
+                    self.order.pop_front();
+                }
+            }
+        }
+    }
+}
+
+fn position(location: &Location) -> Position {
+    (location.group(), location.object())
+}
+
+/// Whether `object` precedes the end of `range`.
+fn before(range: &FetchRange, object: &Object) -> bool {
+    let (group, end_group) = (object.group_id.number::<u64>(), range.end.group::<u64>());
+    group < end_group || (group == end_group && range.end.object::<u64>() == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, bytes::Bytes};
+
+    fn key(name: &str) -> TrackKey {
+        TrackKey::new(["live"].into(), name.into())
+    }
+
+    fn object(group: u8, object: u8, payload: &'static [u8]) -> Object {
+        FetchObject::builder()
+            .group(group)
+            .object(object)
+            .priority(0)
+            .payload(Bytes::from_static(payload))
+            .build()
+            .into()
+    }
+
+    fn locations(objects: &[FetchObject]) -> Vec<Location> {
+        objects.iter().map(FetchObject::location).collect()
+    }
+
+    #[test]
+    fn range_test() {
+        let cache = Cache::new(DEFAULT_CACHE_SIZE);
+        let video = key("video");
+        assert_eq!(
+            cache.objects(&video, &FetchRange::new([0u8, 0u8], [1u8, 0u8])),
+            None
+        );
+
+        for (group, obj) in [(1, 0), (1, 1), (2, 0), (2, 1), (3, 0)] {
+            cache.insert(&video, object(group, obj, b"frame"));
+        }
+
+        let objects = cache
+            .objects(&video, &FetchRange::new([1u8, 1u8], [2u8, 0u8]))
+            .unwrap();
+        assert_eq!(
+            locations(&objects),
+            [
+                Location::new(1u8, 1u8),
+                Location::new(2u8, 0u8),
+                Location::new(2u8, 1u8)
+            ]
+        );
+
+        let objects = cache
+            .objects(&video, &FetchRange::new([4u8, 0u8], [5u8, 0u8]))
+            .unwrap();
+        assert!(objects.is_empty());
+        assert_eq!(cache.largest(&video), Some(Location::new(3u8, 0u8)));
+        assert_eq!(cache.largest(&key("audio")), None);
+    }
+
+    #[test]
+    fn budget_test() {
+        let cache = Cache::new(10);
+        let (video, audio) = (key("video"), key("audio"));
+
+        cache.insert(&video, object(0, 0, b"1234"));
+        cache.insert(&audio, object(0, 0, b"1234"));
+        // replacing an Object frees its bytes
+        cache.insert(&video, object(0, 0, b"12"));
+        assert_eq!(cache.bytes(), 6);
+
+        // evicts the oldest Object across all Tracks
+        cache.insert(&video, object(0, 1, b"12345"));
+        assert_eq!(cache.bytes(), 7);
+        let all = FetchRange::new([0u8, 0u8], [1u8, 0u8]);
+        assert!(cache.objects(&audio, &all).unwrap().is_empty());
+        assert_eq!(cache.objects(&video, &all).unwrap().len(), 2);
+
+        // larger than the whole budget
+        cache.insert(&audio, object(1, 0, b"12345678901"));
+        assert_eq!(cache.bytes(), 7);
+        assert_eq!(cache.largest(&audio), Some(Location::new(1u8, 0u8)));
+    }
+
+    #[test]
+    fn max_duration_test() {
+        let cache = Cache::new(DEFAULT_CACHE_SIZE);
+        let video = key("video");
+        let all = FetchRange::new([0u8, 0u8], [1u8, 0u8]);
+
+        cache.set_max_duration(&video, Some(Duration::ZERO));
+        cache.insert(&video, object(0, 0, b"frame"));
+        assert!(cache.objects(&video, &all).unwrap().is_empty());
+
+        cache.set_max_duration(&video, Some(Duration::from_millis(5)));
+        cache.insert(&video, object(0, 1, b"frame"));
+        assert_eq!(cache.objects(&video, &all).unwrap().len(), 1);
+
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(cache.objects(&video, &all).unwrap().is_empty());
+        // evicted with the next insert
+        cache.insert(&video, object(0, 2, b"frame"));
+        assert_eq!(cache.bytes(), 5);
+    }
+}
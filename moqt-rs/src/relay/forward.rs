@@ -0,0 +1,252 @@
+use {
+    super::{Peer, Relay, Subscriber, SubscriberKey},
+    crate::{
+        SubgroupReader, SubgroupWriter,
+        transport::RecvStream,
+        types::{
+            data::{FetchHeader, FetchHeaderType, Object, ObjectDatagram, SubgroupHeader},
+            error_code::{self, Termination},
+        },
+    },
+    bytes::BytesMut,
+    std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    },
+    tokio::sync::mpsc,
+    tracing::{debug, warn},
+    varint::{VarIntNumber, x},
+};
+
+/// Number of Objects buffered per forwarded
+/// Subgroup and Subscriber.
+const OBJECT_BUFFER: usize = 64;
+
+/// Forwards the Subgroup stream `stream` sent by
+/// `publisher` to every Subscriber of its Track
+/// and caches its Objects.
+///
+/// Each Subscriber receives the Subgroup on a
+/// stream of its own, carrying the Track Alias
+/// the Relay assigned to it. Subscribers joining
+/// while the Subgroup is forwarded receive the
+/// remaining Objects, unless they wait for the
+/// next Group. Objects are only forwarded to
+/// Subscribers with forwarding enabled and whose
+/// range contains them.
+///
+/// Subscriptions ending with the Group of the
+/// Subgroup are ended once it was forwarded.
+///
+/// Fetch streams of an Upstream server are
+/// handed to the Fetch they answer.
+///
+/// Closes the session of `publisher` with
+/// [DataStreamTimeout](Termination::DataStreamTimeout)
+/// unless the header arrives within the data
+/// stream timeout.
+pub(crate) async fn forward_stream(relay: Arc<Relay>, publisher: Arc<Peer>, stream: RecvStream) {
+    let timeout = relay.timeouts().data_stream;
+    let header = tokio::time::timeout(timeout, read_header(stream));
+    let mut reader = match header.await {
+        Ok(Some(DataStream::Subgroup(reader))) => reader,
+        Ok(Some(DataStream::Fetch(request_id, stream, buf))) => {
+            relay.fetch_stream(&publisher, request_id, stream, buf);
+            return;
+        }
+        Ok(None) => return,
+        Err(_) => {
+            warn!(?timeout, addr = %publisher.addr(), "publisher took too long to send the subgroup header");
+            publisher.transport().close(Termination::DataStreamTimeout);
+            return;
+        }
+    };
+
+    let alias = reader.header().track_alias.number::<u64>();
+    let Some(track) = publisher.published.get(&alias).map(|track| track.clone()) else {
+        debug!(alias, addr = %publisher.addr(), "received subgroup stream for unknown track");
+        return;
+    };
+
+    let mut joined = track.joined();
+    let mut known: HashSet<SubscriberKey> =
+        track.subscribers().iter().map(Subscriber::key).collect();
+    let mut outputs: HashMap<SubscriberKey, mpsc::Sender<Object>> = HashMap::new();
+
+    loop {
+        let object = match reader.next().await {
+            Ok(Some(object)) => object,
+            Ok(None) => break,
+            Err(err) => {
+                warn!(%err, alias, "failed to read object");
+                break;
+            }
+        };
+        relay.cache().insert(track.key(), object.clone());
+
+        let subscribers = track.subscribers();
+        if track.joined() != joined {
+            joined = track.joined();
+            for subscriber in &subscribers {
+                if subscriber.joins_group() {
+                    known.insert(subscriber.key());
+                }
+            }
+        }
+
+        // Subscribers may pause or narrow their
+        // Subscription at any Object
+        let location = object.location();
+        for subscriber in subscribers {
+            let key = subscriber.key();
+            if !known.contains(&key) || !subscriber.accepts(&location) {
+                continue;
+            }
+
+            let tx = outputs
+                .entry(key)
+                .or_insert_with(|| spawn_output(subscriber, reader.header()));
+            if tx.send(object.clone()).await.is_err() {
+                known.remove(&key);
+                outputs.remove(&key);
+            }
+        }
+    }
+    drop(outputs);
+    track.stream_forwarded();
+
+    // the Subgroup of their end Group is complete
+    let group = reader.header().group_id.number::<u64>();
+    for subscriber in track.subscribers() {
+        if known.contains(&subscriber.key()) && subscriber.ends_with(group) {
+            relay
+                .end_subscription(
+                    &subscriber,
+                    error_code::PublishDone::SubscriptionEnded,
+                    "reached the end group",
+                )
+                .await;
+        }
+    }
+}
+
+/// Spawns the task writing the Subgroup of
+/// `header` to `subscriber` and returns the
+/// channel for its Objects.
+fn spawn_output(subscriber: Subscriber, header: &SubgroupHeader) -> mpsc::Sender<Object> {
+    let (tx, rx) = mpsc::channel(OBJECT_BUFFER);
+    subscriber.stream_opened();
+    let header = SubgroupHeader {
+        track_alias: subscriber.alias.clone(),
+        ..header.clone()
+    };
+    tokio::spawn(write_subgroup(subscriber, header, rx));
+    tx
+}
+
+/// Writes the Objects received on `objects` on a
+/// new Subgroup stream to `subscriber`.
+async fn write_subgroup(
+    subscriber: Subscriber,
+    header: SubgroupHeader,
+    mut objects: mpsc::Receiver<Object>,
+) {
+    let mut writer = match SubgroupWriter::open(subscriber.peer.transport(), header).await {
+        Ok(writer) => writer,
+        Err(err) => {
+            warn!(%err, addr = %subscriber.peer.addr(), "failed to open subgroup stream");
+            return;
+        }
+    };
+
+    while let Some(object) = objects.recv().await {
+        let payload = object.object_payload();
+        let res = if writer.header().has_extensions() {
+            writer
+                .write_with_extensions(object.object_id, object.extensions, payload)
+                .await
+        } else {
+            writer.write(object.object_id, payload).await
+        };
+
+        if let Err(err) = res {
+            warn!(%err, addr = %subscriber.peer.addr(), "failed to forward object");
+            return;
+        }
+    }
+
+    if let Err(err) = writer.finish().await {
+        debug!(%err, addr = %subscriber.peer.addr(), "failed to finish subgroup stream");
+    }
+}
+
+/// Forwards `datagram` sent by `publisher` to
+/// every Subscriber of its Track and caches its
+/// Object.
+pub(crate) fn forward_datagram(relay: &Relay, publisher: &Peer, datagram: ObjectDatagram) {
+    let alias = datagram.track_alias.number::<u64>();
+    let Some(track) = publisher.published.get(&alias).map(|track| track.clone()) else {
+        debug!(alias, addr = %publisher.addr(), "received datagram for unknown track");
+        return;
+    };
+    let object = Object::from(datagram.clone());
+    let location = object.location();
+    relay.cache().insert(track.key(), object);
+
+    for subscriber in track.subscribers() {
+        if !subscriber.accepts(&location) {
+            continue;
+        }
+        let datagram = ObjectDatagram {
+            track_alias: subscriber.alias.clone(),
+            ..datagram.clone()
+        };
+
+        // like any datagram, a forwarded one may be lost
+        if let Err(err) = subscriber.peer.transport().send_object_datagram(&datagram) {
+            debug!(%err, addr = %subscriber.peer.addr(), "failed to forward datagram");
+        }
+    }
+}
+
+/// An incoming unidirectional stream after
+/// its header was received.
+enum DataStream {
+    /// The stream of the Fetch with this
+    /// Request ID.
+    Fetch(u64, RecvStream, BytesMut),
+    Subgroup(Box<SubgroupReader>),
+}
+
+/// Reads the header of `stream`, None if it
+/// ended or the header is invalid.
+async fn read_header(mut stream: RecvStream) -> Option<DataStream> {
+    let mut buf = BytesMut::new();
+
+    let typ = match stream.peek_buffered::<x!(i)>(&mut buf).await {
+        Ok(typ) => typ?,
+        Err(err) => {
+            warn!(%err, "failed to read stream type");
+            return None;
+        }
+    };
+
+    if typ == FetchHeaderType::Fetch.key() {
+        let header = match stream.peek_buffered::<FetchHeader>(&mut buf).await {
+            Ok(header) => header?,
+            Err(err) => {
+                warn!(%err, "failed to read fetch header");
+                return None;
+            }
+        };
+        return Some(DataStream::Fetch(header.request_id.number(), stream, buf));
+    }
+
+    match SubgroupReader::new(stream, buf).await {
+        Ok(reader) => Some(DataStream::Subgroup(Box::new(reader))),
+        Err(err) => {
+            warn!(%err, "failed to read subgroup header");
+            None
+        }
+    }
+}
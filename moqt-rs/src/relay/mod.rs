@@ -0,0 +1,1433 @@
+//! The core of the [Server](crate::Server).
+//!
+//! Routes the Subscriptions of downstream
+//! sessions to the session publishing the
+//! Track and forwards its Objects to every
+//! Subscriber.
+
+mod cache;
+mod forward;
+mod peer;
+mod track;
+mod trie;
+mod upstream;
+
+pub use cache::DEFAULT_CACHE_SIZE;
+pub(crate) use {
+    cache::Cache,
+    forward::{forward_datagram, forward_stream},
+    peer::Peer,
+    track::{Subscriber, SubscriberKey, Track, TrackKey, UpstreamRange},
+    trie::NamespaceTrie,
+    upstream::FetchReply,
+};
+
+use {
+    crate::{
+        AuthRequest, Authorizer, Denial, FetchRange, FetchWriter, Timeouts, Upstream,
+        error::ControlStreamError,
+        types::{
+            ContentExists, Forward, GroupOrder, Namespace, Parameters, ReasonPhrase,
+            config::DEFAULT_PRIORITY,
+            data::FetchObject,
+            error::RequestIdError,
+            error_code::{self, Termination},
+            message::{
+                ControlMessage, Fetch, FetchError, FetchOk, Publish, PublishDone, PublishError,
+                PublishNamespace, PublishNamespaceDone, PublishNamespaceError, PublishNamespaceOk,
+                PublishOk, RequestsBlocked, Subscribe, SubscribeError, SubscribeNamespace,
+                SubscribeNamespaceError, SubscribeNamespaceOk, SubscribeOk, SubscribeUpdate,
+                TrackStatus, TrackStatusError, TrackStatusOk, Unsubscribe,
+            },
+        },
+    },
+    core::{net::SocketAddr, time::Duration},
+    dashmap::{DashMap, mapref::entry::Entry},
+    std::{
+        fmt,
+        sync::{Arc, Mutex, MutexGuard},
+    },
+    tokio::time::Instant,
+    tracing::{debug, warn},
+    upstream::Upstreams,
+    varint::{VarIntNumber, x},
+};
+
+/// ## Relay
+///
+/// The state shared by all sessions of a
+/// [Server](crate::Server).
+pub(crate) struct Relay {
+    timeouts: Timeouts,
+
+    /// Objects of the relayed Tracks.
+    cache: Cache,
+
+    /// Connected sessions by remote address.
+    peers: DashMap<SocketAddr, Arc<Peer>>,
+
+    /// Published Namespaces and the address of
+    /// the session publishing them.
+    namespaces: Mutex<NamespaceTrie<SocketAddr>>,
+
+    /// Relayed Tracks.
+    tracks: DashMap<TrackKey, Arc<Track>>,
+
+    /// Decides which Requests clients may send,
+    /// all are allowed without.
+    authorizer: Option<Arc<dyn Authorizer>>,
+
+    /// Servers asked for the Tracks no session
+    /// publishes.
+    upstreams: Upstreams,
+}
+
+/// The code and reason to reject a Fetch with.
+type Rejection = (error_code::Fetch, &'static str);
+
+/// What to do with an incoming Subscribe once
+/// the [Track] was looked up.
+enum Route {
+    /// Send the Subscribe upstream.
+    Upstream(Arc<Track>),
+
+    /// The Subscriber joined an existing Track,
+    /// Some when it can be answered right away.
+    Joined(Arc<Track>, Option<Box<SubscribeOk>>),
+
+    /// Reject the Subscribe because the publisher
+    /// granted no Request IDs beyond `max`.
+    Blocked(Arc<Peer>, u64),
+
+    /// Reject the Subscribe.
+    Reject(error_code::Subscribe, &'static str),
+}
+
+impl Relay {
+    /// Creates a Relay caching at most
+    /// `cache_size` bytes of Object payloads,
+    /// authorizing Requests with `authorizer`
+    /// and subscribing unknown Tracks at
+    /// `upstreams`.
+    pub fn new(
+        timeouts: Timeouts,
+        cache_size: usize,
+        authorizer: Option<Arc<dyn Authorizer>>,
+        upstreams: Vec<Upstream>,
+    ) -> Self {
+        Self {
+            timeouts,
+            cache: Cache::new(cache_size),
+            peers: DashMap::new(),
+            namespaces: Mutex::default(),
+            tracks: DashMap::new(),
+            authorizer,
+            upstreams: Upstreams::new(upstreams),
+        }
+    }
+
+    /// How long sessions may take before they
+    /// are closed.
+    pub fn timeouts(&self) -> &Timeouts {
+        &self.timeouts
+    }
+
+    /// The Objects of the relayed Tracks.
+    pub fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    /// Authorizes `request` with the [Authorizer],
+    /// allows all without.
+    pub fn authorize(&self, request: &AuthRequest<'_>) -> Result<(), Denial> {
+        match &self.authorizer {
+            Some(authorizer) => authorizer.authorize(request),
+            None => Ok(()),
+        }
+    }
+
+    /// Stops sending new Requests to the
+    /// [Upstream] server `peer` once it asked to
+    /// go away.
+    pub fn upstream_goaway(&self, peer: &Peer) {
+        self.upstreams.release(peer);
+    }
+
+    /// Registers a newly established session.
+    pub fn join(&self, peer: Arc<Peer>) {
+        self.peers.insert(peer.addr(), peer);
+    }
+
+    /// Removes a closed session along with its
+    /// Namespaces, Tracks and Subscriptions.
+    pub async fn leave(&self, peer: &Arc<Peer>) {
+        self.peers.remove(&peer.addr());
+        if peer.is_upstream_server() {
+            self.upstreams.release(peer);
+            // pending forwarded Fetches learn it closed
+            peer.forwarded_fetches.clear();
+        }
+        let withdrawn = self.namespaces().extract_if(|_, addr| *addr == peer.addr());
+        for (namespace, _) in withdrawn {
+            self.withdraw_namespace(&namespace).await;
+        }
+
+        let downstream: Vec<u64> = peer.downstream.iter().map(|entry| *entry.key()).collect();
+        for request_id in downstream {
+            self.unsubscribe(peer, request_id).await;
+        }
+        for entry in peer.fetches.iter() {
+            entry.value().abort();
+        }
+        peer.fetches.clear();
+
+        let statuses: Vec<u64> = peer
+            .track_statuses
+            .iter()
+            .map(|entry| *entry.key())
+            .collect();
+        for request_id in statuses {
+            if let Some((_, (downstream, request_id))) = peer.track_statuses.remove(&request_id) {
+                reject_track_status(
+                    &downstream,
+                    request_id,
+                    error_code::TrackStatus::InternalError,
+                    "the publisher closed its session",
+                )
+                .await;
+            }
+        }
+
+        let upstream: Vec<Arc<Track>> = peer
+            .upstream
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        peer.upstream.clear();
+        peer.published.clear();
+
+        for track in upstream {
+            self.end_track(
+                &track,
+                error_code::PublishDone::TrackEnded,
+                ReasonPhrase::new("the publisher closed its session"),
+            )
+            .await;
+        }
+    }
+
+    /// Handles a [PublishNamespace] sent by `peer`.
+    ///
+    /// Every Namespace is published by at most
+    /// one session at a time. Sessions subscribed
+    /// to a prefix of the Namespace learn about it.
+    pub async fn publish_namespace(
+        &self,
+        peer: &Arc<Peer>,
+        msg: PublishNamespace,
+    ) -> Result<(), ControlStreamError> {
+        let (accepted, new) = {
+            let mut namespaces = self.namespaces();
+            match namespaces.get(&msg.namespace) {
+                Some(addr) => (*addr == peer.addr(), false),
+                None => {
+                    namespaces.insert(&msg.namespace, peer.addr());
+                    (true, true)
+                }
+            }
+        };
+
+        let response = if accepted {
+            debug!(namespace = %msg.namespace, addr = %peer.addr(), "namespace published");
+            ControlMessage::PublishNamespaceOk(PublishNamespaceOk::new(msg.request_id))
+        } else {
+            ControlMessage::PublishNamespaceError(PublishNamespaceError::new(
+                msg.request_id,
+                error_code::PublishNamespace::Uninterested,
+                "namespace is published by another session",
+            ))
+        };
+        peer.control_stream().send(response).await?;
+
+        if new {
+            for subscriber in self.namespace_subscribers(&msg.namespace) {
+                if subscriber.addr() != peer.addr() {
+                    self.announce_namespace(&subscriber, &msg.namespace).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Withdraws `namespace` if it was published
+    /// by `peer`.
+    pub async fn publish_namespace_done(&self, peer: &Peer, namespace: &Namespace) {
+        let removed = {
+            let mut namespaces = self.namespaces();
+            let published = namespaces.get(namespace) == Some(&peer.addr());
+            published && namespaces.remove(namespace).is_some()
+        };
+
+        if removed {
+            self.withdraw_namespace(namespace).await;
+        }
+    }
+
+    /// Returns the session publishing the longest
+    /// Namespace `namespace` starts with.
+    pub fn publisher_of(&self, namespace: &Namespace) -> Option<Arc<Peer>> {
+        let addr = *self.namespaces().longest_prefix(namespace)?;
+        self.peers.get(&addr).map(|peer| peer.clone())
+    }
+
+    /// The connected sessions, including those
+    /// to Upstream servers.
+    pub fn sessions(&self) -> Vec<Arc<Peer>> {
+        self.peers
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// The published Namespaces and the address of
+    /// the session publishing them.
+    pub fn published_namespaces(&self) -> Vec<(Namespace, SocketAddr)> {
+        self.namespaces()
+            .entries()
+            .into_iter()
+            .map(|(namespace, addr)| (namespace, *addr))
+            .collect()
+    }
+
+    /// The relayed Tracks.
+    pub fn active_tracks(&self) -> Vec<Arc<Track>> {
+        self.tracks
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// Handles a [SubscribeNamespace] sent by `peer`.
+    ///
+    /// The Namespaces starting with the prefix are
+    /// published to `peer`, the existing ones right
+    /// away and future ones once they are.
+    pub async fn subscribe_namespace(
+        &self,
+        peer: &Arc<Peer>,
+        msg: SubscribeNamespace,
+    ) -> Result<(), ControlStreamError> {
+        let prefix = &msg.namespace_prefix;
+        let (known, published) = {
+            let namespaces = self.namespaces();
+            let published = namespaces
+                .starting_with(prefix)
+                .into_iter()
+                .filter(|(_, addr)| **addr != peer.addr())
+                .map(|(namespace, _)| namespace)
+                .collect::<Vec<_>>();
+            (namespaces.overlaps(prefix), published)
+        };
+
+        let rejection = if !known {
+            Some((
+                error_code::SubscribeNamespace::NamespacePrefixUnknown,
+                "no namespace is published within the prefix",
+            ))
+        } else if !peer.subscribe_namespace(prefix, msg.request_id.clone()) {
+            Some((
+                error_code::SubscribeNamespace::NamespacePrefixOverlap,
+                "prefix overlaps with another namespace subscription",
+            ))
+        } else {
+            None
+        };
+
+        if let Some((code, reason)) = rejection {
+            return peer
+                .control_stream()
+                .send(ControlMessage::SubscribeNamespaceError(
+                    SubscribeNamespaceError::new(msg.request_id, code, reason),
+                ))
+                .await;
+        }
+
+        debug!(prefix = %prefix, addr = %peer.addr(), "namespace prefix subscribed");
+        peer.control_stream()
+            .send(ControlMessage::SubscribeNamespaceOk(
+                SubscribeNamespaceOk::new(msg.request_id),
+            ))
+            .await?;
+
+        for namespace in published {
+            self.announce_namespace(peer, &namespace).await;
+        }
+        Ok(())
+    }
+
+    /// Handles an [UnsubscribeNamespace](crate::types::message::UnsubscribeNamespace)
+    /// sent by `peer`.
+    pub fn unsubscribe_namespace(&self, peer: &Peer, prefix: &Namespace) {
+        if !peer.unsubscribe_namespace(prefix) {
+            debug!(%prefix, "unsubscribe namespace for unknown subscription");
+        }
+    }
+
+    /// Handles a [PublishNamespaceError] `peer`
+    /// sent for a Namespace the Relay published
+    /// to it.
+    pub fn publish_namespace_error(&self, peer: &Peer, err: PublishNamespaceError) {
+        debug!(request_id = ?err.request_id, reason = %err.reason, "peer rejected namespace");
+        peer.announced
+            .retain(|_, request_id| *request_id != err.request_id);
+    }
+
+    /// The sessions subscribed to a prefix of
+    /// `namespace`.
+    fn namespace_subscribers(&self, namespace: &Namespace) -> Vec<Arc<Peer>> {
+        self.peers
+            .iter()
+            .filter(|peer| peer.subscribes_to(namespace))
+            .map(|peer| peer.clone())
+            .collect()
+    }
+
+    /// Publishes `namespace` to `peer` unless it
+    /// was already.
+    async fn announce_namespace(&self, peer: &Peer, namespace: &Namespace) {
+        if peer.announced.contains_key(namespace) {
+            return;
+        }
+        let request_id = match peer.next_request_id() {
+            Ok(request_id) => request_id,
+            Err(err) => {
+                warn!(%err, addr = %peer.addr(), "failed to publish namespace");
+                return;
+            }
+        };
+        peer.announced.insert(namespace.clone(), request_id.clone());
+
+        let msg = PublishNamespace::builder()
+            .id(request_id)
+            .namespace(namespace.clone())
+            .build();
+        if let Err(err) = peer
+            .control_stream()
+            .send(ControlMessage::PublishNamespace(msg))
+            .await
+        {
+            warn!(%err, addr = %peer.addr(), "failed to publish namespace");
+        }
+    }
+
+    /// Withdraws `namespace` from every session it
+    /// was published to.
+    async fn withdraw_namespace(&self, namespace: &Namespace) {
+        let peers: Vec<Arc<Peer>> = self.peers.iter().map(|peer| peer.clone()).collect();
+
+        for peer in peers {
+            if peer.announced.remove(namespace).is_none() {
+                continue;
+            }
+            let msg = PublishNamespaceDone::new(namespace.clone());
+            if let Err(err) = peer
+                .control_stream()
+                .send(ControlMessage::PublishNamespaceDone(msg))
+                .await
+            {
+                warn!(%err, addr = %peer.addr(), "failed to withdraw namespace");
+            }
+        }
+    }
+
+    /// Handles a [Publish] sent by `peer`.
+    ///
+    /// The pushed Track is relayed to Subscribers
+    /// until `peer` ends it or leaves.
+    pub async fn publish(&self, peer: &Arc<Peer>, msg: Publish) -> Result<(), ControlStreamError> {
+        let key = TrackKey::new(msg.namespace.clone(), msg.name.clone());
+
+        let accepted = match self.tracks.entry(key.clone()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                self.cache
+                    .set_max_duration(&key, msg.max_cache_duration().copied());
+                let ok = SubscribeOk {
+                    request_id: msg.request_id.clone(),
+                    alias: msg.alias.clone(),
+                    expires: Duration::ZERO,
+                    group_order: msg.group_order,
+                    content_exists: msg.content_exists,
+                    largest_location: msg.largest_location.clone(),
+                    parameters: Parameters::default(),
+                };
+                let track = Arc::new(Track::pushed(key, peer.clone(), ok));
+                peer.upstream.insert(msg.request_id.number(), track.clone());
+                peer.published.insert(msg.alias.number(), track.clone());
+                entry.insert(track);
+                true
+            }
+        };
+
+        let response = if accepted {
+            debug!(namespace = %msg.namespace, addr = %peer.addr(), "track published");
+            ControlMessage::PublishOk(
+                PublishOk::builder()
+                    .id(msg.request_id)
+                    .forward(true)
+                    .sub_prio(DEFAULT_PRIORITY)
+                    .group_order(msg.group_order)
+                    .with_largest_object()
+                    .build(),
+            )
+        } else {
+            ControlMessage::PublishError(PublishError::new(
+                msg.request_id,
+                error_code::Publish::InternalError,
+                "track is published by another session",
+            ))
+        };
+
+        peer.control_stream().send(response).await
+    }
+
+    /// Handles a [PublishDone] sent by `peer`.
+    ///
+    /// The Subscribers of the Track are ended once
+    /// all streams the publisher counted were
+    /// forwarded, or after the data stream timeout.
+    pub fn publish_done(self: &Arc<Self>, peer: &Peer, msg: PublishDone) {
+        let request_id = msg.request_id.number::<u64>();
+        let Some((_, track)) = peer.upstream.remove(&request_id) else {
+            debug!(request_id, "publish done for unknown track");
+            return;
+        };
+
+        let relay = self.clone();
+        tokio::spawn(async move {
+            let timeout = relay.timeouts.data_stream;
+            if !track.forwarded(msg.stream_count.number(), timeout).await {
+                warn!(
+                    request_id,
+                    ?timeout,
+                    "publisher's streams did not arrive before its publish done"
+                );
+            }
+            if let Some(alias) = track.upstream_alias() {
+                track.publisher().published.remove(&alias.number::<u64>());
+            }
+
+            relay.end_track(&track, msg.code, msg.reason).await;
+        });
+    }
+
+    /// Handles a [Subscribe] sent by `peer`.
+    ///
+    /// The first Subscriber of a Track causes a
+    /// Subscribe to its publisher, all later ones
+    /// share that upstream Subscription. Tracks no
+    /// session publishes are subscribed from an
+    /// [Upstream] server.
+    pub async fn subscribe(
+        self: &Arc<Self>,
+        peer: &Arc<Peer>,
+        msg: Subscribe,
+    ) -> Result<(), ControlStreamError> {
+        let key = TrackKey::new(msg.namespace.clone(), msg.name.clone());
+        let subscriber = Subscriber {
+            request_id: msg.request_id.clone(),
+            alias: peer.next_alias(),
+            filter: msg.filter_type,
+            largest: None,
+            refreshed: Instant::now(),
+            start: msg.start_location.clone(),
+            end_group: msg.end_group.as_ref().map(VarIntNumber::number),
+            priority: msg.subscriber_priority.number(),
+            forward: msg.forward,
+            streams: Arc::default(),
+            peer: peer.clone(),
+        };
+
+        // connected before the entry of the Track
+        // is held, it is not held across awaits
+        let upstream =
+            if self.tracks.contains_key(&key) || self.publisher_of(&key.namespace).is_some() {
+                None
+            } else {
+                self.upstream().await
+            };
+
+        let route = match self.tracks.entry(key.clone()) {
+            Entry::Occupied(entry) => {
+                let track = entry.get();
+                peer.downstream
+                    .insert(msg.request_id.number(), track.clone());
+                let ok = track.add_subscriber(subscriber).map(Box::new);
+                Route::Joined(track.clone(), ok)
+            }
+            Entry::Vacant(entry) => match self.publisher_of(&key.namespace).or(upstream) {
+                None => Route::Reject(
+                    error_code::Subscribe::TrackDoesNotExist,
+                    "no session publishes the namespace",
+                ),
+                Some(publisher) => match publisher.next_request_id() {
+                    Err(RequestIdError::Blocked { max }) => Route::Blocked(publisher, max),
+                    Err(RequestIdError::Exhausted) => Route::Reject(
+                        error_code::Subscribe::InternalError,
+                        "no request ID left for the publisher",
+                    ),
+                    Ok(request_id) => {
+                        let track = Arc::new(Track::new(
+                            key.clone(),
+                            publisher.clone(),
+                            request_id,
+                            msg.subscriber_priority.number(),
+                        ));
+                        publisher
+                            .upstream
+                            .insert(track.request_id().number(), track.clone());
+                        peer.downstream
+                            .insert(msg.request_id.number(), track.clone());
+                        track.add_subscriber(subscriber);
+                        entry.insert(track.clone());
+                        Route::Upstream(track)
+                    }
+                },
+            },
+        };
+
+        match route {
+            Route::Upstream(track) => {
+                self.subscribe_upstream(&track, msg.subscriber_priority.number(), msg.group_order)
+                    .await;
+                Ok(())
+            }
+            Route::Joined(track, Some(ok)) => {
+                let key = (peer.addr(), msg.request_id.number());
+                let ok = self.accept(&track, &key, *ok);
+                peer.control_stream()
+                    .send(ControlMessage::SubscribeOk(ok))
+                    .await?;
+                self.update_upstream(&track).await;
+                Ok(())
+            }
+            Route::Joined(_, None) => Ok(()),
+            Route::Blocked(publisher, max) => {
+                if publisher.report_blocked(max) {
+                    let msg = <x!(i)>::try_from(max).map(RequestsBlocked::new);
+                    if let Ok(msg) = msg
+                        && let Err(err) = publisher
+                            .control_stream()
+                            .send(ControlMessage::RequestsBlocked(msg))
+                            .await
+                    {
+                        warn!(%err, addr = %publisher.addr(), "failed to report blocked requests");
+                    }
+                }
+
+                peer.control_stream()
+                    .send(ControlMessage::SubscribeError(SubscribeError::new(
+                        msg.request_id,
+                        error_code::Subscribe::InternalError,
+                        "the publisher granted no more request IDs",
+                    )))
+                    .await
+            }
+            Route::Reject(code, reason) => {
+                peer.control_stream()
+                    .send(ControlMessage::SubscribeError(SubscribeError::new(
+                        msg.request_id,
+                        code,
+                        reason,
+                    )))
+                    .await
+            }
+        }
+    }
+
+    /// Handles an [Unsubscribe](crate::types::message::Unsubscribe)
+    /// sent by `peer`.
+    ///
+    /// Ends the upstream Subscription once the
+    /// last Subscriber of the Track is gone.
+    pub async fn unsubscribe(&self, peer: &Peer, request_id: u64) {
+        if !self.remove_subscriber(peer, request_id).await {
+            debug!(request_id, "unsubscribe for unknown subscription");
+        }
+    }
+
+    /// Ends the Subscription of `subscriber` with
+    /// a [PublishDone].
+    pub async fn end_subscription<R>(
+        &self,
+        subscriber: &Subscriber,
+        code: error_code::PublishDone,
+        reason: R,
+    ) where
+        R: Into<ReasonPhrase>,
+    {
+        let request_id = subscriber.request_id.number::<u64>();
+        if self.remove_subscriber(&subscriber.peer, request_id).await {
+            publish_done(subscriber, code, reason).await;
+        }
+    }
+
+    /// Removes the Subscriber of `peer` with
+    /// `request_id`.
+    ///
+    /// Ends the upstream Subscription once the
+    /// last Subscriber of the Track is gone.
+    /// Returns false for unknown Subscriptions.
+    async fn remove_subscriber(&self, peer: &Peer, request_id: u64) -> bool {
+        let Some((_, track)) = peer.downstream.remove(&request_id) else {
+            return false;
+        };
+        let key = (peer.addr(), request_id);
+
+        let unused = match self.tracks.entry(track.key().clone()) {
+            Entry::Occupied(entry) if Arc::ptr_eq(entry.get(), &track) => {
+                let unused = track.remove_subscriber(&key) == 0 && !track.is_pushed();
+                if unused {
+                    entry.remove();
+                }
+                unused
+            }
+            _ => {
+                track.remove_subscriber(&key);
+                false
+            }
+        };
+
+        if unused {
+            self.unsubscribe_upstream(&track).await;
+        } else {
+            self.update_upstream(&track).await;
+        }
+        true
+    }
+
+    /// Handles a [SubscribeUpdate] sent by `peer`.
+    pub async fn subscribe_update(
+        &self,
+        peer: &Peer,
+        msg: SubscribeUpdate,
+    ) -> Result<(), &'static str> {
+        let request_id = msg.request_id.number::<u64>();
+        let Some(track) = peer.downstream.get(&request_id).map(|track| track.clone()) else {
+            debug!(request_id, "subscribe update for unknown subscription");
+            return Ok(());
+        };
+
+        track.update_subscriber(&(peer.addr(), request_id), &msg)?;
+        debug!(
+            request_id,
+            forward = ?msg.forward,
+            priority = ?msg.subscriber_priority,
+            "subscription updated"
+        );
+        self.update_upstream(&track).await;
+        Ok(())
+    }
+
+    /// Handles a [SubscribeOk] the publisher `peer`
+    /// sent for an upstream Subscription.
+    ///
+    /// The upstream Subscription is refreshed
+    /// before it expires.
+    pub async fn subscribe_ok(self: &Arc<Self>, peer: &Peer, ok: SubscribeOk) {
+        let request_id = ok.request_id.number::<u64>();
+        let Some(track) = peer.upstream.get(&request_id).map(|track| track.clone()) else {
+            debug!(request_id, "subscribe ok for unknown subscription");
+            return;
+        };
+        if track.upstream_alias().is_some() {
+            debug!(request_id, "duplicate subscribe ok");
+            return;
+        }
+
+        peer.published.insert(ok.alias.number(), track.clone());
+        self.cache
+            .set_max_duration(track.key(), ok.max_cache_duration().copied());
+        if let Some(period) = self.timeouts.refresh_after(ok.expires) {
+            tokio::spawn(self.clone().refresh_upstream(track.clone(), period));
+        }
+
+        for subscriber in track.activate(ok.clone()) {
+            let ok = self.accept(&track, &subscriber.key(), subscriber.subscribe_ok(&ok));
+            let msg = ControlMessage::SubscribeOk(ok);
+            if let Err(err) = subscriber.peer.control_stream().send(msg).await {
+                warn!(%err, addr = %subscriber.peer.addr(), "failed to accept subscriber");
+            }
+        }
+    }
+
+    /// Handles a [SubscribeError] the publisher
+    /// `peer` sent for an upstream Subscription.
+    pub async fn subscribe_error(&self, peer: &Peer, err: SubscribeError) {
+        let request_id = err.request_id.number::<u64>();
+        let Some((_, track)) = peer.upstream.remove(&request_id) else {
+            debug!(request_id, "subscribe error for unknown subscription");
+            return;
+        };
+
+        self.reject_subscribers(&track, err.code, err.reason).await;
+    }
+
+    /// Handles a [Fetch] sent by `peer`.
+    ///
+    /// Fetches are served from the [Cache], the
+    /// publisher of the Track is not asked. Those
+    /// of Tracks no session publishes and nothing
+    /// is cached of are forwarded to an [Upstream]
+    /// server.
+    pub async fn fetch(
+        self: &Arc<Self>,
+        peer: &Arc<Peer>,
+        msg: Fetch,
+    ) -> Result<(), ControlStreamError> {
+        let objects = self.fetch_range(peer, &msg).and_then(|(key, range)| {
+            let objects = self.cached_objects(&key, &range)?;
+            Ok((objects, range))
+        });
+        let (objects, range) = match objects {
+            Ok(objects) => objects,
+            Err((code, reason)) => {
+                let unknown = code == error_code::Fetch::TrackDoesNotExist
+                    && msg
+                        .standalone
+                        .as_ref()
+                        .is_some_and(|track| self.publisher_of(&track.namespace).is_none());
+                if unknown && let Some(upstream) = self.upstream().await {
+                    return self.fetch_upstream(upstream, peer, msg).await;
+                }
+
+                return peer
+                    .control_stream()
+                    .send(ControlMessage::FetchError(FetchError::new(
+                        msg.request_id,
+                        code,
+                        reason,
+                    )))
+                    .await;
+            }
+        };
+
+        // the cache returns the Objects ascending
+        let end = objects.last().expect("objects are not empty").location();
+        let group_order = match msg.group_order {
+            GroupOrder::Original => GroupOrder::Ascending,
+            order => order,
+        };
+        let ok = FetchOk {
+            request_id: msg.request_id.clone(),
+            group_order,
+            end_of_track: false.into(),
+            end_location: end,
+            parameters: Parameters::default(),
+        };
+        peer.control_stream()
+            .send(ControlMessage::FetchOk(ok))
+            .await?;
+
+        peer.spawn_fetch(msg.request_id.number(), {
+            let peer = peer.clone();
+            let request_id = msg.request_id;
+            async move {
+                serve_fetch(&peer, request_id, &objects, &range, group_order).await;
+            }
+        });
+        Ok(())
+    }
+
+    /// Resolves the Track and the range of Objects
+    /// `msg` of `peer` requests.
+    ///
+    /// A Joining Fetch ends with the Largest
+    /// Location reported to the Subscription it
+    /// joins.
+    fn fetch_range(&self, peer: &Peer, msg: &Fetch) -> Result<(TrackKey, FetchRange), Rejection> {
+        if let Some(standalone) = &msg.standalone {
+            let key = TrackKey::new(standalone.namespace.clone(), standalone.name.clone());
+            return Ok((key, FetchRange::from(standalone)));
+        }
+
+        let joining = msg
+            .joining
+            .as_ref()
+            .ok_or((error_code::Fetch::InternalError, "fetch requests no range"))?;
+        let request_id = joining.request_id.number::<u64>();
+        let track = peer
+            .downstream
+            .get(&request_id)
+            .map(|track| track.clone())
+            .ok_or((
+                error_code::Fetch::InvalidJoiningRequestId,
+                "no subscription with the joining request id",
+            ))?;
+        let largest = track.largest_of(&(peer.addr(), request_id)).ok_or((
+            error_code::Fetch::NoObjects,
+            "the subscription starts before any object",
+        ))?;
+        let range = FetchRange::joining(msg.fetch_type, joining, &largest).ok_or((
+            error_code::Fetch::InvalidRange,
+            "range exceeds the largest location",
+        ))?;
+
+        Ok((track.key().clone(), range))
+    }
+
+    /// Returns the cached Objects of the Track with
+    /// `key` within `range`.
+    fn cached_objects(
+        &self,
+        key: &TrackKey,
+        range: &FetchRange,
+    ) -> Result<Vec<FetchObject>, Rejection> {
+        if !range.is_valid() {
+            return Err((error_code::Fetch::InvalidRange, "range is empty"));
+        }
+
+        match self.cache.objects(key, range) {
+            None if !self.tracks.contains_key(key) => {
+                Err((error_code::Fetch::TrackDoesNotExist, "track is not relayed"))
+            }
+            Some(objects) if !objects.is_empty() => Ok(objects),
+            _ => Err((error_code::Fetch::NoObjects, "no objects are cached")),
+        }
+    }
+
+    /// Handles a [FetchCancel](crate::types::message::FetchCancel)
+    /// sent by `peer`.
+    pub fn fetch_cancel(&self, peer: &Peer, request_id: u64) {
+        match peer.fetches.remove(&request_id) {
+            Some((_, task)) => task.abort(),
+            None => debug!(request_id, "fetch cancel for unknown fetch"),
+        }
+    }
+
+    /// Handles a [TrackStatus] sent by `peer`.
+    ///
+    /// Answered from the registry and the [Cache]
+    /// for active Tracks, otherwise forwarded to
+    /// the publisher of the Namespace or an
+    /// [Upstream] server.
+    pub async fn track_status(
+        self: &Arc<Self>,
+        peer: &Arc<Peer>,
+        msg: TrackStatus,
+    ) -> Result<(), ControlStreamError> {
+        let key = TrackKey::new(msg.namespace.clone(), msg.name.clone());
+        let active = self.tracks.get(&key).and_then(|track| track.subscribe_ok());
+        if let Some(ok) = active {
+            let ok = self.cached_largest(&key, ok);
+            let ok = TrackStatusOk {
+                request_id: msg.request_id,
+                alias: <x!(i)>::default(),
+                ..ok
+            };
+            return peer
+                .control_stream()
+                .send(ControlMessage::TrackStatusOk(ok))
+                .await;
+        }
+
+        let Some(publisher) = self.publisher_or_upstream(&key.namespace).await else {
+            return peer
+                .control_stream()
+                .send(ControlMessage::TrackStatusError(TrackStatusError::new(
+                    msg.request_id,
+                    error_code::TrackStatus::TrackDoesNotExist,
+                    "no session publishes the namespace",
+                )))
+                .await;
+        };
+        let Ok(request_id) = publisher.next_request_id() else {
+            return peer
+                .control_stream()
+                .send(ControlMessage::TrackStatusError(TrackStatusError::new(
+                    msg.request_id,
+                    error_code::TrackStatus::InternalError,
+                    "the publisher granted no more request IDs",
+                )))
+                .await;
+        };
+
+        publisher
+            .track_statuses
+            .insert(request_id.number(), (peer.clone(), msg.request_id.clone()));
+        let upstream = TrackStatus {
+            request_id: request_id.clone(),
+            ..msg
+        };
+        if let Err(err) = publisher
+            .control_stream()
+            .send(ControlMessage::TrackStatus(upstream))
+            .await
+        {
+            warn!(%err, addr = %publisher.addr(), "failed to request track status upstream");
+            if let Some((_, (peer, request_id))) =
+                publisher.track_statuses.remove(&request_id.number::<u64>())
+            {
+                reject_track_status(
+                    &peer,
+                    request_id,
+                    error_code::TrackStatus::InternalError,
+                    "failed to request the track status upstream",
+                )
+                .await;
+            }
+            return Ok(());
+        }
+
+        let timeout = self.timeouts.request;
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let Some((_, (peer, downstream_id))) =
+                publisher.track_statuses.remove(&request_id.number::<u64>())
+            else {
+                return;
+            };
+
+            warn!(
+                addr = %publisher.addr(),
+                ?request_id,
+                "publisher took too long to respond to track status"
+            );
+            reject_track_status(
+                &peer,
+                downstream_id,
+                error_code::TrackStatus::Timeout,
+                "the publisher took too long to respond",
+            )
+            .await;
+            publisher
+                .transport()
+                .close(Termination::ControlMessageTimeout);
+        });
+        Ok(())
+    }
+
+    /// Handles a [TrackStatusOk] the publisher `peer`
+    /// sent for a forwarded TrackStatus.
+    pub async fn track_status_ok(&self, peer: &Peer, ok: TrackStatusOk) {
+        let request_id = ok.request_id.number::<u64>();
+        let Some((_, (downstream, downstream_id))) = peer.track_statuses.remove(&request_id) else {
+            debug!(request_id, "track status ok for unknown request");
+            return;
+        };
+
+        let ok = TrackStatusOk {
+            request_id: downstream_id,
+            ..ok
+        };
+        if let Err(err) = downstream
+            .control_stream()
+            .send(ControlMessage::TrackStatusOk(ok))
+            .await
+        {
+            warn!(%err, addr = %downstream.addr(), "failed to forward track status");
+        }
+    }
+
+    /// Handles a [TrackStatusError] the publisher
+    /// `peer` sent for a forwarded TrackStatus.
+    pub async fn track_status_error(&self, peer: &Peer, err: TrackStatusError) {
+        let request_id = err.request_id.number::<u64>();
+        let Some((_, (downstream, downstream_id))) = peer.track_statuses.remove(&request_id) else {
+            debug!(request_id, "track status error for unknown request");
+            return;
+        };
+
+        reject_track_status(&downstream, downstream_id, err.code, err.reason).await;
+    }
+
+    /// Completes the [SubscribeOk] for the
+    /// Subscriber with `key` of `track` with the
+    /// subscription expiry and starts it.
+    fn accept(
+        self: &Arc<Self>,
+        track: &Arc<Track>,
+        key: &SubscriberKey,
+        ok: SubscribeOk,
+    ) -> SubscribeOk {
+        let expires = self.timeouts.subscription;
+        if !expires.is_zero() {
+            track.refresh_subscriber(key);
+            tokio::spawn(self.clone().expire(track.clone(), *key));
+        }
+
+        SubscribeOk {
+            expires,
+            ..self.with_largest(track, key, ok)
+        }
+    }
+
+    /// Ends the Subscriber with `key` of `track`
+    /// with [Expired](error_code::PublishDone::Expired)
+    /// unless it refreshes its Subscription within
+    /// the subscription expiry.
+    async fn expire(self: Arc<Self>, track: Arc<Track>, key: SubscriberKey) {
+        let expires = self.timeouts.subscription;
+        let subscriber = loop {
+            let Some(subscriber) = track.subscriber(&key) else {
+                return;
+            };
+            let deadline = subscriber.refreshed + expires;
+            if deadline <= Instant::now() {
+                break subscriber;
+            }
+            tokio::time::sleep_until(deadline).await;
+        };
+
+        debug!(
+            addr = %subscriber.peer.addr(),
+            request_id = ?subscriber.request_id,
+            "subscription expired"
+        );
+        self.end_subscription(
+            &subscriber,
+            error_code::PublishDone::Expired,
+            "the subscription expired",
+        )
+        .await;
+    }
+
+    /// Reports the largest cached Location of
+    /// `track` in `ok` if it is beyond the one
+    /// reported by the publisher.
+    ///
+    /// The Subscriber with `key` remembers the
+    /// Location reported to it for Joining Fetches.
+    fn with_largest(&self, track: &Track, key: &SubscriberKey, ok: SubscribeOk) -> SubscribeOk {
+        let ok = self.cached_largest(track.key(), ok);
+        track.set_largest(key, ok.largest_location.clone());
+        ok
+    }
+
+    /// Reports the largest cached Location of the
+    /// Track with `key` in `ok` if it is beyond the
+    /// one reported by the publisher.
+    fn cached_largest(&self, key: &TrackKey, mut ok: SubscribeOk) -> SubscribeOk {
+        if let Some(largest) = self.cache.largest(key)
+            && ok
+                .largest_location
+                .as_ref()
+                .is_none_or(|location| *location < largest)
+        {
+            ok.content_exists = ContentExists::Yes;
+            ok.largest_location = Some(largest);
+        }
+        ok
+    }
+
+    /// Subscribes to `track` at its publisher.
+    ///
+    /// The Subscribers are rejected unless the
+    /// publisher responds within the request
+    /// timeout.
+    async fn subscribe_upstream(
+        self: &Arc<Self>,
+        track: &Arc<Track>,
+        subscriber_priority: u8,
+        group_order: GroupOrder,
+    ) {
+        let msg = Subscribe::builder()
+            .id(track.request_id().clone())
+            .namespace(track.key().namespace.clone())
+            .name(track.key().name.clone())
+            .sub_prio(subscriber_priority)
+            .group_order(group_order)
+            .forward(true)
+            .with_largest_object()
+            .build();
+
+        if let Err(err) = track
+            .publisher()
+            .control_stream()
+            .send(ControlMessage::Subscribe(msg))
+            .await
+        {
+            warn!(%err, "failed to subscribe upstream");
+            track
+                .publisher()
+                .upstream
+                .remove(&track.request_id().number::<u64>());
+            self.reject_subscribers(
+                track,
+                error_code::Subscribe::InternalError,
+                ReasonPhrase::new("failed to subscribe upstream"),
+            )
+            .await;
+            return;
+        }
+
+        let relay = self.clone();
+        let track = track.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(relay.timeouts.request).await;
+            relay.expire_upstream(&track).await;
+        });
+    }
+
+    /// Rejects the Subscribers of `track` if its
+    /// publisher has not responded to the
+    /// Subscribe yet.
+    ///
+    /// The session of the publisher stays open, a
+    /// late response is ignored.
+    async fn expire_upstream(&self, track: &Arc<Track>) {
+        if track.upstream_alias().is_some() {
+            return;
+        }
+        let publisher = track.publisher();
+        let pending = publisher
+            .upstream
+            .remove_if(&track.request_id().number::<u64>(), |_, other| {
+                Arc::ptr_eq(other, track)
+            });
+        if pending.is_none() {
+            return;
+        }
+
+        warn!(
+            addr = %publisher.addr(),
+            request_id = ?track.request_id(),
+            "publisher took too long to respond to subscribe"
+        );
+        self.reject_subscribers(
+            track,
+            error_code::Subscribe::Timeout,
+            ReasonPhrase::new("the publisher took too long to respond"),
+        )
+        .await;
+    }
+
+    /// Asks the publisher of `track` to pause or
+    /// resume forwarding and to use a new priority
+    /// once the Subscribers changed either.
+    ///
+    /// The upstream Subscription starts after the
+    /// largest cached Location from then on.
+    async fn update_upstream(&self, track: &Track) {
+        if let Some((forward, priority)) = track.upstream_update() {
+            let range = track.advance_upstream(self.cache.largest(track.key()).as_ref());
+            self.send_upstream_update(track, forward, priority, range).await;
+        }
+    }
+
+    /// Refreshes the upstream Subscription of
+    /// `track` every `period` for as long as the
+    /// Relay keeps it.
+    async fn refresh_upstream(self: Arc<Self>, track: Arc<Track>, period: Duration) {
+        let mut refresh = tokio::time::interval_at(Instant::now() + period, period);
+        loop {
+            refresh.tick().await;
+            let request_id = track.request_id().number::<u64>();
+            let current = track
+                .publisher()
+                .upstream
+                .get(&request_id)
+                .is_some_and(|upstream| Arc::ptr_eq(&upstream, &track));
+            let (true, Some((forward, priority))) = (current, track.upstream()) else {
+                return;
+            };
+
+            // only keeps it alive, it is not changed
+            self.send_upstream_update(&track, forward, priority, track.upstream_range()).await;
+        }
+    }
+
+    /// Sends a [SubscribeUpdate] with `forward`,
+    /// `priority` and the start and end of `range`
+    /// for the upstream Subscription of `track`.
+    async fn send_upstream_update(
+        &self,
+        track: &Track,
+        forward: Forward,
+        priority: u8,
+        (start, end_group): UpstreamRange,
+    ) {
+        let (Ok(group), Ok(object), Ok(end_group)) = (
+            <x!(i)>::try_from(start.group::<u64>()),
+            <x!(i)>::try_from(start.object::<u64>()),
+            <x!(i)>::try_from(end_group),
+        ) else {
+            return;
+        };
+
+        let msg = SubscribeUpdate::builder()
+            .id(track.request_id().clone())
+            .start(group, object)
+            .end_group(end_group)
+            .subscriber_priority(priority)
+            .forward(forward)
+            .build();
+        let publisher = track.publisher();
+        debug!(addr = %publisher.addr(), request_id = ?track.request_id(), ?forward, "updating upstream subscription");
+        if let Err(err) = publisher
+            .control_stream()
+            .send(ControlMessage::SubscribeUpdate(msg))
+            .await
+        {
+            warn!(%err, addr = %publisher.addr(), "failed to update upstream subscription");
+        }
+    }
+
+    /// Ends the upstream Subscription of `track`.
+    async fn unsubscribe_upstream(&self, track: &Track) {
+        let publisher = track.publisher();
+        publisher
+            .upstream
+            .remove(&track.request_id().number::<u64>());
+        if let Some(alias) = track.upstream_alias() {
+            publisher.published.remove(&alias.number::<u64>());
+        }
+
+        let msg = ControlMessage::Unsubscribe(Unsubscribe::new(track.request_id().clone()));
+        if let Err(err) = publisher.control_stream().send(msg).await {
+            warn!(%err, addr = %publisher.addr(), "failed to unsubscribe upstream");
+        }
+    }
+
+    /// Removes `track` and rejects all its
+    /// Subscribers with `code`.
+    async fn reject_subscribers(
+        &self,
+        track: &Arc<Track>,
+        code: error_code::Subscribe,
+        reason: ReasonPhrase,
+    ) {
+        self.remove_track(track);
+
+        for subscriber in track.close() {
+            subscriber
+                .peer
+                .downstream
+                .remove(&subscriber.request_id.number::<u64>());
+
+            let msg = ControlMessage::SubscribeError(SubscribeError::new(
+                subscriber.request_id.clone(),
+                code,
+                reason.clone(),
+            ));
+            if let Err(err) = subscriber.peer.control_stream().send(msg).await {
+                warn!(%err, addr = %subscriber.peer.addr(), "failed to reject subscriber");
+            }
+        }
+    }
+
+    /// Removes `track` and ends all its Subscribers
+    /// with a [PublishDone] once its publisher
+    /// ended it.
+    async fn end_track(
+        &self,
+        track: &Arc<Track>,
+        code: error_code::PublishDone,
+        reason: ReasonPhrase,
+    ) {
+        self.remove_track(track);
+
+        for subscriber in track.close() {
+            subscriber
+                .peer
+                .downstream
+                .remove(&subscriber.request_id.number::<u64>());
+            debug!(
+                addr = %subscriber.peer.addr(),
+                request_id = ?subscriber.request_id,
+                ?code,
+                "track ended"
+            );
+            publish_done(&subscriber, code, reason.clone()).await;
+        }
+    }
+
+    fn namespaces(&self) -> MutexGuard<'_, NamespaceTrie<SocketAddr>> {
+        self.namespaces.lock().expect("namespace lock poisoned")
+    }
+
+    /// Removes `track` from the registry unless it
+    /// was already replaced.
+    fn remove_track(&self, track: &Arc<Track>) {
+        self.tracks
+            .remove_if(track.key(), |_, other| Arc::ptr_eq(other, track));
+    }
+}
+
+/// Tells `subscriber` its Subscription ended,
+/// with the number of streams opened to it.
+async fn publish_done<R>(subscriber: &Subscriber, code: error_code::PublishDone, reason: R)
+where
+    R: Into<ReasonPhrase>,
+{
+    let msg = <x!(i)>::try_from(subscriber.stream_count()).map(|stream_count| {
+        PublishDone::new(subscriber.request_id.clone(), code, stream_count, reason)
+    });
+    let Ok(msg) = msg else {
+        return;
+    };
+
+    let peer = &subscriber.peer;
+    if let Err(err) = peer
+        .control_stream()
+        .send(ControlMessage::PublishDone(msg))
+        .await
+    {
+        warn!(%err, addr = %peer.addr(), "failed to end subscription");
+    }
+}
+
+/// Rejects the TrackStatus `peer` sent with
+/// `request_id`.
+async fn reject_track_status<R>(
+    peer: &Peer,
+    request_id: x!(i),
+    code: error_code::TrackStatus,
+    reason: R,
+) where
+    R: Into<ReasonPhrase>,
+{
+    let msg = ControlMessage::TrackStatusError(TrackStatusError::new(request_id, code, reason));
+    if let Err(err) = peer.control_stream().send(msg).await {
+        warn!(%err, addr = %peer.addr(), "failed to reject track status");
+    }
+}
+
+/// Sends the cached `objects` within `range` on
+/// a new Fetch stream to `peer`.
+async fn serve_fetch(
+    peer: &Peer,
+    request_id: x!(i),
+    objects: &[FetchObject],
+    range: &FetchRange,
+    order: GroupOrder,
+) {
+    let mut writer = match FetchWriter::open(peer.transport(), request_id).await {
+        Ok(writer) => writer,
+        Err(err) => {
+            warn!(%err, addr = %peer.addr(), "failed to open fetch stream");
+            return;
+        }
+    };
+
+    if let Err(err) = writer.serve(objects, range, order).await {
+        warn!(%err, addr = %peer.addr(), "failed to serve fetch");
+        return;
+    }
+    if let Err(err) = writer.finish().await {
+        debug!(%err, addr = %peer.addr(), "failed to finish fetch stream");
+    }
+}
+
+impl fmt::Debug for Relay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Relay")
+            .field("peers", &self.peers.len())
+            .field("namespaces", &self.namespaces().len())
+            .field("tracks", &self.tracks.len())
+            .field("cached_bytes", &self.cache.bytes())
+            .field("authorizer", &self.authorizer)
+            .field("upstreams", &self.upstreams.len())
+            .finish()
+    }
+}
@@ -0,0 +1,282 @@
+use {
+    super::{FetchReply, NamespaceTrie, Track},
+    crate::{
+        ControlStream,
+        transport::Connection,
+        types::{
+            Namespace, RequestId, RequestWindow, Token, TokenCache, error::RequestIdError,
+            error_code::Termination, message::MaxRequestId,
+        },
+    },
+    core::net::SocketAddr,
+    dashmap::DashMap,
+    std::sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    tokio::{
+        sync::{mpsc, oneshot},
+        task::AbortHandle,
+    },
+    varint::x,
+};
+
+/// ## Peer
+///
+/// The state the [Relay](super::Relay) keeps
+/// for a single connected session.
+pub(crate) struct Peer {
+    addr: SocketAddr,
+    transport: Arc<Connection>,
+    control_stream: Arc<ControlStream>,
+    /// Whether the Relay connected to this peer
+    /// as client.
+    upstream_server: bool,
+    request_id: Mutex<RequestId>,
+    requests: Mutex<RequestWindow>,
+    /// Authorization Tokens this peer registered.
+    tokens: Mutex<TokenCache>,
+    /// The maximum Request ID the last
+    /// RequestsBlocked was sent for.
+    blocked_at: AtomicU64,
+    next_alias: AtomicU64,
+    /// Namespace prefixes this peer subscribed to
+    /// by the Request ID of the SubscribeNamespace.
+    namespace_prefixes: Mutex<NamespaceTrie<x!(i)>>,
+
+    /// Subscriptions the Relay sent to this peer
+    /// by Request ID.
+    pub upstream: DashMap<u64, Arc<Track>>,
+
+    /// Tracks published by this peer by the
+    /// Track Alias this peer assigned.
+    pub published: DashMap<u64, Arc<Track>>,
+
+    /// Subscriptions of this peer by Request ID.
+    pub downstream: DashMap<u64, Arc<Track>>,
+
+    /// Fetches of this peer being served by
+    /// Request ID.
+    pub fetches: DashMap<u64, AbortHandle>,
+
+    /// Namespaces the Relay published to this
+    /// peer with the Request ID of the
+    /// PublishNamespace.
+    pub announced: DashMap<Namespace, x!(i)>,
+
+    /// TrackStatus requests the Relay forwarded
+    /// to this peer by Request ID, with the peer
+    /// asking and the Request ID it used.
+    pub track_statuses: DashMap<u64, (Arc<Peer>, x!(i))>,
+
+    /// Fetches the Relay forwarded to this peer by
+    /// Request ID, receiving its response and stream.
+    pub forwarded_fetches: DashMap<u64, mpsc::UnboundedSender<FetchReply>>,
+}
+
+impl Peer {
+    /// Creates the Peer of a client session.
+    pub fn new(transport: Arc<Connection>, control_stream: Arc<ControlStream>) -> Self {
+        let tokens = TokenCache::new(control_stream.auth_token_cache_size());
+        Self::with_roles(
+            transport,
+            control_stream,
+            (RequestId::new_server(), RequestWindow::new_client()),
+            tokens,
+        )
+    }
+
+    /// Creates the Peer of a session the Relay
+    /// established to an [Upstream](crate::Upstream)
+    /// server as client.
+    pub fn upstream_server(transport: Arc<Connection>, control_stream: Arc<ControlStream>) -> Self {
+        Self {
+            upstream_server: true,
+            ..Self::with_roles(
+                transport,
+                control_stream,
+                (RequestId::new_client(), RequestWindow::new_server()),
+                TokenCache::default(),
+            )
+        }
+    }
+
+    fn with_roles(
+        transport: Arc<Connection>,
+        control_stream: Arc<ControlStream>,
+        (mut request_id, requests): (RequestId, RequestWindow),
+        tokens: TokenCache,
+    ) -> Self {
+        request_id.set_max(control_stream.peer_max_request_id());
+
+        Self {
+            addr: transport.remote_addr(),
+            transport,
+            control_stream,
+            upstream_server: false,
+            request_id: Mutex::new(request_id),
+            requests: Mutex::new(requests),
+            tokens: Mutex::new(tokens),
+            blocked_at: AtomicU64::new(u64::MAX),
+            next_alias: AtomicU64::new(0),
+            namespace_prefixes: Mutex::default(),
+            upstream: DashMap::new(),
+            published: DashMap::new(),
+            downstream: DashMap::new(),
+            fetches: DashMap::new(),
+            announced: DashMap::new(),
+            track_statuses: DashMap::new(),
+            forwarded_fetches: DashMap::new(),
+        }
+    }
+
+    /// The remote address of this peer.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn transport(&self) -> &Connection {
+        &self.transport
+    }
+
+    pub fn control_stream(&self) -> &ControlStream {
+        &self.control_stream
+    }
+
+    /// Whether the Relay connected to this peer
+    /// as client.
+    pub fn is_upstream_server(&self) -> bool {
+        self.upstream_server
+    }
+
+    /// Returns the Request ID for the next Request
+    /// the Relay sends to this peer.
+    pub fn next_request_id(&self) -> Result<x!(i), RequestIdError> {
+        self.request_id().get()
+    }
+
+    /// Whether the Relay sent a Request with
+    /// `request_id` to this peer.
+    pub fn is_issued(&self, request_id: u64) -> bool {
+        self.request_id().is_issued(request_id)
+    }
+
+    /// Raises the maximum Request ID this peer
+    /// allows the Relay to use.
+    ///
+    /// Returns false when `request_id` does not
+    /// exceed the previous maximum.
+    pub fn raise_max_request_id(&self, request_id: u64) -> bool {
+        self.request_id().set_max(request_id)
+    }
+
+    /// Whether a RequestsBlocked is due since the
+    /// Relay is blocked at `max` for the first time.
+    pub fn report_blocked(&self, max: u64) -> bool {
+        self.blocked_at.swap(max, Ordering::Relaxed) != max
+    }
+
+    /// Accepts the Request ID of a new Request
+    /// sent by this peer.
+    ///
+    /// Returns the [MaxRequestId] granting this
+    /// peer new Request IDs, if due, or the code
+    /// to close the session with.
+    pub fn accept_request_id(&self, request_id: u64) -> Result<Option<MaxRequestId>, Termination> {
+        let mut requests = self.requests();
+        requests.accept(request_id)?;
+        Ok(requests.grant().map(MaxRequestId::new))
+    }
+
+    /// Grants this peer new Request IDs unless
+    /// enough are left.
+    pub fn grant_request_ids(&self) -> Option<MaxRequestId> {
+        self.requests().grant().map(MaxRequestId::new)
+    }
+
+    /// Applies the Authorization `token` sent by
+    /// this peer to the Tokens it registered.
+    ///
+    /// Returns the Token it refers to, if any,
+    /// or the code to close the session with.
+    pub fn apply_token(&self, token: &Token) -> Result<Option<Token>, Termination> {
+        self.tokens().apply(token)
+    }
+
+    /// Returns the Track Alias for the next
+    /// Subscription of this peer.
+    pub fn next_alias(&self) -> x!(i) {
+        let alias = self.next_alias.fetch_add(1, Ordering::Relaxed);
+        <x!(i)>::try_from(alias).expect("track aliases are exhausted")
+    }
+
+    /// Subscribes this peer to the Namespaces
+    /// starting with `prefix`.
+    ///
+    /// Returns false when `prefix` overlaps with
+    /// a prefix this peer already subscribed to.
+    pub fn subscribe_namespace(&self, prefix: &Namespace, request_id: x!(i)) -> bool {
+        let mut prefixes = self.namespace_prefixes();
+        if prefixes.overlaps(prefix) {
+            return false;
+        }
+        prefixes.insert(prefix, request_id);
+        true
+    }
+
+    /// Ends the subscription to the Namespaces
+    /// starting with `prefix`.
+    ///
+    /// Returns false when this peer did not
+    /// subscribe to `prefix`.
+    pub fn unsubscribe_namespace(&self, prefix: &Namespace) -> bool {
+        self.namespace_prefixes().remove(prefix).is_some()
+    }
+
+    /// Whether this peer subscribed to a prefix
+    /// `namespace` starts with.
+    pub fn subscribes_to(&self, namespace: &Namespace) -> bool {
+        self.namespace_prefixes()
+            .longest_prefix(namespace)
+            .is_some()
+    }
+
+    /// Serves the Fetch this peer sent with
+    /// `request_id` with `serve` in a task, which
+    /// a FetchCancel aborts.
+    pub fn spawn_fetch<F>(self: &Arc<Self>, request_id: u64, serve: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        // registered before it runs, a task done
+        // right away must not leave its handle
+        let (start, started) = oneshot::channel();
+        let peer = self.clone();
+        let task = tokio::spawn(async move {
+            if started.await.is_ok() {
+                serve.await;
+            }
+            peer.fetches.remove(&request_id);
+        });
+        self.fetches.insert(request_id, task.abort_handle());
+        let _ = start.send(());
+    }
+
+    fn namespace_prefixes(&self) -> MutexGuard<'_, NamespaceTrie<x!(i)>> {
+        self.namespace_prefixes
+            .lock()
+            .expect("namespace prefix lock poisoned")
+    }
+
+    fn request_id(&self) -> MutexGuard<'_, RequestId> {
+        self.request_id.lock().expect("request ID lock poisoned")
+    }
+
+    fn requests(&self) -> MutexGuard<'_, RequestWindow> {
+        self.requests.lock().expect("request window lock poisoned")
+    }
+
+    fn tokens(&self) -> MutexGuard<'_, TokenCache> {
+        self.tokens.lock().expect("token cache lock poisoned")
+    }
+}
@@ -0,0 +1,460 @@
+use {
+    super::Peer,
+    crate::types::{
+        FilterType, Forward, Location, Name, Namespace,
+        config::DEFAULT_PRIORITY,
+        message::{SubscribeOk, SubscribeUpdate},
+    },
+    core::{net::SocketAddr, time::Duration},
+    std::{
+        collections::HashMap,
+        sync::{
+            Arc, Mutex, MutexGuard,
+            atomic::{AtomicU64, Ordering},
+        },
+    },
+    tokio::{sync::watch, time::Instant},
+    varint::{VarIntNumber, x},
+};
+
+/// Identifies a Track by its full name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TrackKey {
+    pub namespace: Namespace,
+    pub name: Name,
+}
+
+impl TrackKey {
+    pub fn new(namespace: Namespace, name: Name) -> Self {
+        Self { namespace, name }
+    }
+}
+
+/// Identifies a Subscriber by the address of
+/// its peer and its Request ID.
+pub(crate) type SubscriberKey = (SocketAddr, u64);
+
+/// ## Subscriber
+///
+/// A downstream Subscription to a [Track].
+#[derive(Clone)]
+pub(crate) struct Subscriber {
+    /// The Request ID of the Subscribe.
+    pub request_id: x!(i),
+
+    /// The Track Alias the Relay assigned for
+    /// this Subscriber.
+    pub alias: x!(i),
+
+    /// Where the Subscription starts.
+    pub filter: FilterType,
+
+    /// The first Location forwarded, None until
+    /// the Subscriber of a relative filter is
+    /// accepted.
+    pub start: Option<Location>,
+
+    /// The last Group forwarded, None while the
+    /// Subscription is open-ended.
+    pub end_group: Option<u64>,
+
+    pub priority: u8,
+
+    /// Whether Objects are forwarded.
+    pub forward: Forward,
+
+    /// Number of Subgroup streams opened to the
+    /// Subscriber, reported in its PublishDone.
+    pub streams: Arc<AtomicU64>,
+
+    /// The Largest Location reported to the
+    /// Subscriber, a Joining Fetch ends with it.
+    pub largest: Option<Location>,
+
+    /// When the Subscription was accepted or last
+    /// updated, it expires relative to that.
+    pub refreshed: Instant,
+
+    pub peer: Arc<Peer>,
+}
+
+impl Subscriber {
+    pub fn key(&self) -> SubscriberKey {
+        (self.peer.addr(), self.request_id.number())
+    }
+
+    /// Whether the Subscriber receives Objects of
+    /// the Group already being forwarded.
+    pub fn joins_group(&self) -> bool {
+        self.filter != FilterType::NextGroupStart
+    }
+
+    /// Whether the Subscription ends with `group`.
+    pub fn ends_with(&self, group: u64) -> bool {
+        self.end_group == Some(group)
+    }
+
+    /// Counts a Subgroup stream opened to this
+    /// Subscriber.
+    pub fn stream_opened(&self) {
+        self.streams.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of Subgroup streams opened to this
+    /// Subscriber so far.
+    pub fn stream_count(&self) -> u64 {
+        self.streams.load(Ordering::Relaxed)
+    }
+
+    /// Whether the Object at `location` is forwarded
+    /// to this Subscriber.
+    pub fn accepts(&self, location: &Location) -> bool {
+        self.forward == Forward::Enabled
+            && self.start.as_ref().is_none_or(|start| start <= location)
+            && self
+                .end_group
+                .is_none_or(|end_group| location.group::<u64>() <= end_group)
+    }
+
+    /// Narrows the Subscription with `update`, as
+    /// checked by [SubscribeUpdate::narrows].
+    pub fn update(&mut self, update: &SubscribeUpdate) -> Result<(), &'static str> {
+        update.narrows(self.start.as_ref(), self.end_group)?;
+
+        self.start = Some(update.start_location.clone());
+        self.end_group = update.final_group();
+        self.priority = update.subscriber_priority.number();
+        self.forward = update.forward;
+        self.refreshed = Instant::now();
+        Ok(())
+    }
+
+    /// Builds the [SubscribeOk] for this Subscriber
+    /// from the one the publisher sent.
+    pub fn subscribe_ok(&self, upstream: &SubscribeOk) -> SubscribeOk {
+        SubscribeOk {
+            request_id: self.request_id.clone(),
+            alias: self.alias.clone(),
+            ..upstream.clone()
+        }
+    }
+}
+
+/// ## Track
+///
+/// A Track relayed from its publisher to any
+/// number of Subscribers.
+///
+/// The Relay holds a single upstream Subscription
+/// per Track, shared by all its Subscribers.
+///
+/// Tracks pushed by a [Publish](crate::types::message::Publish)
+/// stay relayed without any Subscribers.
+pub(crate) struct Track {
+    key: TrackKey,
+    publisher: Arc<Peer>,
+    request_id: x!(i),
+    pushed: bool,
+    inner: Mutex<TrackState>,
+    /// Number of Subgroup streams of the publisher
+    /// forwarded completely.
+    forwarded: watch::Sender<u64>,
+}
+
+/// The forwarding and Subscriber Priority of
+/// the upstream Subscription.
+pub(crate) type Upstream = (Forward, u8);
+
+/// The start and the end Group plus 1, 0 while
+/// open-ended, of the upstream Subscription.
+pub(crate) type UpstreamRange = (Location, u64);
+
+struct TrackState {
+    /// Some once the publisher accepted the
+    /// upstream Subscription.
+    ok: Option<SubscribeOk>,
+    subscribers: HashMap<SubscriberKey, Subscriber>,
+    /// Number of Subscribers added so far.
+    joined: u64,
+    /// As last requested from the publisher.
+    upstream: Upstream,
+    /// As last requested from the publisher, an
+    /// update must not widen it.
+    range: UpstreamRange,
+}
+
+impl TrackState {
+    fn new(priority: u8) -> Self {
+        Self {
+            ok: None,
+            subscribers: HashMap::new(),
+            joined: 0,
+            upstream: (Forward::Enabled, priority),
+            range: (Location::new(0u8, 0u8), 0),
+        }
+    }
+
+    /// Marks the upstream Subscription as active
+    /// with `ok`, it starts after the Largest
+    /// Location the publisher reported.
+    fn activate(&mut self, ok: SubscribeOk) {
+        if let Some(start) = ok.largest_location.as_ref().and_then(after) {
+            self.range.0 = start;
+        }
+        self.ok = Some(ok);
+    }
+}
+
+/// The Location of the Object following `location`.
+fn after(location: &Location) -> Option<Location> {
+    let group = <x!(i)>::try_from(location.group::<u64>()).ok()?;
+    let object = <x!(i)>::try_from(location.object::<u64>().checked_add(1)?).ok()?;
+    Some(Location::new(group, object))
+}
+
+impl Track {
+    /// Creates a Track subscribed from `publisher`
+    /// with the upstream `request_id` and
+    /// `priority`.
+    pub fn new(key: TrackKey, publisher: Arc<Peer>, request_id: x!(i), priority: u8) -> Self {
+        Self {
+            key,
+            publisher,
+            request_id,
+            pushed: false,
+            inner: Mutex::new(TrackState::new(priority)),
+            forwarded: watch::Sender::new(0),
+        }
+    }
+
+    /// Creates an active Track pushed by `publisher`
+    /// with the [Publish](crate::types::message::Publish)
+    /// described by `ok`.
+    pub fn pushed(key: TrackKey, publisher: Arc<Peer>, ok: SubscribeOk) -> Self {
+        let request_id = ok.request_id.clone();
+        let mut state = TrackState::new(DEFAULT_PRIORITY);
+        state.activate(ok);
+
+        Self {
+            key,
+            publisher,
+            request_id,
+            pushed: true,
+            inner: Mutex::new(state),
+            forwarded: watch::Sender::new(0),
+        }
+    }
+
+    pub fn key(&self) -> &TrackKey {
+        &self.key
+    }
+
+    /// The peer publishing this Track.
+    pub fn publisher(&self) -> &Arc<Peer> {
+        &self.publisher
+    }
+
+    /// The Request ID of the upstream Subscription.
+    pub fn request_id(&self) -> &x!(i) {
+        &self.request_id
+    }
+
+    /// Whether the publisher pushed this Track
+    /// instead of the Relay subscribing to it.
+    pub fn is_pushed(&self) -> bool {
+        self.pushed
+    }
+
+    /// The Track Alias the publisher assigned,
+    /// once the upstream Subscription is active.
+    pub fn upstream_alias(&self) -> Option<x!(i)> {
+        self.state().ok.as_ref().map(|ok| ok.alias.clone())
+    }
+
+    /// The [SubscribeOk] describing the Track, once
+    /// the upstream Subscription is active.
+    pub fn subscribe_ok(&self) -> Option<SubscribeOk> {
+        self.state().ok.clone()
+    }
+
+    /// Adds `subscriber`.
+    ///
+    /// Returns the [SubscribeOk] to send to it when
+    /// the upstream Subscription is already active.
+    pub fn add_subscriber(&self, subscriber: Subscriber) -> Option<SubscribeOk> {
+        let mut state = self.state();
+        let ok = state.ok.as_ref().map(|ok| subscriber.subscribe_ok(ok));
+        state.subscribers.insert(subscriber.key(), subscriber);
+        state.joined += 1;
+        ok
+    }
+
+    /// Number of Subscribers added so far, to
+    /// detect new ones.
+    pub fn joined(&self) -> u64 {
+        self.state().joined
+    }
+
+    /// Records the Largest Location reported to
+    /// the Subscriber with `key` and resolves
+    /// where it starts.
+    pub fn set_largest(&self, key: &SubscriberKey, largest: Option<Location>) {
+        if let Some(subscriber) = self.state().subscribers.get_mut(key) {
+            // relative filters start after it, updates
+            // must not move the start before that
+            if subscriber.start.is_none() {
+                subscriber.start = subscriber.filter.resolve_start(None, largest.as_ref());
+            }
+            subscriber.largest = largest;
+        }
+    }
+
+    /// The Largest Location reported to the
+    /// Subscriber with `key`, if any.
+    pub fn largest_of(&self, key: &SubscriberKey) -> Option<Location> {
+        self.state().subscribers.get(key)?.largest.clone()
+    }
+
+    /// Restarts the expiry of the Subscriber with
+    /// `key`.
+    pub fn refresh_subscriber(&self, key: &SubscriberKey) {
+        if let Some(subscriber) = self.state().subscribers.get_mut(key) {
+            subscriber.refreshed = Instant::now();
+        }
+    }
+
+    /// The Subscriber with `key`, if it is still
+    /// subscribed.
+    pub fn subscriber(&self, key: &SubscriberKey) -> Option<Subscriber> {
+        self.state().subscribers.get(key).cloned()
+    }
+
+    /// Counts a Subgroup stream of the publisher
+    /// that was forwarded completely.
+    pub fn stream_forwarded(&self) {
+        self.forwarded.send_modify(|count| *count += 1);
+    }
+
+    /// Waits until `count` Subgroup streams of the
+    /// publisher were forwarded.
+    ///
+    /// Returns false if that took longer than
+    /// `timeout`.
+    pub async fn forwarded(&self, count: u64, timeout: Duration) -> bool {
+        let mut forwarded = self.forwarded.subscribe();
+        let all = forwarded.wait_for(|forwarded| *forwarded >= count);
+        matches!(tokio::time::timeout(timeout, all).await, Ok(Ok(_)))
+    }
+
+    /// Applies `update` to the Subscriber with `key`.
+    pub fn update_subscriber(
+        &self,
+        key: &SubscriberKey,
+        update: &SubscribeUpdate,
+    ) -> Result<(), &'static str> {
+        match self.state().subscribers.get_mut(key) {
+            Some(subscriber) => subscriber.update(update),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the forwarding and priority to
+    /// request from the publisher if they changed
+    /// since the last request.
+    ///
+    /// Objects are forwarded while any Subscriber
+    /// wants them, or without Subscribers to keep
+    /// the [Cache](super::Cache) filled, with the
+    /// highest priority of all Subscribers.
+    pub fn upstream_update(&self) -> Option<Upstream> {
+        let mut state = self.state();
+        state.ok.as_ref()?;
+
+        let forward = state.subscribers.is_empty()
+            || state
+                .subscribers
+                .values()
+                .any(|subscriber| subscriber.forward == Forward::Enabled);
+        let priority = state
+            .subscribers
+            .values()
+            .map(|subscriber| subscriber.priority)
+            .min()
+            .unwrap_or(state.upstream.1);
+
+        let upstream = (Forward::from(forward), priority);
+        if upstream == state.upstream {
+            return None;
+        }
+        state.upstream = upstream;
+        Some(upstream)
+    }
+
+    /// The forwarding and priority last requested
+    /// from the publisher, once the upstream
+    /// Subscription is active.
+    pub fn upstream(&self) -> Option<Upstream> {
+        let state = self.state();
+        state.ok.as_ref()?;
+        Some(state.upstream)
+    }
+
+    /// The start and end of the upstream
+    /// Subscription as last requested.
+    pub fn upstream_range(&self) -> UpstreamRange {
+        self.state().range.clone()
+    }
+
+    /// Moves the start of the upstream Subscription
+    /// after `largest` unless it already starts
+    /// beyond it.
+    ///
+    /// Returns the start and end to request, the
+    /// start never moves backwards.
+    pub fn advance_upstream(&self, largest: Option<&Location>) -> UpstreamRange {
+        let mut state = self.state();
+        if let Some(start) = largest.and_then(after)
+            && start > state.range.0
+        {
+            state.range.0 = start;
+        }
+        state.range.clone()
+    }
+
+    /// Removes the Subscriber with `key`.
+    ///
+    /// Returns the number of remaining Subscribers.
+    pub fn remove_subscriber(&self, key: &SubscriberKey) -> usize {
+        let mut state = self.state();
+        state.subscribers.remove(key);
+        state.subscribers.len()
+    }
+
+    /// Marks the upstream Subscription as active.
+    ///
+    /// Returns all Subscribers that were waiting
+    /// for it.
+    pub fn activate(&self, ok: SubscribeOk) -> Vec<Subscriber> {
+        let mut state = self.state();
+        state.activate(ok);
+        state.subscribers.values().cloned().collect()
+    }
+
+    /// Removes and returns all Subscribers.
+    pub fn close(&self) -> Vec<Subscriber> {
+        self.state()
+            .subscribers
+            .drain()
+            .map(|(_, subscriber)| subscriber)
+            .collect()
+    }
+
+    /// Returns the current Subscribers.
+    pub fn subscribers(&self) -> Vec<Subscriber> {
+        self.state().subscribers.values().cloned().collect()
+    }
+
+    fn state(&self) -> MutexGuard<'_, TrackState> {
+        self.inner.lock().expect("track lock poisoned")
+    }
+}
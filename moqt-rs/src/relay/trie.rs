@@ -0,0 +1,259 @@
+use {crate::types::Namespace, std::collections::BTreeMap, varint::x};
+
+/// ## Namespace Trie
+///
+/// Values by [Namespace], to look up the
+/// Namespaces by their Tuple field prefixes.
+pub(crate) struct NamespaceTrie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+struct Node<V> {
+    value: Option<V>,
+    children: BTreeMap<x!(b), Node<V>>,
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Self {
+            value: None,
+            children: BTreeMap::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.value.is_none() && self.children.is_empty()
+    }
+
+    /// Collects the values of this Node and all
+    /// Nodes below it, `fields` leading to it.
+    fn collect<'a>(&'a self, fields: &mut Vec<x!(b)>, out: &mut Vec<(Namespace, &'a V)>) {
+        if let Some(value) = &self.value {
+            out.push((Namespace::from(fields.clone()), value));
+        }
+        for (field, child) in &self.children {
+            fields.push(field.clone());
+            child.collect(fields, out);
+            fields.pop();
+        }
+    }
+
+    /// Removes the value of the Node `fields` lead
+    /// to, pruning Nodes left empty.
+    fn remove(&mut self, fields: &[x!(b)]) -> Option<V> {
+        let Some((field, rest)) = fields.split_first() else {
+            return self.value.take();
+        };
+
+        let child = self.children.get_mut(field)?;
+        let value = child.remove(rest);
+        if child.is_empty() {
+            self.children.remove(field);
+        }
+        value
+    }
+
+    /// Removes the values `remove` matches from
+    /// this Node and all Nodes below it.
+    fn extract<F>(&mut self, fields: &mut Vec<x!(b)>, remove: &mut F, out: &mut Vec<(Namespace, V)>)
+    where
+        F: FnMut(&Namespace, &V) -> bool,
+    {
+        if let Some(value) = self.value.take() {
+            let namespace = Namespace::from(fields.clone());
+            if remove(&namespace, &value) {
+                out.push((namespace, value));
+            } else {
+                self.value = Some(value);
+            }
+        }
+        for (field, child) in &mut self.children {
+            fields.push(field.clone());
+            child.extract(fields, remove, out);
+            fields.pop();
+        }
+        self.children.retain(|_, child| !child.is_empty());
+    }
+}
+
+impl<V> NamespaceTrie<V> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    /// Number of Namespaces with a value.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Sets the value of `namespace`, returning
+    /// the previous one.
+    pub fn insert(&mut self, namespace: &Namespace, value: V) -> Option<V> {
+        let node = namespace
+            .fields()
+            .iter()
+            .fold(&mut self.root, |node, field| {
+                node.children.entry(field.clone()).or_insert_with(Node::new)
+            });
+
+        let old = node.value.replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Returns the value of `namespace`.
+    pub fn get(&self, namespace: &Namespace) -> Option<&V> {
+        self.node(namespace)?.value.as_ref()
+    }
+
+    /// Removes the value of `namespace`.
+    pub fn remove(&mut self, namespace: &Namespace) -> Option<V> {
+        let value = self.root.remove(namespace.fields());
+        if value.is_some() {
+            self.len -= 1;
+        }
+        value
+    }
+
+    /// Returns the value of the longest Namespace
+    /// `namespace` starts with.
+    pub fn longest_prefix(&self, namespace: &Namespace) -> Option<&V> {
+        self.prefixes(namespace).pop()
+    }
+
+    /// Returns the values of all Namespaces
+    /// `namespace` starts with, shortest first.
+    pub fn prefixes(&self, namespace: &Namespace) -> Vec<&V> {
+        let mut node = &self.root;
+        let mut values: Vec<&V> = node.value.iter().collect();
+
+        for field in namespace.fields() {
+            match node.children.get(field) {
+                Some(child) => node = child,
+                None => break,
+            }
+            values.extend(&node.value);
+        }
+        values
+    }
+
+    /// Returns all Namespaces starting with
+    /// `prefix` and their values.
+    pub fn starting_with(&self, prefix: &Namespace) -> Vec<(Namespace, &V)> {
+        let mut out = Vec::new();
+        if let Some(node) = self.node(prefix) {
+            node.collect(&mut prefix.fields().to_vec(), &mut out);
+        }
+        out
+    }
+
+    /// Returns all Namespaces and their values.
+    pub fn entries(&self) -> Vec<(Namespace, &V)> {
+        let mut out = Vec::new();
+        self.root.collect(&mut Vec::new(), &mut out);
+        out
+    }
+
+    /// Whether a Namespace starts with `namespace`
+    /// or `namespace` starts with one.
+    pub fn overlaps(&self, namespace: &Namespace) -> bool {
+        !self.prefixes(namespace).is_empty() || self.node(namespace).is_some()
+    }
+
+    /// Removes and returns all Namespaces and their
+    /// values `remove` returns true for.
+    pub fn extract_if<F>(&mut self, mut remove: F) -> Vec<(Namespace, V)>
+    where
+        F: FnMut(&Namespace, &V) -> bool,
+    {
+        let mut out = Vec::new();
+        self.root.extract(&mut Vec::new(), &mut remove, &mut out);
+        self.len -= out.len();
+        out
+    }
+
+    fn node(&self, namespace: &Namespace) -> Option<&Node<V>> {
+        namespace
+            .fields()
+            .iter()
+            .try_fold(&self.root, |node, field| node.children.get(field))
+    }
+}
+
+impl<V> Default for NamespaceTrie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trie() -> NamespaceTrie<u8> {
+        let mut trie = NamespaceTrie::new();
+        trie.insert(&["chat"].into(), 1);
+        trie.insert(&["chat", "lobby", "alice"].into(), 2);
+        trie.insert(&["chat", "lobby", "bob"].into(), 3);
+        trie.insert(&["video"].into(), 4);
+        trie
+    }
+
+    #[test]
+    fn lookup_test() {
+        let trie = trie();
+        assert_eq!(trie.len(), 4);
+        assert_eq!(trie.entries().len(), 4);
+        assert_eq!(trie.get(&["chat", "lobby", "bob"].into()), Some(&3));
+        assert_eq!(trie.get(&["chat", "lobby"].into()), None);
+
+        let namespace = ["chat", "lobby", "alice", "cam"].into();
+        assert_eq!(trie.prefixes(&namespace), [&1, &2]);
+        assert_eq!(trie.longest_prefix(&namespace), Some(&2));
+        assert_eq!(trie.longest_prefix(&["audio"].into()), None);
+
+        let lobby: Vec<_> = trie
+            .starting_with(&["chat", "lobby"].into())
+            .into_iter()
+            .map(|(namespace, value)| (namespace.to_string(), *value))
+            .collect();
+        assert_eq!(
+            lobby,
+            [
+                (Namespace::from(["chat", "lobby", "alice"]).to_string(), 2),
+                (Namespace::from(["chat", "lobby", "bob"]).to_string(), 3),
+            ]
+        );
+        assert!(trie.starting_with(&["audio"].into()).is_empty());
+    }
+
+    #[test]
+    fn overlap_test() {
+        let trie = trie();
+        assert!(trie.overlaps(&["chat", "lobby"].into()));
+        assert!(trie.overlaps(&["video", "hd"].into()));
+        assert!(trie.overlaps(&["chat"].into()));
+        assert!(!trie.overlaps(&["audio"].into()));
+    }
+
+    #[test]
+    fn remove_test() {
+        let mut trie = trie();
+        assert_eq!(trie.remove(&["chat", "lobby"].into()), None);
+        assert_eq!(trie.remove(&["chat", "lobby", "alice"].into()), Some(2));
+        assert_eq!(trie.len(), 3);
+
+        let removed = trie.extract_if(|_, value| *value > 2);
+        assert_eq!(removed.len(), 2);
+        assert_eq!(trie.len(), 1);
+        // empty branches are pruned
+        assert_eq!(trie.starting_with(&["chat"].into()).len(), 1);
+        assert!(!trie.overlaps(&["video"].into()));
+    }
+}
@@ -0,0 +1,396 @@
+use {
+    super::{Peer, Relay, TrackKey},
+    crate::{
+        FetchStream, FetchWriter, Session, Upstream,
+        error::ControlStreamError,
+        transport::RecvStream,
+        types::{
+            Namespace, Parameters, ReasonPhrase, error_code,
+            message::{ControlMessage, Fetch, FetchError, FetchOk},
+        },
+    },
+    bytes::BytesMut,
+    std::{
+        ptr,
+        sync::{Arc, Mutex, MutexGuard},
+        time::Duration,
+    },
+    tokio::{
+        sync::{mpsc, watch},
+        time::Instant,
+    },
+    tracing::{debug, warn},
+    varint::{VarIntNumber, x},
+};
+
+/// What an [Upstream] server sends for a Fetch
+/// the Relay forwarded to it.
+pub(crate) enum FetchReply {
+    Ok(FetchOk),
+    Error(FetchError),
+    /// The stream carrying the Objects, with the
+    /// data already read from it.
+    Stream(RecvStream, BytesMut),
+}
+
+/// How long the Relay waits before connecting
+/// again after none of the [Upstream] servers was
+/// reachable, doubled with every further failure.
+const RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// The longest wait between two attempts.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// ## Upstreams
+///
+/// The [Upstream] servers of the Relay, tried in
+/// order until one accepts the session.
+///
+/// That session carries all upstream Requests
+/// until it ends or the server sends a Goaway.
+pub(crate) struct Upstreams {
+    servers: Vec<Upstream>,
+    current: Mutex<Current>,
+}
+
+/// The session to the [Upstream] servers.
+enum Current {
+    /// Connected on next use.
+    Idle,
+    /// Being connected, yields the session or
+    /// None once done.
+    Connecting(watch::Receiver<Option<Option<Arc<Peer>>>>),
+    Connected(Arc<Peer>),
+    /// No server was reachable, not tried again
+    /// before `retry`.
+    Failed {
+        retry: Instant,
+        /// How long it waits, doubled every time.
+        backoff: Duration,
+    },
+}
+
+impl Upstreams {
+    pub fn new(servers: Vec<Upstream>) -> Self {
+        Self {
+            servers,
+            current: Mutex::new(Current::Idle),
+        }
+    }
+
+    /// The number of Upstream servers.
+    pub fn len(&self) -> usize {
+        self.servers.len()
+    }
+
+    /// Stops sending new Requests on the session
+    /// of `peer`, the next one connects anew.
+    pub fn release(&self, peer: &Peer) {
+        let mut current = self.current();
+        if matches!(&*current, Current::Connected(current) if ptr::eq(&**current, peer)) {
+            *current = Current::Idle;
+        }
+    }
+
+    fn current(&self) -> MutexGuard<'_, Current> {
+        self.current.lock().expect("upstreams lock poisoned")
+    }
+}
+
+impl Relay {
+    /// Returns the session publishing the longest
+    /// Namespace `namespace` starts with, or the
+    /// one to an [Upstream] server without.
+    pub(super) async fn publisher_or_upstream(
+        self: &Arc<Self>,
+        namespace: &Namespace,
+    ) -> Option<Arc<Peer>> {
+        match self.publisher_of(namespace) {
+            Some(publisher) => Some(publisher),
+            None => self.upstream().await,
+        }
+    }
+
+    /// Returns the session to the first [Upstream]
+    /// server accepting one, established on first
+    /// use.
+    ///
+    /// None without Upstream servers or if none
+    /// of them is reachable. Callers meanwhile
+    /// share one attempt, which is repeated only
+    /// after a backoff.
+    pub(super) async fn upstream(self: &Arc<Self>) -> Option<Arc<Peer>> {
+        if self.upstreams.servers.is_empty() {
+            return None;
+        }
+
+        let mut connecting = {
+            let mut current = self.upstreams.current();
+            match &*current {
+                Current::Connected(peer) => return Some(peer.clone()),
+                Current::Connecting(connecting) => connecting.clone(),
+                Current::Failed { retry, .. } if Instant::now() < *retry => return None,
+                Current::Idle | Current::Failed { .. } => {
+                    let backoff = match &*current {
+                        Current::Failed { backoff, .. } => *backoff,
+                        _ => Duration::ZERO,
+                    };
+                    let (tx, rx) = watch::channel(None);
+                    *current = Current::Connecting(rx.clone());
+                    // not cancelled with the Request waiting for it
+                    tokio::spawn(self.clone().connect_upstream(tx, backoff));
+                    rx
+                }
+            }
+        };
+
+        match connecting.wait_for(Option::is_some).await {
+            Ok(peer) => peer.clone().flatten(),
+            Err(_) => None,
+        }
+    }
+
+    /// Connects to the first reachable [Upstream]
+    /// server and sends the session with `tx`.
+    ///
+    /// `backoff` is how long the Relay waited after
+    /// the previous attempt failed.
+    async fn connect_upstream(
+        self: Arc<Self>,
+        tx: watch::Sender<Option<Option<Arc<Peer>>>>,
+        backoff: Duration,
+    ) {
+        let mut peer = None;
+        for server in &self.upstreams.servers {
+            match server.connect(self.timeouts.setup).await {
+                Ok((transport, control_stream)) => {
+                    debug!(url = %server.url(), "connected upstream");
+                    peer = Some(Session::spawn_upstream(
+                        transport,
+                        control_stream,
+                        self.clone(),
+                    ));
+                    break;
+                }
+                Err(err) => warn!(%err, url = %server.url(), "failed to connect upstream"),
+            }
+        }
+
+        *self.upstreams.current() = match &peer {
+            Some(peer) => Current::Connected(peer.clone()),
+            None => {
+                let backoff = (backoff * 2).clamp(RETRY_AFTER, MAX_RETRY_AFTER);
+                Current::Failed {
+                    retry: Instant::now() + backoff,
+                    backoff,
+                }
+            }
+        };
+        tx.send_replace(Some(peer));
+    }
+
+    /// Forwards the Standalone Fetch `msg` of `peer`
+    /// to the [Upstream] server `upstream`.
+    ///
+    /// Its response and Objects are relayed to
+    /// `peer`, the Objects are cached on the way.
+    pub(super) async fn fetch_upstream(
+        self: &Arc<Self>,
+        upstream: Arc<Peer>,
+        peer: &Arc<Peer>,
+        msg: Fetch,
+    ) -> Result<(), ControlStreamError> {
+        let Some(standalone) = &msg.standalone else {
+            return Ok(());
+        };
+        let key = TrackKey::new(standalone.namespace.clone(), standalone.name.clone());
+        let Ok(request_id) = upstream.next_request_id() else {
+            return reject_fetch(
+                peer,
+                msg.request_id,
+                error_code::Fetch::InternalError,
+                "the upstream granted no more request IDs",
+            )
+            .await;
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        upstream.forwarded_fetches.insert(request_id.number(), tx);
+        let downstream_id = msg.request_id.clone();
+        let forwarded = Fetch {
+            request_id: request_id.clone(),
+            parameters: Parameters::default(),
+            ..msg
+        };
+        if let Err(err) = upstream
+            .control_stream()
+            .send(ControlMessage::Fetch(forwarded))
+            .await
+        {
+            warn!(%err, addr = %upstream.addr(), "failed to fetch upstream");
+            upstream
+                .forwarded_fetches
+                .remove(&request_id.number::<u64>());
+            return reject_fetch(
+                peer,
+                downstream_id,
+                error_code::Fetch::InternalError,
+                "failed to fetch upstream",
+            )
+            .await;
+        }
+
+        peer.spawn_fetch(downstream_id.number(), {
+            let relay = self.clone();
+            let peer = peer.clone();
+            async move {
+                relay.relay_fetch(&peer, downstream_id, &key, rx).await;
+            }
+        });
+        Ok(())
+    }
+
+    /// Relays the `replies` to a forwarded Fetch of
+    /// the Track with `key` to `peer`, which sent it
+    /// with `request_id`.
+    async fn relay_fetch(
+        &self,
+        peer: &Peer,
+        request_id: x!(i),
+        key: &TrackKey,
+        mut replies: mpsc::UnboundedReceiver<FetchReply>,
+    ) {
+        let response = tokio::time::timeout(self.timeouts.request, replies.recv()).await;
+        let ok = match response {
+            Ok(Some(FetchReply::Ok(ok))) => Ok(ok),
+            Ok(Some(FetchReply::Error(err))) => Err((err.code, err.reason)),
+            Ok(_) => Err((
+                error_code::Fetch::InternalError,
+                ReasonPhrase::new("the upstream closed its session"),
+            )),
+            Err(_) => Err((
+                error_code::Fetch::Timeout,
+                ReasonPhrase::new("the upstream took too long to respond"),
+            )),
+        };
+        let ok = match ok {
+            Ok(ok) => ok,
+            Err((code, reason)) => {
+                if let Err(err) = reject_fetch(peer, request_id, code, reason).await {
+                    warn!(%err, addr = %peer.addr(), "failed to reject fetch");
+                }
+                return;
+            }
+        };
+
+        let group_order = ok.group_order;
+        let ok = FetchOk {
+            request_id: request_id.clone(),
+            ..ok
+        };
+        if let Err(err) = peer
+            .control_stream()
+            .send(ControlMessage::FetchOk(ok))
+            .await
+        {
+            warn!(%err, addr = %peer.addr(), "failed to accept fetch");
+            return;
+        }
+
+        let stream = tokio::time::timeout(self.timeouts.data_stream, replies.recv()).await;
+        let Ok(Some(FetchReply::Stream(stream, buf))) = stream else {
+            warn!(?request_id, "upstream did not open the fetch stream");
+            return;
+        };
+        let mut objects = match FetchStream::new(stream, buf, group_order).await {
+            Ok(objects) => objects,
+            Err(err) => {
+                warn!(%err, "failed to read upstream fetch stream");
+                return;
+            }
+        };
+        let mut writer = match FetchWriter::open(peer.transport(), request_id).await {
+            Ok(writer) => writer,
+            Err(err) => {
+                warn!(%err, addr = %peer.addr(), "failed to open fetch stream");
+                return;
+            }
+        };
+
+        loop {
+            let object = match objects.next().await {
+                Ok(Some(object)) => object,
+                Ok(None) => break,
+                Err(err) => {
+                    warn!(%err, "failed to read upstream fetch object");
+                    return;
+                }
+            };
+            self.cache.insert(key, object.clone().into());
+            if let Err(err) = writer.write(object).await {
+                warn!(%err, addr = %peer.addr(), "failed to relay fetch object");
+                return;
+            }
+        }
+        if let Err(err) = writer.finish().await {
+            debug!(%err, addr = %peer.addr(), "failed to finish fetch stream");
+        }
+    }
+
+    /// Handles a [FetchOk] the [Upstream] server
+    /// `peer` sent for a forwarded Fetch.
+    pub fn fetch_ok(&self, peer: &Peer, ok: FetchOk) {
+        let request_id = ok.request_id.number::<u64>();
+        let sent = peer
+            .forwarded_fetches
+            .get(&request_id)
+            .map(|tx| tx.send(FetchReply::Ok(ok)).is_ok());
+        match sent {
+            Some(true) => (),
+            // the Fetch was cancelled meanwhile
+            Some(false) => {
+                peer.forwarded_fetches.remove(&request_id);
+            }
+            None => debug!(request_id, "fetch ok for unknown fetch"),
+        }
+    }
+
+    /// Handles a [FetchError] the [Upstream] server
+    /// `peer` sent for a forwarded Fetch.
+    pub fn fetch_error(&self, peer: &Peer, err: FetchError) {
+        let request_id = err.request_id.number::<u64>();
+        if let Some((_, tx)) = peer.forwarded_fetches.remove(&request_id) {
+            let _ = tx.send(FetchReply::Error(err));
+        } else {
+            debug!(request_id, "fetch error for unknown fetch");
+        }
+    }
+
+    /// Handles the Fetch `stream` the [Upstream]
+    /// server `peer` opened for a forwarded Fetch.
+    pub fn fetch_stream(&self, peer: &Peer, request_id: u64, stream: RecvStream, buf: BytesMut) {
+        if let Some((_, tx)) = peer.forwarded_fetches.remove(&request_id) {
+            let _ = tx.send(FetchReply::Stream(stream, buf));
+        } else {
+            debug!(request_id, "fetch stream for unknown fetch");
+        }
+    }
+}
+
+/// Rejects the Fetch `peer` sent with
+/// `request_id`.
+async fn reject_fetch<R>(
+    peer: &Peer,
+    request_id: x!(i),
+    code: error_code::Fetch,
+    reason: R,
+) -> Result<(), ControlStreamError>
+where
+    R: Into<ReasonPhrase>,
+{
+    peer.control_stream()
+        .send(ControlMessage::FetchError(FetchError::new(
+            request_id, code, reason,
+        )))
+        .await
+}
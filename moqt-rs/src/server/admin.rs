@@ -0,0 +1,549 @@
+use {
+    super::{GoawayTarget, Server, ServerError},
+    crate::types::{Forward, Namespace, error_code::Termination, message::Goaway},
+    axum::{
+        Json, Router,
+        extract::{Path, Request, State},
+        http::{
+            StatusCode,
+            header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
+        },
+        middleware::{self, Next},
+        response::{Html, IntoResponse, Response},
+        routing::{get, post},
+    },
+    core::net::SocketAddr,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    subtle::ConstantTimeEq,
+    tracing::{error, info, warn},
+    url::Url,
+    varint::{VarIntBytes, VarIntNumber},
+};
+
+/// The page listing the state of the Server.
+const DASHBOARD: &str = include_str!("admin.html");
+
+/// ## Session Info
+///
+/// A session of a [Server] as listed by its
+/// admin API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub addr: SocketAddr,
+
+    /// Whether the Server connected to the peer
+    /// as client, see [Upstream](crate::Upstream).
+    pub upstream: bool,
+
+    /// Number of Tracks the peer publishes.
+    pub published_tracks: usize,
+
+    /// Number of Subscriptions of the peer.
+    pub subscriptions: usize,
+
+    /// Number of Fetches being served to the peer.
+    pub fetches: usize,
+}
+
+/// ## Namespace Info
+///
+/// A published Namespace as listed by the
+/// admin API of a [Server].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceInfo {
+    /// The Tuple fields of the Namespace.
+    pub namespace: Vec<String>,
+
+    /// The address of the publishing session.
+    pub publisher: SocketAddr,
+}
+
+/// ## Track Info
+///
+/// A relayed Track as listed by the admin API
+/// of a [Server].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackInfo {
+    /// The Tuple fields of the Namespace.
+    pub namespace: Vec<String>,
+    pub name: String,
+
+    /// The address of the publishing session.
+    pub publisher: SocketAddr,
+
+    /// Whether the publisher sent a Publish
+    /// instead of being subscribed to.
+    pub pushed: bool,
+
+    /// Number of Subscribers of the Track.
+    pub subscribers: usize,
+}
+
+/// ## Subscription Info
+///
+/// A Subscription of a session as listed by the
+/// admin API of a [Server].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionInfo {
+    /// The address of the subscribing session.
+    pub subscriber: SocketAddr,
+
+    /// The Request ID of the Subscribe.
+    pub request_id: u64,
+
+    /// The Tuple fields of the Namespace.
+    pub namespace: Vec<String>,
+    pub name: String,
+
+    /// Whether Objects are forwarded.
+    pub forward: bool,
+    pub priority: u8,
+}
+
+/// Body of the goaway and shutdown actions.
+#[derive(Debug, Default, Deserialize)]
+struct GoawayRequest {
+    /// The URL to migrate to, the current one
+    /// without.
+    url: Option<Url>,
+}
+
+/// Body of the close action.
+#[derive(Debug, Default, Deserialize)]
+struct CloseRequest {
+    /// The numeric [Termination] code,
+    /// `NO_ERROR` without.
+    code: Option<u64>,
+}
+
+impl Server {
+    /// The sessions of the Server.
+    pub fn list_sessions(&self) -> Vec<SessionInfo> {
+        self.relay
+            .sessions()
+            .iter()
+            .map(|peer| SessionInfo {
+                addr: peer.addr(),
+                upstream: peer.is_upstream_server(),
+                published_tracks: peer.published.len(),
+                subscriptions: peer.downstream.len(),
+                fetches: peer.fetches.len(),
+            })
+            .collect()
+    }
+
+    /// The Namespaces published by the sessions.
+    pub fn list_namespaces(&self) -> Vec<NamespaceInfo> {
+        self.relay
+            .published_namespaces()
+            .into_iter()
+            .map(|(namespace, publisher)| NamespaceInfo {
+                namespace: fields(&namespace),
+                publisher,
+            })
+            .collect()
+    }
+
+    /// The Tracks relayed by the Server.
+    pub fn list_tracks(&self) -> Vec<TrackInfo> {
+        self.relay
+            .active_tracks()
+            .iter()
+            .map(|track| TrackInfo {
+                namespace: fields(&track.key().namespace),
+                name: track.key().name.to_string(),
+                publisher: track.publisher().addr(),
+                pushed: track.is_pushed(),
+                subscribers: track.subscribers().len(),
+            })
+            .collect()
+    }
+
+    /// The Subscriptions of all sessions.
+    pub fn list_subscriptions(&self) -> Vec<SubscriptionInfo> {
+        self.relay
+            .active_tracks()
+            .iter()
+            .flat_map(|track| {
+                track
+                    .subscribers()
+                    .into_iter()
+                    .map(|subscriber| SubscriptionInfo {
+                        subscriber: subscriber.peer.addr(),
+                        request_id: subscriber.request_id.number(),
+                        namespace: fields(&track.key().namespace),
+                        name: track.key().name.to_string(),
+                        forward: subscriber.forward == Forward::Enabled,
+                        priority: subscriber.priority,
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Serves the admin API of `server` on `bind`
+/// until the task is aborted.
+///
+/// Without an admin token only loopback
+/// addresses are served, the actions are not
+/// authenticated then.
+pub(super) async fn serve(server: Arc<Server>, bind: SocketAddr) {
+    if server.admin_token.is_none() && !bind.ip().is_loopback() {
+        error!(%bind, "the admin API requires a token beyond loopback");
+        return;
+    }
+
+    let listener = match tokio::net::TcpListener::bind(bind).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(%err, %bind, "failed to bind the admin API");
+            return;
+        }
+    };
+    info!(%bind, "admin API is listening");
+
+    if let Err(err) = axum::serve(listener, router(server)).await {
+        error!(%err, "admin API failed");
+    }
+}
+
+/// The routes of the admin API.
+///
+/// - `GET /`: dashboard
+/// - `GET /sessions`, `/namespaces`, `/tracks`
+///   and `/subscriptions`: JSON lists
+/// - `POST /sessions/{addr}/goaway`: asks a
+///   session to go away, optionally to `url`
+/// - `POST /sessions/{addr}/close`: closes a
+///   session, optionally with a Termination `code`
+/// - `POST /shutdown`: drains and stops the Server
+///
+/// All but the dashboard, which holds no data,
+/// require the admin token of the Server as
+/// bearer token, if it has one. The actions
+/// only accept JSON bodies, which other origins
+/// cannot send without a CORS preflight.
+pub(super) fn router(server: Arc<Server>) -> Router {
+    let actions = Router::new()
+        .route("/sessions/{addr}/goaway", post(goaway))
+        .route("/sessions/{addr}/close", post(close))
+        .route("/shutdown", post(shutdown))
+        .route_layer(middleware::from_fn(require_json));
+
+    let api = Router::new()
+        .route(
+            "/sessions",
+            get(|State(server): State<Arc<Server>>| async move {
+                Json(server.list_sessions())
+            }),
+        )
+        .route(
+            "/namespaces",
+            get(|State(server): State<Arc<Server>>| async move {
+                Json(server.list_namespaces())
+            }),
+        )
+        .route(
+            "/tracks",
+            get(|State(server): State<Arc<Server>>| async move { Json(server.list_tracks()) }),
+        )
+        .route(
+            "/subscriptions",
+            get(|State(server): State<Arc<Server>>| async move {
+                Json(server.list_subscriptions())
+            }),
+        )
+        .merge(actions)
+        .route_layer(middleware::from_fn_with_state(server.clone(), authorize));
+
+    Router::new()
+        .route("/", get(|| async { Html(DASHBOARD) }))
+        .merge(api)
+        .with_state(server)
+}
+
+/// Rejects `request` unless it carries the admin
+/// token of `server` as bearer token.
+async fn authorize(
+    State(server): State<Arc<Server>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = &server.admin_token else {
+        return next.run(request).await;
+    };
+
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match bearer {
+        Some(bearer) if bool::from(bearer.as_bytes().ct_eq(token.as_bytes())) => {
+            next.run(request).await
+        }
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(WWW_AUTHENTICATE, "Bearer")],
+            "missing or invalid admin token",
+        )
+            .into_response(),
+    }
+}
+
+/// Rejects `request` unless its body is JSON.
+async fn require_json(request: Request, next: Next) -> Response {
+    let json = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !json {
+        return (StatusCode::UNSUPPORTED_MEDIA_TYPE, "expected a JSON body").into_response();
+    }
+
+    next.run(request).await
+}
+
+async fn goaway(
+    State(server): State<Arc<Server>>,
+    Path(addr): Path<SocketAddr>,
+    body: Option<Json<GoawayRequest>>,
+) -> Response {
+    let Json(body) = body.unwrap_or_default();
+    match server.goaway(GoawayTarget::Session(addr), body.url).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn close(
+    State(server): State<Arc<Server>>,
+    Path(addr): Path<SocketAddr>,
+    body: Option<Json<CloseRequest>>,
+) -> Response {
+    let Json(body) = body.unwrap_or_default();
+    let code = match body.code {
+        Some(code) => Termination::try_from(code).ok(),
+        None => Some(Termination::NoError),
+    };
+    let Some(code) = code else {
+        return (StatusCode::BAD_REQUEST, "unknown termination code").into_response();
+    };
+
+    match server.close_session(addr, code).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => error_response(err),
+    }
+}
+
+/// Starts the shutdown, the Server drains in
+/// the background.
+async fn shutdown(
+    State(server): State<Arc<Server>>,
+    body: Option<Json<GoawayRequest>>,
+) -> Response {
+    let Json(body) = body.unwrap_or_default();
+    // fail early on URLs exceeding the limit
+    if let Err(err) = Goaway::with_url(body.url.as_ref()) {
+        return (StatusCode::BAD_REQUEST, err.to_string()).into_response();
+    }
+
+    tokio::spawn(async move {
+        if let Err(err) = server.shutdown(body.url).await {
+            warn!(%err, "failed to shut down");
+        }
+    });
+    StatusCode::ACCEPTED.into_response()
+}
+
+fn error_response(err: ServerError) -> Response {
+    let status = match err {
+        ServerError::UnknownSession { .. } => StatusCode::NOT_FOUND,
+        ServerError::Goaway { .. } => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, err.to_string()).into_response()
+}
+
+/// The Tuple fields of `namespace` as text.
+fn fields(namespace: &Namespace) -> Vec<String> {
+    namespace
+        .fields()
+        .iter()
+        .map(|field| String::from_utf8_lossy(&field.bytes()).into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Client,
+        axum::{
+            body::{Body, to_bytes},
+            http::Request,
+        },
+        bytes::Bytes,
+        core::time::Duration,
+        serde::de::DeserializeOwned,
+        tokio::time::timeout,
+        tower::ServiceExt,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    async fn request(server: &Arc<Server>, method: &str, uri: &str, body: &str) -> Response {
+        authorized(server, method, uri, body, None).await
+    }
+
+    async fn authorized(
+        server: &Arc<Server>,
+        method: &str,
+        uri: &str,
+        body: &str,
+        token: Option<&str>,
+    ) -> Response {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+        if !body.is_empty() {
+            request = request.header("content-type", "application/json");
+        }
+        let request = request.body(Body::from(body.to_owned())).unwrap();
+        router(server.clone()).oneshot(request).await.unwrap()
+    }
+
+    async fn list<T>(server: &Arc<Server>, uri: &str) -> Vec<T>
+    where
+        T: DeserializeOwned,
+    {
+        let res = request(server, "GET", uri, "").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn admin_test() {
+        let server = Arc::new(
+            Server::memory_builder()
+                .bind(([127, 0, 0, 1], 4443))
+                .build(),
+        );
+        let connector = server.memory_connector().expect("memory server");
+        let running = server.clone();
+        let run = tokio::spawn(async move { running.run().await });
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (publisher, viewer) = (client().await.unwrap(), client().await.unwrap());
+
+        let _namespace = publisher.publish_namespace(["chat"]).await.unwrap();
+        let mut track = publisher.publish(["chat"], "text").await.unwrap();
+        let mut subscription = viewer.subscribe(["chat"], "text", None).await.unwrap();
+        track.write_object(Bytes::from_static(b"hi")).await.unwrap();
+        timeout(TIMEOUT, subscription.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let sessions: Vec<SessionInfo> = list(&server, "/sessions").await;
+        assert_eq!(sessions.len(), 2);
+        let publisher_addr = sessions
+            .iter()
+            .find(|session| session.published_tracks == 1)
+            .unwrap()
+            .addr;
+        let viewer_addr = sessions
+            .iter()
+            .find(|session| session.subscriptions == 1)
+            .unwrap()
+            .addr;
+
+        let namespaces: Vec<NamespaceInfo> = list(&server, "/namespaces").await;
+        assert_eq!(
+            namespaces,
+            [NamespaceInfo {
+                namespace: vec!["chat".into()],
+                publisher: publisher_addr,
+            }]
+        );
+        let tracks: Vec<TrackInfo> = list(&server, "/tracks").await;
+        assert_eq!(
+            tracks,
+            [TrackInfo {
+                namespace: vec!["chat".into()],
+                name: "text".into(),
+                publisher: publisher_addr,
+                pushed: true,
+                subscribers: 1,
+            }]
+        );
+        let subscriptions: Vec<SubscriptionInfo> = list(&server, "/subscriptions").await;
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].subscriber, viewer_addr);
+        assert!(subscriptions[0].forward);
+
+        let res = request(&server, "POST", "/sessions/127.0.0.9:1/goaway", "{}").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let uri = format!("/sessions/{viewer_addr}/close");
+        let res = request(&server, "POST", &uri, r#"{"code": 63}"#).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // the viewer is kicked, its Subscription ends
+        let res = request(&server, "POST", &uri, r#"{"code": 1}"#).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(timeout(TIMEOUT, subscription.recv()).await.unwrap(), None);
+
+        // other origins cannot post JSON without a preflight
+        let res = request(&server, "POST", "/shutdown", "").await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        // the Server stops once the publisher left
+        let res = request(&server, "POST", "/shutdown", "{}").await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        drop((track, _namespace, publisher));
+        timeout(TIMEOUT, run).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn admin_token_test() {
+        let server = Arc::new(
+            Server::memory_builder()
+                .bind(([127, 0, 0, 1], 4443))
+                .admin_token("secret".to_owned())
+                .build(),
+        );
+        let uri = "/sessions/127.0.0.9:1/goaway";
+
+        let res = request(&server, "POST", uri, "{}").await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = authorized(&server, "POST", uri, "{}", Some("guess")).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = authorized(&server, "POST", uri, "{}", Some("secret")).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = request(&server, "GET", "/sessions", "").await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = authorized(&server, "GET", "/sessions", "", Some("secret")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // the dashboard holds no data
+        let res = request(&server, "GET", "/", "").await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn admin_bind_test() {
+        let server = Arc::new(
+            Server::memory_builder()
+                .bind(([127, 0, 0, 1], 4443))
+                .build(),
+        );
+
+        // never listens beyond loopback without a token
+        let bind = SocketAddr::from(([0, 0, 0, 0], 0));
+        timeout(TIMEOUT, serve(server, bind)).await.unwrap();
+    }
+}
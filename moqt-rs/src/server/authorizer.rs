@@ -0,0 +1,394 @@
+use {
+    super::error::{AllowListError, allow_list_ctx},
+    crate::types::{Name, Namespace, Token, error_code},
+    core::{fmt, str::FromStr},
+    snafu::ensure,
+    std::path::Path,
+    subtle::ConstantTimeEq,
+    varint::VarIntBytes,
+};
+
+/// ## Authorizer
+///
+/// Decides which Requests the clients of a
+/// [Server](crate::Server) may send.
+///
+/// Invoked for every ClientSetup, PublishNamespace,
+/// Publish, Subscribe, SubscribeNamespace and
+/// Fetch before the Relay handles it.
+pub trait Authorizer: fmt::Debug + Send + Sync {
+    /// Allows `request` or fails with the
+    /// [Denial] to reject it with.
+    fn authorize(&self, request: &AuthRequest<'_>) -> Result<(), Denial>;
+}
+
+/// The kind of an authorized Request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    /// The ClientSetup of a new session.
+    Setup,
+    PublishNamespace,
+    Publish,
+    Subscribe,
+    SubscribeNamespace,
+    Fetch,
+}
+
+impl RequestKind {
+    /// Whether the client publishes content
+    /// with this Request.
+    pub fn is_publish(self) -> bool {
+        matches!(self, Self::PublishNamespace | Self::Publish)
+    }
+}
+
+/// ## Authorization Request
+///
+/// A Request an [Authorizer] decides on.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthRequest<'a> {
+    pub kind: RequestKind,
+
+    /// The Authorization Tokens sent with the
+    /// Request followed by the one sent with the
+    /// ClientSetup, aliases resolved to their
+    /// Type and Value.
+    pub tokens: &'a [Token],
+
+    /// The Namespace, or the prefix of a
+    /// SubscribeNamespace. None for the setup.
+    pub namespace: Option<&'a Namespace>,
+
+    /// The Track Name, None for the setup and
+    /// Requests on whole Namespaces.
+    pub name: Option<&'a Name>,
+}
+
+/// ## Denial
+///
+/// Why an [Authorizer] rejected a Request, the
+/// client receives the matching error code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denial {
+    /// The client may not send the Request.
+    Unauthorized,
+
+    /// A Token could not be understood.
+    MalformedAuthToken,
+
+    /// A Token is no longer valid.
+    ExpiredAuthToken,
+}
+
+impl fmt::Display for Denial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unauthorized => "not authorized",
+            Self::MalformedAuthToken => "malformed authorization token",
+            Self::ExpiredAuthToken => "authorization token expired",
+        })
+    }
+}
+
+macro_rules! denial_codes {
+    ($($code:ident),* $(,)?) => {
+        $(
+            impl From<Denial> for error_code::$code {
+                fn from(denial: Denial) -> Self {
+                    match denial {
+                        Denial::Unauthorized => Self::Unauthorized,
+                        Denial::MalformedAuthToken => Self::MalformedAuthToken,
+                        Denial::ExpiredAuthToken => Self::ExpiredAuthToken,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+denial_codes!(
+    Termination,
+    PublishNamespace,
+    Subscribe,
+    SubscribeNamespace,
+    Fetch
+);
+
+impl From<Denial> for error_code::Publish {
+    /// A Publish can only be rejected as
+    /// [Unauthorized](error_code::Publish::Unauthorized).
+    fn from(_: Denial) -> Self {
+        Self::Unauthorized
+    }
+}
+
+/// ## Allow List
+///
+/// A static [Authorizer] granting access to the
+/// Token Values it lists, loaded from a file.
+///
+/// Every line holds a Token Value, the access it
+/// grants (`publish`, `subscribe` or `*` for both)
+/// and the Namespace prefix with its fields
+/// separated by `/` (`*` for all), optionally
+/// followed by a Track Name:
+///
+/// ```text
+/// # token   access     namespace   name
+/// s3cr3t    publish    chat/lobby
+/// viewer    subscribe  chat        text
+/// admin     *          *
+/// ```
+///
+/// A client may set up a session with any listed
+/// Token. Fetches need `subscribe` access.
+///
+/// A field starting with `#` starts a comment,
+/// so Tokens may contain `#` but not start with it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AllowList {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    token: Vec<u8>,
+    publish: bool,
+    subscribe: bool,
+    /// None for all Namespaces.
+    prefix: Option<Namespace>,
+    /// None for all Tracks.
+    name: Option<Name>,
+}
+
+impl AllowList {
+    /// Loads the AllowList from the file at `path`.
+    pub fn load<P>(path: P) -> Result<Self, AllowListError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let list = std::fs::read_to_string(path).map_err(|err| AllowListError::Read {
+            path: path.to_owned(),
+            msg: err.to_string(),
+        })?;
+        list.parse()
+    }
+
+    /// The number of listed entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no Token is listed.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Entry {
+    /// Whether this entry grants `request`
+    /// to the Token with `value`.
+    fn grants(&self, value: &[u8], request: &AuthRequest<'_>) -> bool {
+        // in constant time, the duration must not
+        // reveal how much of a guess is correct
+        if !bool::from(self.token.ct_eq(value)) {
+            return false;
+        }
+
+        let access = match request.kind {
+            RequestKind::Setup => return true,
+            kind if kind.is_publish() => self.publish,
+            _ => self.subscribe,
+        };
+        let namespace = match (&self.prefix, request.namespace) {
+            (None, _) => true,
+            (Some(prefix), Some(namespace)) => namespace.starts_with(prefix),
+            (Some(_), None) => false,
+        };
+        let name = self.name.is_none() || self.name.as_ref() == request.name;
+
+        access && namespace && name
+    }
+}
+
+impl Authorizer for AllowList {
+    fn authorize(&self, request: &AuthRequest<'_>) -> Result<(), Denial> {
+        let granted = request.tokens.iter().filter_map(Token::value).any(|value| {
+            let value = value.bytes();
+            self.entries
+                .iter()
+                .any(|entry| entry.grants(&value, request))
+        });
+
+        if granted {
+            Ok(())
+        } else {
+            Err(Denial::Unauthorized)
+        }
+    }
+}
+
+impl FromStr for AllowList {
+    type Err = AllowListError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let fields = line
+                .split_whitespace()
+                .take_while(|field| !field.starts_with('#'))
+                .collect::<Vec<_>>();
+            if fields.is_empty() {
+                continue;
+            }
+
+            let line = i + 1;
+            ensure!(
+                (3..=4).contains(&fields.len()),
+                allow_list_ctx::InvalidEntrySnafu {
+                    line,
+                    reason: "expected a token, access, namespace and optional name",
+                }
+            );
+            let (publish, subscribe) = match fields[1] {
+                "publish" => (true, false),
+                "subscribe" => (false, true),
+                "*" => (true, true),
+                _ => {
+                    return allow_list_ctx::InvalidEntrySnafu {
+                        line,
+                        reason: "access must be publish, subscribe or *",
+                    }
+                    .fail();
+                }
+            };
+            let prefix = match fields[2] {
+                "*" => None,
+                prefix => Some(Namespace::from(
+                    prefix.split('/').map(str::to_owned).collect::<Vec<_>>(),
+                )),
+            };
+
+            entries.push(Entry {
+                token: fields[0].as_bytes().to_vec(),
+                publish,
+                subscribe,
+                prefix,
+                name: fields.get(3).map(|name| Name::from(name.to_string())),
+            });
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = "
+        # token  access     namespace   name
+        pub      publish    chat/lobby
+        sub      subscribe  chat        text
+        admin    *          *           # everything
+    ";
+
+    fn token(value: &str) -> Token {
+        Token::builder()
+            .use_value()
+            .typ(0u8)
+            .value(value.as_bytes())
+            .build()
+    }
+
+    fn request<'a>(
+        kind: RequestKind,
+        tokens: &'a [Token],
+        namespace: Option<&'a Namespace>,
+        name: Option<&'a Name>,
+    ) -> AuthRequest<'a> {
+        AuthRequest {
+            kind,
+            tokens,
+            namespace,
+            name,
+        }
+    }
+
+    #[test]
+    fn allow_list_test() {
+        let list = LIST.parse::<AllowList>().unwrap();
+        assert_eq!(list.len(), 3);
+
+        let lobby = Namespace::from(["chat", "lobby"]);
+        let chat = Namespace::from(["chat"]);
+        let text = Name::from("text");
+        let (publisher, subscriber, admin, unknown) = (
+            [token("pub")],
+            [token("sub")],
+            [token("admin")],
+            [token("x")],
+        );
+
+        for tokens in [&publisher, &subscriber, &admin] {
+            let setup = request(RequestKind::Setup, tokens, None, None);
+            assert_eq!(list.authorize(&setup), Ok(()));
+        }
+        let setup = request(RequestKind::Setup, &unknown, None, None);
+        assert_eq!(list.authorize(&setup), Err(Denial::Unauthorized));
+        let setup = request(RequestKind::Setup, &[], None, None);
+        assert_eq!(list.authorize(&setup), Err(Denial::Unauthorized));
+
+        let publish = request(RequestKind::Publish, &publisher, Some(&lobby), Some(&text));
+        assert_eq!(list.authorize(&publish), Ok(()));
+        let publish = request(RequestKind::PublishNamespace, &publisher, Some(&chat), None);
+        assert_eq!(list.authorize(&publish), Err(Denial::Unauthorized));
+        let subscribe = request(
+            RequestKind::Subscribe,
+            &publisher,
+            Some(&lobby),
+            Some(&text),
+        );
+        assert_eq!(list.authorize(&subscribe), Err(Denial::Unauthorized));
+
+        let subscribe = request(RequestKind::Fetch, &subscriber, Some(&lobby), Some(&text));
+        assert_eq!(list.authorize(&subscribe), Ok(()));
+        let subscribe = request(
+            RequestKind::SubscribeNamespace,
+            &subscriber,
+            Some(&chat),
+            None,
+        );
+        assert_eq!(list.authorize(&subscribe), Err(Denial::Unauthorized));
+
+        let publish = request(RequestKind::PublishNamespace, &admin, Some(&chat), None);
+        assert_eq!(list.authorize(&publish), Ok(()));
+
+        // only a field starting with # is a comment
+        let list = "a#b publish chat #c".parse::<AllowList>().unwrap();
+        let (hashed, cut) = ([token("a#b")], [token("a")]);
+        let setup = request(RequestKind::Setup, &hashed, None, None);
+        assert_eq!(list.authorize(&setup), Ok(()));
+        let setup = request(RequestKind::Setup, &cut, None, None);
+        assert_eq!(list.authorize(&setup), Err(Denial::Unauthorized));
+    }
+
+    #[test]
+    fn invalid_allow_list_test() {
+        assert_eq!(
+            "a b".parse::<AllowList>(),
+            Err(AllowListError::InvalidEntry {
+                line: 1,
+                reason: "expected a token, access, namespace and optional name",
+            })
+        );
+        assert_eq!(
+            "\ntoken read chat".parse::<AllowList>(),
+            Err(AllowListError::InvalidEntry {
+                line: 2,
+                reason: "access must be publish, subscribe or *",
+            })
+        );
+    }
+}
@@ -1,10 +1,16 @@
 use {
-    super::{Endpoint, Server, ServerConfig, ServerError, ctx},
-    crate::Protocol,
+    super::{
+        AllowList, Authorizer, Endpoint, KeySet, Server, ServerConfig, ServerError, Upstream, ctx,
+    },
+    crate::{
+        ALPN, Protocol, Timeouts,
+        relay::{DEFAULT_CACHE_SIZE, Relay},
+    },
     bon::bon,
     core::net::SocketAddr,
     snafu::ResultExt,
     std::{path::Path, sync::Arc},
+    tokio::sync::Notify,
 };
 
 #[bon]
@@ -12,12 +18,31 @@ impl Server {
     /// Create a [Server] using a [ServerConfig]
     #[tracing::instrument]
     pub async fn new(config: ServerConfig) -> Result<Self, ServerError> {
+        let authorizer = match (&config.allow_list, &config.token_keys) {
+            (Some(path), _) => {
+                let list = AllowList::load(path).context(ctx::AllowListSnafu)?;
+                Some(Arc::new(list) as Arc<dyn Authorizer>)
+            }
+            (None, Some(path)) => {
+                let keys = KeySet::load(path).context(ctx::KeySetSnafu)?;
+                Some(Arc::new(keys) as Arc<dyn Authorizer>)
+            }
+            (None, None) => None,
+        };
+
         match &config.protocol {
             Protocol::Quic => {
                 Self::quic_builder()
                     .bind(config.bind)
                     .cert(&config.cert)
                     .key(&config.key)
+                    .timeouts(config.timeouts())
+                    .maybe_cache_size(config.cache_size)
+                    .maybe_auth_token_cache_size(config.auth_token_cache_size)
+                    .maybe_authorizer(authorizer)
+                    .upstreams(config.upstreams())
+                    .maybe_admin(config.admin)
+                    .maybe_admin_token(config.admin_token.clone())
                     .build()
                     .await
             }
@@ -26,6 +51,13 @@ impl Server {
                     .bind(config.bind)
                     .cert(&config.cert)
                     .key(&config.key)
+                    .timeouts(config.timeouts())
+                    .maybe_cache_size(config.cache_size)
+                    .maybe_auth_token_cache_size(config.auth_token_cache_size)
+                    .maybe_authorizer(authorizer)
+                    .upstreams(config.upstreams())
+                    .maybe_admin(config.admin)
+                    .maybe_admin_token(config.admin_token.clone())
                     .build()
                     .await
             }
@@ -54,6 +86,56 @@ impl Server {
             /// The Path to the key file.
         }))]
         key: K,
+        #[builder(default, setters(doc {
+            /// ## Timeouts
+            /// 
+            /// How long clients may take to complete
+            /// the setup, respond or send data.
+        }))]
+        timeouts: Timeouts,
+        #[builder(default = DEFAULT_CACHE_SIZE, setters(doc {
+            /// ## Cache Size
+            /// 
+            /// Maximum bytes of Object payloads cached
+            /// to serve Fetches.
+        }))]
+        cache_size: usize,
+        #[builder(default, setters(doc {
+            /// ## Authorization Token Cache Size
+            /// 
+            /// Maximum bytes of Authorization Tokens
+            /// a client may register per session.
+        }))]
+        auth_token_cache_size: u64,
+        #[builder(setters(doc {
+            /// ## Authorizer
+            /// 
+            /// Decides which Requests clients may send,
+            /// all are authorized without.
+        }))]
+        authorizer: Option<Arc<dyn Authorizer>>,
+        #[builder(default, setters(doc {
+            /// ## Upstreams
+            /// 
+            /// Servers subscribed to for Tracks no
+            /// client publishes, tried in order.
+        }))]
+        upstreams: Vec<Upstream>,
+        #[builder(setters(doc {
+            /// ## Admin Address
+            /// 
+            /// The Socket Address the admin HTTP API
+            /// listens on, none is served without.
+        }))]
+        admin: Option<SocketAddr>,
+        #[builder(setters(doc {
+            /// ## Admin Token
+            /// 
+            /// The bearer token the actions of the admin
+            /// API require, it only listens on loopback
+            /// addresses without.
+        }))]
+        admin_token: Option<String>,
     ) -> Result<Self, ServerError>
     where
         C: AsRef<Path>,
@@ -71,6 +153,13 @@ impl Server {
                 .await
                 .context(ctx::EndpointSnafu)?,
             sessions: Default::default(),
+            relay: Arc::new(Relay::new(timeouts, cache_size, authorizer, upstreams)),
+            timeouts,
+            auth_token_cache_size,
+            draining: Default::default(),
+            admin,
+            admin_token,
+            stopped: Notify::new(),
         })
     }
 
@@ -96,14 +185,65 @@ impl Server {
             /// The Path to the key file.
         }))]
         key: K,
+        #[builder(default, setters(doc {
+            /// ## Timeouts
+            /// 
+            /// How long clients may take to complete
+            /// the setup, respond or send data.
+        }))]
+        timeouts: Timeouts,
+        #[builder(default = DEFAULT_CACHE_SIZE, setters(doc {
+            /// ## Cache Size
+            /// 
+            /// Maximum bytes of Object payloads cached
+            /// to serve Fetches.
+        }))]
+        cache_size: usize,
+        #[builder(default, setters(doc {
+            /// ## Authorization Token Cache Size
+            /// 
+            /// Maximum bytes of Authorization Tokens
+            /// a client may register per session.
+        }))]
+        auth_token_cache_size: u64,
+        #[builder(setters(doc {
+            /// ## Authorizer
+            /// 
+            /// Decides which Requests clients may send,
+            /// all are authorized without.
+        }))]
+        authorizer: Option<Arc<dyn Authorizer>>,
+        #[builder(default, setters(doc {
+            /// ## Upstreams
+            /// 
+            /// Servers subscribed to for Tracks no
+            /// client publishes, tried in order.
+        }))]
+        upstreams: Vec<Upstream>,
+        #[builder(setters(doc {
+            /// ## Admin Address
+            /// 
+            /// The Socket Address the admin HTTP API
+            /// listens on, none is served without.
+        }))]
+        admin: Option<SocketAddr>,
+        #[builder(setters(doc {
+            /// ## Admin Token
+            /// 
+            /// The bearer token the actions of the admin
+            /// API require, it only listens on loopback
+            /// addresses without.
+        }))]
+        admin_token: Option<String>,
     ) -> Result<Self, ServerError>
     where
         C: AsRef<Path>,
         K: AsRef<Path>,
     {
-        let tls_config = webtransport::tls::server::build_default_tls_config(
+        let mut tls_config = webtransport::tls::server::build_default_tls_config(
             webtransport::Identity::load_pemfiles(cert, key).await?,
         );
+        tls_config.alpn_protocols = vec![ALPN.to_vec()];
 
         let crypto = Arc::new(
             quic::crypto::rustls::QuicServerConfig::try_from(tls_config)
@@ -121,6 +261,90 @@ impl Server {
                 .await
                 .context(ctx::EndpointSnafu)?,
             sessions: Default::default(),
+            relay: Arc::new(Relay::new(timeouts, cache_size, authorizer, upstreams)),
+            timeouts,
+            auth_token_cache_size,
+            draining: Default::default(),
+            admin,
+            admin_token,
+            stopped: Notify::new(),
         })
     }
+
+    /// Create an in-memory [Server] using a Builder pattern
+    ///
+    /// Clients connect to it with its
+    /// [memory_connector](Self::memory_connector).
+    #[builder(start_fn = memory_builder, finish_fn = build)]
+    pub fn new_memory(
+        #[builder(into, setters(doc {
+            /// ## Bind Address
+            /// 
+            /// The Socket Address clients connect to,
+            /// no socket is bound to it.
+        }))]
+        bind: SocketAddr,
+        #[builder(default, setters(doc {
+            /// ## Timeouts
+            /// 
+            /// How long clients may take to complete
+            /// the setup, respond or send data.
+        }))]
+        timeouts: Timeouts,
+        #[builder(default = DEFAULT_CACHE_SIZE, setters(doc {
+            /// ## Cache Size
+            /// 
+            /// Maximum bytes of Object payloads cached
+            /// to serve Fetches.
+        }))]
+        cache_size: usize,
+        #[builder(default, setters(doc {
+            /// ## Authorization Token Cache Size
+            /// 
+            /// Maximum bytes of Authorization Tokens
+            /// a client may register per session.
+        }))]
+        auth_token_cache_size: u64,
+        #[builder(setters(doc {
+            /// ## Authorizer
+            /// 
+            /// Decides which Requests clients may send,
+            /// all are authorized without.
+        }))]
+        authorizer: Option<Arc<dyn Authorizer>>,
+        #[builder(default, setters(doc {
+            /// ## Upstreams
+            /// 
+            /// Servers subscribed to for Tracks no
+            /// client publishes, tried in order.
+        }))]
+        upstreams: Vec<Upstream>,
+        #[builder(setters(doc {
+            /// ## Admin Address
+            /// 
+            /// The Socket Address the admin HTTP API
+            /// listens on, none is served without.
+        }))]
+        admin: Option<SocketAddr>,
+        #[builder(setters(doc {
+            /// ## Admin Token
+            /// 
+            /// The bearer token the actions of the admin
+            /// API require, it only listens on loopback
+            /// addresses without.
+        }))]
+        admin_token: Option<String>,
+    ) -> Self {
+        Self {
+            transport: Endpoint::memory_builder().bind(bind).build(),
+            sessions: Default::default(),
+            relay: Arc::new(Relay::new(timeouts, cache_size, authorizer, upstreams)),
+            timeouts,
+            auth_token_cache_size,
+            draining: Default::default(),
+            admin,
+            admin_token,
+            stopped: Notify::new(),
+        }
+    }
 }
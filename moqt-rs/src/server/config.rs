@@ -1,10 +1,13 @@
 use {
+    super::Upstream,
+    crate::{Timeouts, TlsVerification, types::Token},
     bon::Builder,
     clap::Parser,
-    core::net::SocketAddr,
+    core::{net::SocketAddr, time::Duration},
     serde::{Deserialize, Serialize},
     std::path::PathBuf,
     strum_lite::strum,
+    url::Url,
 };
 
 /// ## Configuration of [Relay](crate::Relay)
@@ -51,6 +54,227 @@ pub struct ServerConfig {
         /// Whether to use QUIC or WebTransport
     }))]
     pub protocol: Protocol,
+
+    /// Milliseconds clients may take to complete
+    /// the setup, defaults to 10 seconds
+    #[arg(long = "setup-timeout")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Setup Timeout
+        /// 
+        /// Milliseconds clients may take to complete
+        /// the setup, defaults to 10 seconds
+    }))]
+    pub setup_timeout: Option<u64>,
+
+    /// Milliseconds publishers may take to respond
+    /// to a Request, defaults to 10 seconds
+    #[arg(long = "request-timeout")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Request Timeout
+        /// 
+        /// Milliseconds publishers may take to respond
+        /// to a Request, defaults to 10 seconds
+    }))]
+    pub request_timeout: Option<u64>,
+
+    /// Milliseconds clients may take to send the
+    /// header of a data stream, defaults to 10 seconds
+    #[arg(long = "data-stream-timeout")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Data Stream Timeout
+        /// 
+        /// Milliseconds clients may take to send the
+        /// header of a data stream, defaults to 10 seconds
+    }))]
+    pub data_stream_timeout: Option<u64>,
+
+    /// Milliseconds after which Subscriptions expire
+    /// unless refreshed, never by default
+    #[arg(long = "subscription-expiry")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Subscription Expiry
+        /// 
+        /// Milliseconds after which Subscriptions expire
+        /// unless refreshed, never by default
+    }))]
+    pub subscription_expiry: Option<u64>,
+
+    /// Maximum bytes of Object payloads cached to
+    /// serve Fetches, defaults to 64 MiB
+    #[arg(long = "cache-size")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Cache Size
+        /// 
+        /// Maximum bytes of Object payloads cached to
+        /// serve Fetches, defaults to 64 MiB
+    }))]
+    pub cache_size: Option<usize>,
+
+    /// Maximum bytes of Authorization Tokens a client
+    /// may register per session, none by default
+    #[arg(long = "auth-token-cache-size")]
+    #[serde(default)]
+    #[builder(setters(doc {
+        /// ## Authorization Token Cache Size
+        /// 
+        /// Maximum bytes of Authorization Tokens a client
+        /// may register per session, none by default
+    }))]
+    pub auth_token_cache_size: Option<u64>,
+
+    /// Path to the allow list of Authorization Tokens,
+    /// all clients are authorized without
+    #[arg(long = "allow-list")]
+    #[serde(default)]
+    #[builder(into, setters(doc {
+        /// ## Allow List
+        /// 
+        /// Path to the [AllowList](crate::AllowList) of
+        /// Authorization Tokens, all clients are
+        /// authorized without
+    }))]
+    pub allow_list: Option<PathBuf>,
+
+    /// Path to the keys signed claim Authorization
+    /// Tokens are verified with
+    #[arg(long = "token-keys", conflicts_with = "allow_list")]
+    #[serde(default)]
+    #[builder(into, setters(doc {
+        /// ## Token Keys
+        /// 
+        /// Path to the [KeySet](crate::KeySet) signed
+        /// claim Authorization Tokens are verified with,
+        /// replaces the allow list
+    }))]
+    pub token_keys: Option<PathBuf>,
+
+    /// Relays or origins subscribed to for Tracks no
+    /// client publishes, tried in the given order
+    #[arg(long = "upstream")]
+    #[serde(default)]
+    #[builder(default, setters(doc {
+        /// ## Upstreams
+        /// 
+        /// Relays or origins subscribed to for Tracks no
+        /// client publishes, tried in the given order
+    }))]
+    pub upstreams: Vec<Url>,
+
+    /// PEM file with the CA certificates to trust
+    /// for upstreams instead of the native roots (QUIC only)
+    #[arg(long = "upstream-ca")]
+    #[serde(default)]
+    #[builder(into, setters(doc {
+        /// ## Upstream CA Certificates
+        /// 
+        /// PEM file with the CA certificates to trust
+        /// for upstreams instead of the native roots (QUIC only)
+    }))]
+    pub upstream_ca: Option<PathBuf>,
+
+    /// Accept self-signed certificates of upstreams
+    /// on localhost (QUIC only)
+    #[arg(long = "upstream-insecure-localhost")]
+    #[serde(default)]
+    #[builder(default, setters(doc {
+        /// ## Upstream Insecure Localhost
+        /// 
+        /// Accept self-signed certificates of upstreams
+        /// on localhost (QUIC only)
+    }))]
+    pub upstream_insecure_localhost: bool,
+
+    /// Authorization Token sent with the setup to
+    /// the upstreams
+    #[arg(long = "upstream-auth-token")]
+    #[serde(default)]
+    #[builder(into, setters(doc {
+        /// ## Upstream Authorization Token
+        /// 
+        /// Authorization Token sent with the setup to
+        /// the upstreams
+    }))]
+    pub upstream_auth_token: Option<String>,
+
+    /// The admin HTTP API will listen on this address,
+    /// it is not served by default
+    #[arg(long = "admin")]
+    #[serde(default)]
+    #[builder(into, setters(doc {
+        /// ## Admin Address
+        /// 
+        /// The Socket Address the admin HTTP API
+        /// listens on, it is not served by default
+    }))]
+    pub admin: Option<SocketAddr>,
+
+    /// Bearer token the admin HTTP API requires to
+    /// act, it only listens on loopback without
+    #[arg(long = "admin-token")]
+    #[serde(default)]
+    #[builder(into, setters(doc {
+        /// ## Admin Token
+        /// 
+        /// Bearer token the admin HTTP API requires to
+        /// act, it only listens on loopback without
+    }))]
+    pub admin_token: Option<String>,
+}
+
+impl ServerConfig {
+    /// How long clients may take before their
+    /// session is closed.
+    pub fn timeouts(&self) -> Timeouts {
+        let timeouts = Timeouts::from_millis(
+            self.setup_timeout,
+            self.request_timeout,
+            self.data_stream_timeout,
+        );
+        Timeouts {
+            subscription: self
+                .subscription_expiry
+                .map_or(Timeouts::SUBSCRIPTION, Duration::from_millis),
+            ..timeouts
+        }
+    }
+
+    /// The [upstreams](Self::upstreams), connected
+    /// to with the [protocol](Self::protocol) of
+    /// the relay.
+    pub fn upstreams(&self) -> Vec<Upstream> {
+        let tls = match (&self.upstream_ca, self.upstream_insecure_localhost) {
+            (_, true) => TlsVerification::InsecureLocalhost,
+            (Some(ca), false) => TlsVerification::CaFile(ca.clone()),
+            (None, false) => TlsVerification::NativeRoots,
+        };
+        let auth_token = self.upstream_auth_token.as_ref().map(|value| {
+            Token::builder()
+                .use_value()
+                .typ(0u8)
+                .value(value.as_bytes())
+                .build()
+        });
+
+        self.upstreams
+            .iter()
+            .map(|url| match self.protocol {
+                Protocol::Quic => Upstream::quic_builder()
+                    .connect(url.clone())
+                    .tls(tls.clone())
+                    .maybe_auth_token(auth_token.clone())
+                    .build(),
+                Protocol::WebTransport => Upstream::webtransport_builder()
+                    .connect(url.clone())
+                    .maybe_auth_token(auth_token.clone())
+                    .build(),
+            })
+            .collect()
+    }
 }
 
 strum! {
@@ -2,9 +2,13 @@ use {
     crate::{
         error::ControlStreamError,
         macro_helper::impl_from_msg_error,
-        transport::error::{ConnectionError, EndpointError},
+        transport::error::EndpointError,
+        types::message::GoawayError,
     },
+    core::net::SocketAddr,
     snafu::Snafu,
+    std::path::PathBuf,
+    varint::core::WriterError,
 };
 
 /// [Relay](crate::transport::Relay) Error
@@ -22,6 +26,73 @@ pub enum ServerError {
     /// ControlStream Error
     #[snafu(display("failed to establish the ControlStream"))]
     ControlStream { source: ControlStreamError },
+
+    /// invalid migration URL
+    #[snafu(display("failed to ask sessions to go away"))]
+    Goaway { source: GoawayError },
+
+    /// no session with this address
+    #[snafu(display("no session with {addr}"))]
+    UnknownSession { addr: SocketAddr },
+
+    /// invalid allow list
+    #[snafu(display("failed to load the allow list"))]
+    AllowList { source: AllowListError },
+
+    /// invalid key set
+    #[snafu(display("failed to load the token keys"))]
+    KeySet { source: KeySetError },
+}
+
+/// [AllowList](crate::AllowList) Error
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[snafu(visibility(pub), module(allow_list_ctx))]
+pub enum AllowListError {
+    /// unable to read the file
+    #[snafu(display("failed to read {}: {msg}", path.display()))]
+    Read { path: PathBuf, msg: String },
+
+    /// malformed line
+    #[snafu(display("invalid entry in line {line}: {reason}"))]
+    InvalidEntry { line: usize, reason: &'static str },
+}
+
+/// [KeySet](crate::KeySet) Error
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[snafu(visibility(pub), module(key_set_ctx))]
+pub enum KeySetError {
+    /// unable to read the file
+    #[snafu(display("failed to read {}: {msg}", path.display()))]
+    Read { path: PathBuf, msg: String },
+
+    /// malformed line
+    #[snafu(display("invalid entry in line {line}: {reason}"))]
+    InvalidEntry { line: usize, reason: &'static str },
+}
+
+/// [SignedClaims](crate::SignedClaims) Error
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[snafu(visibility(pub), module(sign_ctx))]
+pub enum SignError {
+    /// the Ed25519 seed was rejected
+    #[snafu(display("invalid signing key"))]
+    InvalidKey,
+
+    /// expiry before 1970 or too far ahead
+    #[snafu(display("expiry cannot be encoded"))]
+    Expiry,
+
+    /// unknown action bits too large
+    #[snafu(display("actions cannot be encoded"))]
+    Actions,
+
+    /// unable to encode the claims
+    #[snafu(display("failed to encode the claims"))]
+    VarInt { source: varint::Error },
+
+    /// unable to finish the encoding
+    #[snafu(display("failed to write the claims"))]
+    Writer { source: WriterError },
 }
 
 impl_from_msg_error! {
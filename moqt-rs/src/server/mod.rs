@@ -1,24 +1,43 @@
+mod admin;
+mod authorizer;
 mod builder;
 mod config;
 mod error;
+mod signed_claims;
+mod upstream;
 
 pub use {
+    admin::{NamespaceInfo, SessionInfo, SubscriptionInfo, TrackInfo},
+    authorizer::{AllowList, AuthRequest, Authorizer, Denial, RequestKind},
     config::{Protocol, ServerConfig},
-    error::ServerError,
+    error::{AllowListError, KeySetError, ServerError, SignError},
+    signed_claims::{Actions, KeySet, SignedClaims, SigningKey, VerifyingKey},
+    upstream::Upstream,
 };
 
 use {
     crate::{
-        Session, error::ControlStreamError, session::SessionHandle, transport::Endpoint,
-        types::error_code::Termination,
+        GOAWAY_TIMEOUT, Session, Timeouts,
+        error::{ControlStreamError, SessionHandleError},
+        relay::Relay,
+        session::{SessionHandle, SessionMessage},
+        transport::{Connection, Endpoint, memory},
+        types::{error_code::Termination, message::Goaway},
     },
-    core::net::SocketAddr,
+    core::{net::SocketAddr, time::Duration},
     dashmap::DashMap,
     error::ctx,
-    snafu::ResultExt,
-    tracing::{error, info},
+    snafu::{OptionExt, ResultExt},
+    std::sync::{Arc, OnceLock},
+    tokio::sync::Notify,
+    tracing::{debug, error, info, warn},
+    url::Url,
 };
 
+/// How often the [Server] checks its sessions
+/// for messages.
+const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// A Server is the MOQT **Relay**.
 ///
 /// It is named Server to keep it consistent
@@ -28,45 +47,210 @@ use {
 pub struct Server {
     transport: Endpoint,
     sessions: DashMap<SocketAddr, SessionHandle>,
-    // TODO add Client connections to other Relays/(ControlTower?) to query them for Tracks this Relay doesn't know
+    relay: Arc<Relay>,
+    timeouts: Timeouts,
+    /// Maximum bytes of Authorization Tokens a
+    /// client may register per session.
+    auth_token_cache_size: u64,
+    /// Set once all sessions were asked to go
+    /// away, with the migration URL if any.
+    draining: OnceLock<Option<Url>>,
+    /// Where the admin API listens, if anywhere.
+    admin: Option<SocketAddr>,
+    /// The bearer token the admin actions require.
+    admin_token: Option<String>,
+    /// Notified once the Server shut down.
+    stopped: Notify,
+}
+
+/// The sessions a [Server::goaway] applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoawayTarget {
+    /// Every current and future session.
+    All,
+    /// The session of the peer at this address.
+    Session(SocketAddr),
+}
+
+impl From<SocketAddr> for GoawayTarget {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Session(addr)
+    }
 }
-// TODO add HTTP server for dashboard and outside control, like shutdown, etc.
 
 impl Server {
     /// Launches the [Relay] making it run until
     /// termination or encountering an fatal error.
+    ///
+    /// Serves the admin API alongside if an
+    /// address was given for it.
     #[tracing::instrument(skip(self))]
-    pub async fn run(&self) {
+    pub async fn run(self: &Arc<Self>) {
         info!(
             addr = ?self.transport.local_address(),
             proto = self.transport.proto(),
             "Relay is running"
         );
+        let admin = self
+            .admin
+            .map(|bind| tokio::spawn(admin::serve(self.clone(), bind)));
+
+        let mut poll = tokio::time::interval(SESSION_POLL_INTERVAL);
+        // polling the sessions must not cancel a
+        // connection in the middle of being accepted
+        let accept = self.accept_session();
+        tokio::pin!(accept);
 
         loop {
             tokio::select! {
                 biased;
-                res = self.accept_session() => {
-                    match res {
-                        Ok(_) => info!("accepted new session"),
-                        Err(_) => error!("failed to accept new session")
+                res = &mut accept => {
+                    if res.is_err() {
+                        error!("failed to accept new connection");
                     }
+                    accept.set(self.accept_session());
+                }
+                _ = poll.tick() => self.recv_session_messages().await,
+                _ = self.stopped.notified() => break,
+            }
+        }
+
+        if let Some(admin) = admin {
+            admin.abort();
+        }
+        info!("Relay stopped");
+    }
+
+    /// The [Connector](memory::Connector) of an
+    /// in-memory Server.
+    pub fn memory_connector(&self) -> Option<memory::Connector> {
+        match &self.transport {
+            Endpoint::Memory(ep) => Some(ep.connector()),
+            _ => None,
+        }
+    }
+
+    /// Asks the sessions of `target` to migrate to
+    /// `url`, or to reconnect to the current URL
+    /// without one.
+    ///
+    /// Sessions still open after
+    /// [GOAWAY_TIMEOUT](crate::GOAWAY_TIMEOUT)
+    /// are closed. Once all sessions are asked to
+    /// go away, so are new ones.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn goaway<T>(&self, target: T, url: Option<Url>) -> Result<(), ServerError>
+    where
+        T: Into<GoawayTarget> + core::fmt::Debug,
+    {
+        // fail early on URLs exceeding the limit
+        Goaway::with_url(url.as_ref()).context(ctx::GoawaySnafu)?;
+
+        let sessions: Vec<(SocketAddr, SessionHandle)> = match target.into() {
+            GoawayTarget::All => {
+                if self.draining.set(url.clone()).is_err() {
+                    debug!("sessions are already going away");
+                    return Ok(());
+                }
+                self.sessions
+                    .iter()
+                    .map(|entry| (*entry.key(), entry.value().clone()))
+                    .collect()
+            }
+            GoawayTarget::Session(addr) => {
+                let session = self
+                    .sessions
+                    .get(&addr)
+                    .context(ctx::UnknownSessionSnafu { addr })?;
+                vec![(addr, session.clone())]
+            }
+        };
+
+        for (addr, session) in sessions {
+            if let Err(err) = session.send(SessionMessage::Goaway(url.clone())).await {
+                warn!(%addr, %err, "failed to ask session to go away");
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes the session of the peer at `addr`
+    /// with `code`.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn close_session(
+        &self,
+        addr: SocketAddr,
+        code: Termination,
+    ) -> Result<(), ServerError> {
+        let session = self
+            .sessions
+            .get(&addr)
+            .map(|session| session.clone())
+            .context(ctx::UnknownSessionSnafu { addr })?;
+        // the session ended meanwhile
+        session
+            .send(SessionMessage::Close(code))
+            .await
+            .ok()
+            .context(ctx::UnknownSessionSnafu { addr })
+    }
+
+    /// Asks all sessions to go away like
+    /// [goaway](Self::goaway) and stops
+    /// [run](Self::run) once they are closed.
+    ///
+    /// Sessions still open after
+    /// [GOAWAY_TIMEOUT] are closed.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn shutdown(&self, url: Option<Url>) -> Result<(), ServerError> {
+        self.goaway(GoawayTarget::All, url).await?;
+
+        let drained = tokio::time::timeout(GOAWAY_TIMEOUT, async {
+            while !self.sessions.is_empty() {
+                tokio::time::sleep(SESSION_POLL_INTERVAL).await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            let open: Vec<SocketAddr> = self.sessions.iter().map(|entry| *entry.key()).collect();
+            for addr in open {
+                if let Err(err) = self.close_session(addr, Termination::GoAwayTimeout).await {
+                    debug!(%addr, %err, "session closed before the shutdown");
                 }
-                // res = self.recv_session_messages() => {
-                //     if let Err(_err) = res {
-                //         todo!("what to do when server runs into an error? shouldn't happen, so panic?")
-                //     }
-                // }
-                // TODO check sessions channels and handle them
             }
         }
+
+        info!("shutting down");
+        self.stopped.notify_one();
+        Ok(())
     }
 
+    /// Accepts the next connection and sets up its
+    /// session in the background, a client slow to
+    /// set up does not hold up the next one.
     #[tracing::instrument(skip(self), err)]
-    async fn accept_session(&self) -> Result<(), ServerError> {
+    async fn accept_session(self: &Arc<Self>) -> Result<(), ServerError> {
         let conn = self.transport.accept().await.context(ctx::EndpointSnafu)?;
+        let server = self.clone();
+        tokio::spawn(async move {
+            if server.setup_session(conn).await.is_ok() {
+                info!("accepted new session");
+            }
+        });
+        Ok(())
+    }
 
-        let control_stream = match crate::ControlStream::accept(&conn).await {
+    /// Exchanges the setup on `conn` and spawns
+    /// the session once it succeeded.
+    #[tracing::instrument(skip_all, fields(addr = %conn.remote_addr()), err)]
+    async fn setup_session(&self, conn: Connection) -> Result<(), ServerError> {
+        let control_stream = match crate::ControlStream::accept(
+            &conn,
+            self.timeouts.setup,
+            self.auth_token_cache_size,
+        )
+        .await
+        {
             Ok(cs) => cs,
             Err(source @ ControlStreamError::NoSupportedVersion { .. }) => {
                 error!("unable to negotiate a version, dropping connection...");
@@ -81,35 +265,1215 @@ impl Server {
 
         // TODO I need a way to poll the session to
         // [ ] exchange Messages, like
-        //      [ ] forcing a Goaway to all/specific sessions
+        //      [x] forcing a Goaway to all/specific sessions
         //      [ ] collecting announced Tracks
         //      [ ] relaying Tracks to sessions
         // [ ] remove session and their associated announced/subscribed Tracks when they error
         // [ ] more?
         let addr = conn.remote_addr();
-        let handle = Session::spawn(conn, control_stream);
+        let handle = Session::spawn(conn, control_stream, self.relay.clone());
+
+        if let Some(url) = self.draining.get() {
+            debug!(%addr, "asking new session to go away");
+            if let Err(err) = handle.send(SessionMessage::Goaway(url.clone())).await {
+                warn!(%addr, %err, "failed to ask session to go away");
+            }
+        }
 
         self.sessions.insert(addr, handle);
         Ok(())
     }
 
-    async fn recv_session_messages(&self) -> Result<(), ServerError> {
-        let mut remove = Vec::new();
+    /// Handles the messages of all sessions and
+    /// removes the sessions which have ended.
+    async fn recv_session_messages(&self) {
+        let sessions: Vec<(SocketAddr, SessionHandle)> = self
+            .sessions
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+
+        for (addr, session) in sessions {
+            loop {
+                match session.recv().await {
+                    Ok(Some(SessionMessage::Error(err))) => {
+                        error!(%addr, %err, "session ran into an error")
+                    }
+                    Ok(Some(msg)) => debug!(%addr, ?msg, "ignoring session message"),
+                    Ok(None) => break,
+                    Err(SessionHandleError::ChannelClosed { .. }) => {
+                        debug!(%addr, "session ended");
+                        self.sessions.remove(&addr);
+                        break;
+                    }
+                    Err(err) => {
+                        error!(%addr, %err, "failed to receive from session");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            Client, ControlStream, FetchStream, NamespaceEvent, Subscription, TrackPublisher,
+            error::ClientError,
+            transport::{
+                Connection,
+                error::{ConnectionError, DatagramError},
+                memory::Conditions,
+            },
+            types::{
+                ContentExists, Forward, GroupOrder, Location, Parameters, StandaloneFetch, Token,
+                config::{DEFAULT_PRIORITY, SubscribeConfig},
+                error_code,
+                message::{
+                    ClientSetup, ControlMessage, MaxRequestId, PublishNamespace,
+                    PublishNamespaceOk, ServerSetup, SubscribeOk, SubscribeUpdate,
+                },
+            },
+        },
+        bytes::Bytes,
+        tokio::time::{Instant, timeout},
+        varint::{VarInt, VarIntNumber, Writer, core::ReferenceWriter},
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// What the in-memory test server is built with.
+    #[derive(Default)]
+    struct TestConfig {
+        timeouts: Timeouts,
+        auth_token_cache_size: u64,
+        authorizer: Option<Arc<dyn Authorizer>>,
+    }
+
+    fn spawn_memory_server(config: TestConfig) -> (Arc<Server>, memory::Connector) {
+        let server = Arc::new(
+            Server::memory_builder()
+                .bind(([127, 0, 0, 1], 4443))
+                .timeouts(config.timeouts)
+                .auth_token_cache_size(config.auth_token_cache_size)
+                .maybe_authorizer(config.authorizer)
+                .build(),
+        );
+        let connector = server.memory_connector().expect("memory server");
+
+        let running = server.clone();
+        tokio::spawn(async move { running.run().await });
+        (server, connector)
+    }
+
+    /// Connects a session publishing the Namespace
+    /// `chat` which never responds to any Request.
+    async fn silent_publisher(connector: &memory::Connector) -> (Connection, ControlStream) {
+        let transport = Connection::Memory(Box::new(
+            connector.connect(&Conditions::default()).unwrap(),
+        ));
+        let control_stream = ControlStream::open(&transport, Timeouts::SETUP)
+            .await
+            .unwrap();
+
+        let msg = PublishNamespace::builder()
+            .id(0u8)
+            .namespace(["chat"])
+            .build();
+        control_stream
+            .send(ControlMessage::PublishNamespace(msg))
+            .await
+            .unwrap();
+        loop {
+            match control_stream.recv().await.unwrap() {
+                ControlMessage::PublishNamespaceOk(_) => break,
+                ControlMessage::MaxRequestId(_) => (),
+                msg => panic!("unexpected {}", msg.key()),
+            }
+        }
+        (transport, control_stream)
+    }
+
+    #[tokio::test]
+    async fn memory_relay_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let conditions = Conditions::builder()
+            .latency(Duration::from_millis(1))
+            .build();
+
+        let publisher = Client::memory_builder()
+            .connect(connector.clone())
+            .conditions(conditions.clone())
+            .build()
+            .await
+            .unwrap();
+        let subscriber = Client::memory_builder()
+            .connect(connector)
+            .conditions(conditions)
+            .build()
+            .await
+            .unwrap();
+
+        let mut track = publisher.publish(["chat"], "text").await.unwrap();
+        let mut subscription = subscriber.subscribe(["chat"], "text", None).await.unwrap();
+
+        track
+            .write_object(Bytes::from_static(b"hello"))
+            .await
+            .unwrap();
+        let object = timeout(TIMEOUT, subscription.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"hello"));
+    }
+
+    #[tokio::test]
+    async fn memory_goaway_test() {
+        let (server, connector) = spawn_memory_server(TestConfig::default());
+
+        let publisher = Client::memory_builder()
+            .connect(connector.clone())
+            .build()
+            .await
+            .unwrap();
+        let subscriber = Client::memory_builder()
+            .connect(connector)
+            .reconnect(true)
+            .build()
+            .await
+            .unwrap();
+
+        let mut track = publisher.publish(["chat"], "text").await.unwrap();
+        let mut subscription = subscriber.subscribe(["chat"], "text", None).await.unwrap();
+
+        // the publisher connected first
+        let subscriber_addr = loop {
+            let addrs: Vec<SocketAddr> = server.sessions.iter().map(|e| *e.key()).collect();
+            if addrs.len() == 2 {
+                break addrs.into_iter().max().unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        };
+        // join polls the first future first to not miss the Goaway
+        let (goaway, res) = tokio::join!(
+            timeout(TIMEOUT, subscriber.goaway()),
+            server.goaway(subscriber_addr, None)
+        );
+        res.unwrap();
+        assert_eq!(goaway.unwrap().unwrap(), None);
+
+        // the subscription continues once re-issued on the new session
+        let object = timeout(TIMEOUT, async {
+            loop {
+                track
+                    .write_object(Bytes::from_static(b"migrated"))
+                    .await
+                    .unwrap();
+                let recv = timeout(Duration::from_millis(50), subscription.recv()).await;
+                if let Ok(object) = recv {
+                    break object.unwrap();
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"migrated"));
+    }
+
+    #[tokio::test]
+    async fn datagram_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (publisher, subscriber) = (client().await.unwrap(), client().await.unwrap());
+
+        let track = publisher.publish(["sensors"], "temp").await.unwrap();
+        let mut subscription = subscriber
+            .subscribe(["sensors"], "temp", None)
+            .await
+            .unwrap();
+        let datagrams = track.datagram_publisher();
+
+        let too_large = Bytes::from(vec![0; 1 << 16]);
+        assert!(matches!(
+            datagrams.send(0u8, 0u8, too_large),
+            Err(ClientError::Datagram {
+                source: DatagramError::TooLarge { .. }
+            })
+        ));
+
+        // datagrams may be lost, even in memory
+        let object = timeout(TIMEOUT, async {
+            loop {
+                datagrams
+                    .send(0u8, 0u8, Bytes::from_static(b"21"))
+                    .unwrap();
+                let recv = timeout(Duration::from_millis(50), subscription.recv()).await;
+                if let Ok(object) = recv {
+                    break object.unwrap();
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"21"));
+    }
+
+    #[tokio::test]
+    async fn setup_timeout_test() {
+        // never accepts the session
+        let server = Server::memory_builder()
+            .bind(([127, 0, 0, 1], 4443))
+            .build();
+        let timeouts = Timeouts::builder().setup(Duration::from_millis(20)).build();
+
+        let res = Client::memory_builder()
+            .connect(server.memory_connector().unwrap())
+            .timeouts(timeouts)
+            .build()
+            .await;
+        assert!(matches!(
+            res,
+            Err(ClientError::ControlStream {
+                source: ControlStreamError::Timeout { .. }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn silent_setup_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+
+        // never sends its ClientSetup
+        let _silent = connector.connect(&Conditions::default()).unwrap();
+        let client = Client::memory_builder().connect(connector).build();
+        let res = timeout(TIMEOUT, client).await.unwrap();
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn malformed_control_message_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let transport = Connection::Memory(Box::new(
+            connector.connect(&Conditions::default()).unwrap(),
+        ));
+        let encode = |msg: ControlMessage| {
+            let mut writer = ReferenceWriter::new();
+            msg.encode(&mut writer, Some(msg.len_bits().unwrap()))
+                .unwrap();
+            writer.finish().unwrap().to_vec()
+        };
+
+        let setup = ClientSetup::builder()
+            .version(crate::DRAFT_VERSION)
+            .max_request_id(0u8)
+            .build();
+        // the Message Length ends before the Request ID
+        let mut truncated = encode(ControlMessage::MaxRequestId(MaxRequestId::new(1u16 << 10)));
+        truncated[2] -= 1;
+        truncated.pop();
+
+        let (mut tx, _rx) = transport.open_bi().await.unwrap();
+        tx.write(&encode(ControlMessage::ClientSetup(setup)))
+            .await
+            .unwrap();
+        tx.write(&truncated).await.unwrap();
+
+        let err = timeout(TIMEOUT, transport.accept_uni())
+            .await
+            .unwrap()
+            .err();
+        assert!(matches!(
+            err,
+            Some(ConnectionError::MemoryClosed { code, .. })
+                if code == u64::from(Termination::ProtocolViolation.key())
+        ));
+    }
+
+    #[tokio::test]
+    async fn protocol_violation_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let client_setup = ClientSetup::builder()
+            .version(crate::DRAFT_VERSION)
+            .max_request_id(0u8)
+            .build();
+        let server_setup = ServerSetup::builder()
+            .version(crate::DRAFT_VERSION)
+            .build();
+        let violations = [
+            ControlMessage::GoAway(Goaway::new("")),
+            ControlMessage::ClientSetup(client_setup),
+            ControlMessage::ServerSetup(server_setup),
+            // the relay sent no Request with this ID
+            ControlMessage::PublishNamespaceOk(PublishNamespaceOk::new(99u8)),
+        ];
 
-        for session in &self.sessions {
-            let (addr, session) = session.pair();
-            let msg = match session.recv().await {
-                Ok(Some(msg)) => msg,
-                Ok(None) => continue,
-                Err(err) => {
-                    error!("session ran into an error: {}", err);
-                    remove.push(addr.to_owned());
-                    continue;
+        for msg in violations {
+            let transport = Connection::Memory(Box::new(
+                connector.connect(&Conditions::default()).unwrap(),
+            ));
+            let control_stream = ControlStream::open(&transport, Timeouts::SETUP)
+                .await
+                .unwrap();
+            let key = msg.key();
+            control_stream.send(msg).await.unwrap();
+
+            let err = timeout(TIMEOUT, transport.accept_uni())
+                .await
+                .unwrap()
+                .err();
+            assert!(
+                matches!(
+                    err,
+                    Some(ConnectionError::MemoryClosed { code, .. })
+                        if code == u64::from(Termination::ProtocolViolation.key())
+                ),
+                "{key}"
+            );
+        }
+
+        // only the offending sessions were closed
+        let client = Client::memory_builder().connect(connector).build();
+        assert!(timeout(TIMEOUT, client).await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn request_timeout_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let timeouts = Timeouts::builder()
+            .request(Duration::from_millis(20))
+            .build();
+
+        // the publisher never responds to the Subscribe of the relay
+        let _publisher = silent_publisher(&connector).await;
+        let subscriber = Client::memory_builder()
+            .connect(connector)
+            .timeouts(timeouts)
+            .build()
+            .await
+            .unwrap();
+
+        let res = subscriber.subscribe(["chat"], "text", None).await;
+        assert_eq!(
+            res.err(),
+            Some(ClientError::RequestTimeout {
+                timeout: Duration::from_millis(20)
+            })
+        );
+        // the session was closed
+        assert!(subscriber.subscribe(["chat"], "text", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn upstream_timeout_test() {
+        let timeouts = Timeouts::builder()
+            .request(Duration::from_millis(20))
+            .build();
+        let (_server, connector) = spawn_memory_server(TestConfig {
+            timeouts,
+            ..Default::default()
+        });
+
+        // the publisher never responds to the Subscribe of the relay
+        let (_transport, publisher) = silent_publisher(&connector).await;
+        let subscriber = Client::memory_builder()
+            .connect(connector)
+            .build()
+            .await
+            .unwrap();
+
+        let res = timeout(TIMEOUT, subscriber.subscribe(["chat"], "text", None))
+            .await
+            .unwrap();
+        assert!(matches!(
+            res,
+            Err(ClientError::SubscribeRejected {
+                code: error_code::Subscribe::Timeout,
+                ..
+            })
+        ));
+        // only the Subscribe was rejected, the sessions stay open
+        let msg = PublishNamespace::builder()
+            .id(2u8)
+            .namespace(["other"])
+            .build();
+        publisher
+            .send(ControlMessage::PublishNamespace(msg))
+            .await
+            .unwrap();
+        let ok = timeout(TIMEOUT, async {
+            loop {
+                match publisher.recv().await.unwrap() {
+                    ControlMessage::PublishNamespaceOk(ok) => break ok,
+                    ControlMessage::Subscribe(_) | ControlMessage::MaxRequestId(_) => (),
+                    msg => panic!("unexpected {}", msg.key()),
                 }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(ok.request_id.number::<u64>(), 2);
+        assert!(subscriber.subscribe_namespace(["other"]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn fetch_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+
+        let publisher = Client::memory_builder()
+            .connect(connector.clone())
+            .build()
+            .await
+            .unwrap();
+        let subscriber = Client::memory_builder()
+            .connect(connector)
+            .build()
+            .await
+            .unwrap();
+        let fetch = |start: (u8, u8), end: (u8, u8)| {
+            StandaloneFetch::builder()
+                .namespace(["video"])
+                .name("hd")
+                .start(start.0, start.1)
+                .end(end.0, end.1)
+                .build()
+        };
+
+        let res = subscriber
+            .fetch(fetch((0, 0), (1, 0)), GroupOrder::Ascending)
+            .await;
+        assert!(matches!(
+            res.err(),
+            Some(ClientError::FetchRejected {
+                code: error_code::Fetch::TrackDoesNotExist,
+                ..
+            })
+        ));
+
+        let mut track = publisher.publish(["video"], "hd").await.unwrap();
+        let mut subscription = subscriber.subscribe(["video"], "hd", None).await.unwrap();
+        for payload in [&b"first"[..], b"second"] {
+            track
+                .write_object(Bytes::from_static(payload))
+                .await
+                .unwrap();
+        }
+        track.new_group().await.unwrap();
+        track
+            .write_object(Bytes::from_static(b"third"))
+            .await
+            .unwrap();
+        // the relay cached all Objects once they were forwarded
+        for _ in 0..3 {
+            timeout(TIMEOUT, subscription.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        let mut stream = subscriber
+            .fetch(fetch((0, 1), (1, 0)), GroupOrder::Descending)
+            .await
+            .unwrap();
+        let mut locations = Vec::new();
+        while let Some(object) = timeout(TIMEOUT, stream.next()).await.unwrap().unwrap() {
+            locations.push(object.location());
+        }
+        assert_eq!(
+            locations,
+            [Location::new(1u8, 0u8), Location::new(0u8, 1u8)]
+        );
+
+        let res = subscriber
+            .fetch(fetch((2, 0), (3, 0)), GroupOrder::Ascending)
+            .await;
+        assert!(matches!(
+            res.err(),
+            Some(ClientError::FetchRejected {
+                code: error_code::Fetch::NoObjects,
+                ..
+            })
+        ));
+
+        let res = subscriber
+            .fetch(fetch((1, 0), (0, 0)), GroupOrder::Ascending)
+            .await;
+        assert!(matches!(
+            res.err(),
+            Some(ClientError::FetchRejected {
+                code: error_code::Fetch::InvalidRange,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn joining_fetch_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (publisher, viewer, player) = (
+            client().await.unwrap(),
+            client().await.unwrap(),
+            client().await.unwrap(),
+        );
+
+        let mut track = publisher.publish(["video"], "hd").await.unwrap();
+        let mut viewing = viewer.subscribe(["video"], "hd", None).await.unwrap();
+        for payload in [&b"first"[..], b"second"] {
+            track
+                .write_object(Bytes::from_static(payload))
+                .await
+                .unwrap();
+        }
+        track.new_group().await.unwrap();
+        track
+            .write_object(Bytes::from_static(b"third"))
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            timeout(TIMEOUT, viewing.recv()).await.unwrap().unwrap();
+        }
+
+        let config = SubscribeConfig::builder()
+            .sub_prio(DEFAULT_PRIORITY)
+            .group_order(GroupOrder::Original)
+            .forward(Forward::Enabled)
+            .with_largest_object()
+            .build();
+        let playing = player
+            .subscribe(["video"], "hd", Some(config))
+            .await
+            .unwrap();
+        assert_eq!(
+            playing.subscribe_ok().largest_location,
+            Some(Location::new(1u8, 0u8))
+        );
+
+        let fetched = async |stream: Result<FetchStream, ClientError>| {
+            let mut stream = stream.unwrap();
+            let mut locations = Vec::new();
+            while let Some(object) = timeout(TIMEOUT, stream.next()).await.unwrap().unwrap() {
+                locations.push(object.location());
+            }
+            locations
+        };
+        let relative = player
+            .relative_joining_fetch(&playing, 1u8, GroupOrder::Ascending)
+            .await;
+        assert_eq!(
+            fetched(relative).await,
+            [
+                Location::new(0u8, 0u8),
+                Location::new(0u8, 1u8),
+                Location::new(1u8, 0u8)
+            ]
+        );
+        let absolute = player
+            .absolute_joining_fetch(&playing, 1u8, GroupOrder::Ascending)
+            .await;
+        assert_eq!(fetched(absolute).await, [Location::new(1u8, 0u8)]);
+
+        // the Subscription belongs to another session
+        let res = publisher
+            .relative_joining_fetch(&playing, 1u8, GroupOrder::Ascending)
+            .await;
+        assert!(matches!(
+            res.err(),
+            Some(ClientError::FetchRejected {
+                code: error_code::Fetch::InvalidJoiningRequestId,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn subscribe_namespace_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (alice, bob, carol) = (
+            client().await.unwrap(),
+            client().await.unwrap(),
+            client().await.unwrap(),
+        );
+
+        let _alice_ns = alice
+            .publish_namespace(["chat", "lobby", "alice"])
+            .await
+            .unwrap();
+        let _bob_ns = bob
+            .publish_namespace(["chat", "lobby", "bob"])
+            .await
+            .unwrap();
+        let taken = carol.publish_namespace(["chat", "lobby", "alice"]).await;
+        assert!(matches!(
+            taken.err(),
+            Some(ClientError::PublishNamespaceRejected {
+                code: error_code::PublishNamespace::Uninterested,
+                ..
+            })
+        ));
+
+        let mut lobby = bob.subscribe_namespace(["chat", "lobby"]).await.unwrap();
+        // a session does not learn about its own Namespaces
+        assert_eq!(
+            timeout(TIMEOUT, lobby.recv()).await.unwrap(),
+            Some(NamespaceEvent::Published(["chat", "lobby", "alice"].into()))
+        );
+
+        let carol_ns = carol
+            .publish_namespace(["chat", "lobby", "carol"])
+            .await
+            .unwrap();
+        assert_eq!(
+            timeout(TIMEOUT, lobby.recv()).await.unwrap(),
+            Some(NamespaceEvent::Published(["chat", "lobby", "carol"].into()))
+        );
+        drop(carol_ns);
+        assert_eq!(
+            timeout(TIMEOUT, lobby.recv()).await.unwrap(),
+            Some(NamespaceEvent::Done(["chat", "lobby", "carol"].into()))
+        );
+
+        let overlap = bob.subscribe_namespace(["chat"]).await;
+        assert!(matches!(
+            overlap.err(),
+            Some(ClientError::SubscribeNamespaceRejected {
+                code: error_code::SubscribeNamespace::NamespacePrefixOverlap,
+                ..
+            })
+        ));
+        let unknown = bob.subscribe_namespace(["unknown"]).await;
+        assert!(matches!(
+            unknown.err(),
+            Some(ClientError::SubscribeNamespaceRejected {
+                code: error_code::SubscribeNamespace::NamespacePrefixUnknown,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn track_status_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (publisher, monitor) = (client().await.unwrap(), client().await.unwrap());
+
+        let mut track = publisher.publish(["video"], "hd").await.unwrap();
+        let mut viewing = monitor.subscribe(["video"], "hd", None).await.unwrap();
+        track
+            .write_object(Bytes::from_static(b"first"))
+            .await
+            .unwrap();
+        timeout(TIMEOUT, viewing.recv()).await.unwrap().unwrap();
+
+        let ok = monitor.track_status(["video"], "hd").await.unwrap();
+        assert_eq!(ok.content_exists, ContentExists::Yes);
+        assert_eq!(ok.largest_location, Some(Location::new(0u8, 0u8)));
+        assert_eq!(ok.group_order, GroupOrder::Ascending);
+
+        let res = monitor.track_status(["audio"], "hd").await;
+        assert!(matches!(
+            res.err(),
+            Some(ClientError::TrackStatusRejected {
+                code: error_code::TrackStatus::TrackDoesNotExist,
+                ..
+            })
+        ));
+
+        // forwarded to the publisher of the Namespace
+        let _live = publisher.publish_namespace(["live"]).await.unwrap();
+        let res = monitor.track_status(["live"], "hd").await;
+        assert!(matches!(
+            res.err(),
+            Some(ClientError::TrackStatusRejected {
+                code: error_code::TrackStatus::NotSupported,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn subscribe_update_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (publisher, viewer) = (client().await.unwrap(), client().await.unwrap());
+
+        let mut track = publisher.publish(["tiles"], "cam").await.unwrap();
+        let mut tile = viewer.subscribe(["tiles"], "cam", None).await.unwrap();
+        let forwarding = async |track: &TrackPublisher, forward: bool| {
+            timeout(TIMEOUT, async {
+                while track.is_forwarding() != forward {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            })
+            .await
+            .unwrap();
+        };
+
+        track.write_object(Bytes::from_static(b"a")).await.unwrap();
+        let object = timeout(TIMEOUT, tile.recv()).await.unwrap().unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"a"));
+
+        // the only Subscriber paused, so does the publisher
+        tile.update(0u8, 1u8, None::<u8>, DEFAULT_PRIORITY, false)
+            .await
+            .unwrap();
+        forwarding(&track, false).await;
+        track.write_object(Bytes::from_static(b"b")).await.unwrap();
+
+        tile.update(0u8, 2u8, None::<u8>, DEFAULT_PRIORITY, true)
+            .await
+            .unwrap();
+        forwarding(&track, true).await;
+        track.write_object(Bytes::from_static(b"c")).await.unwrap();
+        let object = timeout(TIMEOUT, tile.recv()).await.unwrap().unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"c"));
+
+        let unknown = SubscribeUpdate::builder()
+            .id(99u8)
+            .start(0u8, 0u8)
+            .end_group(0u8)
+            .subscriber_priority(DEFAULT_PRIORITY)
+            .forward(true)
+            .build();
+        assert_eq!(
+            viewer.update_subscription(unknown).await,
+            Err(ClientError::UnknownSubscription { request_id: 99 })
+        );
+
+        // widening the Subscription closes the session
+        let widened = SubscribeUpdate::builder()
+            .id(tile.request_id())
+            .start(0u8, 0u8)
+            .end_group(0u8)
+            .subscriber_priority(DEFAULT_PRIORITY)
+            .forward(true)
+            .build();
+        viewer.update_subscription(widened).await.unwrap();
+        assert_eq!(timeout(TIMEOUT, tile.recv()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn upstream_update_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let (_transport, publisher) = silent_publisher(&connector).await;
+        let viewer = Client::memory_builder()
+            .connect(connector)
+            .build()
+            .await
+            .unwrap();
+        let next = async |publisher: &ControlStream| {
+            timeout(TIMEOUT, async {
+                loop {
+                    match publisher.recv().await.unwrap() {
+                        ControlMessage::MaxRequestId(_) => (),
+                        msg => break msg,
+                    }
+                }
+            })
+            .await
+            .unwrap()
+        };
+
+        // the Track has Objects, none of them reached the relay
+        let respond = async {
+            let ControlMessage::Subscribe(subscribe) = next(&publisher).await else {
+                panic!("expected a subscribe");
             };
+            let ok = SubscribeOk {
+                request_id: subscribe.request_id,
+                alias: 0u8.into(),
+                expires: Duration::ZERO,
+                group_order: GroupOrder::Ascending,
+                content_exists: ContentExists::Yes,
+                largest_location: Some(Location::new(5u8, 3u8)),
+                parameters: Parameters::default(),
+            };
+            publisher
+                .send(ControlMessage::SubscribeOk(ok))
+                .await
+                .unwrap();
+        };
+        let (tile, ()) = tokio::join!(viewer.subscribe(["chat"], "text", None), respond);
+        let tile = tile.unwrap();
+
+        // pausing keeps the start after the Largest Location
+        tile.update(6u8, 0u8, None::<u8>, DEFAULT_PRIORITY, false)
+            .await
+            .unwrap();
+        let ControlMessage::SubscribeUpdate(update) = next(&publisher).await else {
+            panic!("expected a subscribe update");
+        };
+        assert_eq!(update.start_location, Location::new(5u8, 4u8));
+        assert_eq!(update.end_group.number::<u64>(), 0);
+        assert_eq!(update.forward, Forward::Disabled);
+    }
+
+    #[tokio::test]
+    async fn publish_done_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig::default());
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (publisher, viewer) = (client().await.unwrap(), client().await.unwrap());
+
+        let mut track = publisher.publish(["tiles"], "map").await.unwrap();
+        let mut whole = viewer.subscribe(["tiles"], "map", None).await.unwrap();
+        let first = SubscribeConfig::builder()
+            .sub_prio(DEFAULT_PRIORITY)
+            .group_order(GroupOrder::Original)
+            .forward(Forward::Enabled)
+            .with_absolute_range(Location::new(0u8, 0u8), 0u8)
+            .build();
+        let mut first = viewer
+            .subscribe(["tiles"], "map", Some(first))
+            .await
+            .unwrap();
+
+        track.write_object(Bytes::from_static(b"a")).await.unwrap();
+        track.write_object(Bytes::from_static(b"b")).await.unwrap();
+        track.new_group().await.unwrap();
+        track.write_object(Bytes::from_static(b"c")).await.unwrap();
+        track.finish().await.unwrap();
 
-            todo!("handle session message: {:?}", msg);
+        // ends once its end Group was forwarded
+        for payload in [b"a", b"b"] {
+            let object = timeout(TIMEOUT, first.recv()).await.unwrap().unwrap();
+            assert_eq!(object.payload, Bytes::from_static(payload));
         }
-        Ok(())
+        assert_eq!(timeout(TIMEOUT, first.recv()).await.unwrap(), None);
+        let done = first.done().unwrap();
+        assert_eq!(done.code, error_code::PublishDone::SubscriptionEnded);
+        assert_eq!(done.stream_count.number::<u64>(), 1);
+
+        // every stream arrives before the Subscription ends
+        let mut objects = 0;
+        while timeout(TIMEOUT, whole.recv()).await.unwrap().is_some() {
+            objects += 1;
+        }
+        assert_eq!(objects, 4);
+        let done = whole.done().unwrap();
+        assert_eq!(done.code, error_code::PublishDone::TrackEnded);
+        assert_eq!(done.stream_count.number::<u64>(), 2);
+    }
+
+    #[tokio::test]
+    async fn subscription_expiry_test() {
+        let expiry = Duration::from_millis(100);
+        let (_server, connector) = spawn_memory_server(TestConfig {
+            timeouts: Timeouts::builder().subscription(expiry).build(),
+            ..Default::default()
+        });
+        let client = |refresh| {
+            Client::memory_builder()
+                .connect(connector.clone())
+                .timeouts(Timeouts::builder().refresh(refresh).build())
+                .build()
+        };
+        let publisher = client(Timeouts::REFRESH).await.unwrap();
+        let (refreshing, stale) = (
+            client(Timeouts::REFRESH).await.unwrap(),
+            client(Duration::ZERO).await.unwrap(),
+        );
+
+        let mut track = publisher.publish(["sensors"], "temp").await.unwrap();
+        let mut probe = publisher.subscribe(["sensors"], "temp", None).await.unwrap();
+        track.write_object(Bytes::from_static(b"20")).await.unwrap();
+        let object = timeout(TIMEOUT, probe.recv()).await.unwrap().unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"20"));
+
+        // starts with the Group after the Largest
+        // Object the relay has
+        let mut live = refreshing
+            .subscribe(["sensors"], "temp", None)
+            .await
+            .unwrap();
+        let mut expired = stale.subscribe(["sensors"], "temp", None).await.unwrap();
+        assert_eq!(live.subscribe_ok().expires, expiry);
+        assert_eq!(
+            live.subscribe_ok().largest_location,
+            Some(Location::new(0u8, 0u8))
+        );
+
+        // never refreshed
+        assert_eq!(timeout(TIMEOUT, expired.recv()).await.unwrap(), None);
+        let done = expired.done().unwrap();
+        assert_eq!(done.code, error_code::PublishDone::Expired);
+
+        // refreshed before every expiry, from the
+        // Group it started at
+        tokio::time::sleep(expiry * 3).await;
+        track.write_object(Bytes::from_static(b"20")).await.unwrap();
+        track.new_group().await.unwrap();
+        track.write_object(Bytes::from_static(b"21")).await.unwrap();
+        let object = timeout(TIMEOUT, live.recv()).await.unwrap().unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"21"));
+        assert_eq!(live.done(), None);
+    }
+
+    #[tokio::test]
+    async fn auth_token_alias_test() {
+        let (_server, connector) = spawn_memory_server(TestConfig {
+            auth_token_cache_size: 4,
+            ..Default::default()
+        });
+
+        let publisher = Client::memory_builder()
+            .connect(connector.clone())
+            .build()
+            .await
+            .unwrap();
+        let subscriber = Client::memory_builder()
+            .connect(connector)
+            .build()
+            .await
+            .unwrap();
+        let _track = publisher.publish(["chat"], "text").await.unwrap();
+        let config = |token: Token| {
+            SubscribeConfig::builder()
+                .sub_prio(DEFAULT_PRIORITY)
+                .group_order(GroupOrder::Original)
+                .forward(Forward::Enabled)
+                .with_largest_object()
+                .auth_token(token)
+                .build()
+        };
+
+        let register = Token::builder()
+            .register()
+            .alias(1u8)
+            .typ(0u8)
+            .value([1, 2, 3])
+            .build();
+        let use_alias = Token::builder().use_alias().alias(1u8).build();
+        for token in [register, use_alias] {
+            let res = subscriber
+                .subscribe(["chat"], "text", Some(config(token)))
+                .await;
+            assert!(res.is_ok());
+        }
+
+        // the session is closed for an unknown alias
+        let unknown = Token::builder().use_alias().alias(2u8).build();
+        let res = timeout(
+            TIMEOUT,
+            subscriber.subscribe(["chat"], "text", Some(config(unknown))),
+        )
+        .await
+        .unwrap();
+        assert!(res.is_err());
+        assert!(subscriber.subscribe(["chat"], "text", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn allow_list_test() {
+        let list: AllowList = "pub publish chat\nsub subscribe chat text".parse().unwrap();
+        let (_server, connector) = spawn_memory_server(TestConfig {
+            authorizer: Some(Arc::new(list)),
+            ..Default::default()
+        });
+        let client = |token: Option<&str>| {
+            Client::memory_builder()
+                .connect(connector.clone())
+                .maybe_auth_token(token.map(|token| {
+                    Token::builder()
+                        .use_value()
+                        .typ(0u8)
+                        .value(token.as_bytes())
+                        .build()
+                }))
+                .build()
+        };
+
+        let publisher = client(Some("pub")).await.unwrap();
+        let _track = publisher.publish(["chat"], "text").await.unwrap();
+        let res = publisher.publish(["news"], "text").await;
+        assert!(matches!(
+            res,
+            Err(ClientError::PublishRejected {
+                code: error_code::Publish::Unauthorized,
+                ..
+            })
+        ));
+
+        let subscriber = client(Some("sub")).await.unwrap();
+        assert!(subscriber.subscribe(["chat"], "text", None).await.is_ok());
+        let res = subscriber.subscribe(["chat"], "other", None).await;
+        assert!(matches!(
+            res,
+            Err(ClientError::SubscribeRejected {
+                code: error_code::Subscribe::Unauthorized,
+                ..
+            })
+        ));
+        let res = subscriber.publish_namespace(["chat"]).await;
+        assert!(matches!(
+            res,
+            Err(ClientError::PublishNamespaceRejected {
+                code: error_code::PublishNamespace::Unauthorized,
+                ..
+            })
+        ));
+
+        // the session is closed without a listed token,
+        // possibly before the setup completed
+        let res = match client(None).await {
+            Ok(stranger) => timeout(TIMEOUT, stranger.subscribe(["chat"], "text", None))
+                .await
+                .unwrap()
+                .map(drop),
+            Err(err) => Err(err),
+        };
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn upstream_relay_test() {
+        let origin = Arc::new(
+            Server::memory_builder()
+                .bind(([127, 0, 0, 2], 4443))
+                .build(),
+        );
+        let upstream = Upstream::memory_builder()
+            .connect(origin.memory_connector().expect("memory server"))
+            .build();
+        let running = origin.clone();
+        tokio::spawn(async move { running.run().await });
+
+        let edge = Arc::new(
+            Server::memory_builder()
+                .bind(([127, 0, 0, 1], 4443))
+                .upstreams(vec![upstream])
+                .build(),
+        );
+        let connector = edge.memory_connector().expect("memory server");
+        let running = edge.clone();
+        tokio::spawn(async move { running.run().await });
+
+        let publisher = Client::memory_builder()
+            .connect(origin.memory_connector().expect("memory server"))
+            .build()
+            .await
+            .unwrap();
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (first, second) = (client().await.unwrap(), client().await.unwrap());
+
+        // both share the Subscription of the edge
+        let mut track = publisher.publish(["live"], "video").await.unwrap();
+        let mut subscriptions = [
+            first.subscribe(["live"], "video", None).await.unwrap(),
+            second.subscribe(["live"], "video", None).await.unwrap(),
+        ];
+        track
+            .write_object(Bytes::from_static(b"key"))
+            .await
+            .unwrap();
+        for subscription in &mut subscriptions {
+            let object = timeout(TIMEOUT, subscription.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(object.payload, Bytes::from_static(b"key"));
+        }
+
+        // the last Subscriber leaving ends it
+        drop(subscriptions);
+        timeout(TIMEOUT, async {
+            while !format!("{:?}", edge.relay).contains("tracks: 0") {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // Fetches of Tracks unknown to the edge are
+        // served by the origin, once it cached them
+        let mut audio = publisher.publish(["live"], "audio").await.unwrap();
+        audio.write_object(Bytes::from_static(b"hi")).await.unwrap();
+        let fetch = StandaloneFetch::builder()
+            .namespace(["live"])
+            .name("audio")
+            .start(0u8, 0u8)
+            .end(1u8, 0u8)
+            .build();
+        let mut stream = timeout(TIMEOUT, async {
+            loop {
+                match first.fetch(fetch.clone(), GroupOrder::Ascending).await {
+                    Ok(stream) => break stream,
+                    Err(ClientError::FetchRejected {
+                        code: error_code::Fetch::NoObjects,
+                        ..
+                    }) => tokio::time::sleep(Duration::from_millis(1)).await,
+                    Err(err) => panic!("{err}"),
+                }
+            }
+        })
+        .await
+        .unwrap();
+        let object = timeout(TIMEOUT, stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(object.payload, Bytes::from_static(b"hi").into());
+
+        let res = first.subscribe(["other"], "video", None).await;
+        assert!(matches!(
+            res.err(),
+            Some(ClientError::SubscribeRejected {
+                code: error_code::Subscribe::TrackDoesNotExist,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn unreachable_upstream_test() {
+        // accepts sessions, never answers their setup
+        let origin = Server::memory_builder()
+            .bind(([127, 0, 0, 2], 4443))
+            .build();
+        let upstream = Upstream::memory_builder()
+            .connect(origin.memory_connector().expect("memory server"))
+            .build();
+
+        let setup = Duration::from_millis(200);
+        let edge = Arc::new(
+            Server::memory_builder()
+                .bind(([127, 0, 0, 1], 4443))
+                .timeouts(Timeouts::builder().setup(setup).build())
+                .upstreams(vec![upstream])
+                .build(),
+        );
+        let connector = edge.memory_connector().expect("memory server");
+        let running = edge.clone();
+        tokio::spawn(async move { running.run().await });
+
+        let client = || Client::memory_builder().connect(connector.clone()).build();
+        let (first, second) = (client().await.unwrap(), client().await.unwrap());
+        let rejected = |res: Result<Subscription, ClientError>| {
+            matches!(
+                res.err(),
+                Some(ClientError::SubscribeRejected {
+                    code: error_code::Subscribe::TrackDoesNotExist,
+                    ..
+                })
+            )
+        };
+
+        // both wait for the same attempt
+        let started = Instant::now();
+        let (video, audio) = tokio::join!(
+            first.subscribe(["live"], "video", None),
+            second.subscribe(["live"], "audio", None),
+        );
+        assert!(rejected(video) && rejected(audio));
+        assert!(started.elapsed() < setup * 2);
+
+        // and the failure is remembered for a while
+        let started = Instant::now();
+        assert!(rejected(first.subscribe(["live"], "text", None).await));
+        assert!(started.elapsed() < setup);
     }
 }
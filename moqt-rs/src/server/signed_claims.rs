@@ -0,0 +1,669 @@
+use {
+    super::{
+        AuthRequest, Authorizer, Denial, RequestKind,
+        error::{KeySetError, SignError, key_set_ctx, sign_ctx},
+    },
+    crate::types::{Name, Namespace, Token, TokenType},
+    core::{fmt, ops::BitOr, str::FromStr, time::Duration},
+    ring::{
+        hmac,
+        signature::{self, Ed25519KeyPair, KeyPair},
+    },
+    snafu::{OptionExt, ResultExt, ensure},
+    std::{
+        collections::HashMap,
+        path::Path,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    varint::{
+        VarInt, VarIntBytes, VarIntNumber, Writer,
+        core::{ReferenceReader, ReferenceWriter},
+        x,
+    },
+};
+
+/// ## Signed Claims
+///
+/// The Value of a [SignedClaims](TokenType::SignedClaims)
+/// Token: what its holder may do until it expires,
+/// signed by an auth service so relays verify it
+/// offline with a [KeySet].
+///
+/// Encoded as the Algorithm, Key ID, Namespace
+/// prefix, Track Name glob, [Actions] and expiry
+/// in Unix seconds, followed by the signature over
+/// all of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedClaims {
+    /// The Namespaces starting with this prefix.
+    pub namespace: Namespace,
+
+    /// The Track Names matching this glob, in
+    /// which `*` matches any run of bytes.
+    pub name: Name,
+
+    /// The Requests the holder may send.
+    pub actions: Actions,
+
+    /// The Token is expired from then on.
+    pub expires: SystemTime,
+}
+
+/// The signed part of [SignedClaims].
+#[derive(Debug, VarInt, PartialEq, Clone)]
+struct Payload {
+    algorithm: x!(i),
+    key_id: x!(i),
+    namespace: Namespace,
+    name: Name,
+    actions: x!(i),
+    expires: x!(i),
+}
+
+/// The signature algorithms of [SignedClaims].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    HmacSha256 = 0x1,
+    Ed25519 = 0x2,
+}
+
+impl Algorithm {
+    fn signature_len(self) -> usize {
+        match self {
+            Self::HmacSha256 => 32,
+            Self::Ed25519 => 64,
+        }
+    }
+}
+
+impl TryFrom<u64> for Algorithm {
+    type Error = Denial;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        match value {
+            0x1 => Ok(Self::HmacSha256),
+            0x2 => Ok(Self::Ed25519),
+            _ => Err(Denial::MalformedAuthToken),
+        }
+    }
+}
+
+/// ## Actions
+///
+/// The Requests [SignedClaims] grant, as bit
+/// flags. The setup is granted with any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Actions(u64);
+
+impl Actions {
+    /// PublishNamespace and Publish.
+    pub const PUBLISH: Self = Self(0x1);
+
+    /// Subscribe, SubscribeNamespace and Fetch.
+    pub const SUBSCRIBE: Self = Self(0x2);
+
+    /// Whether all Actions of `other` are granted.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether a Request of `kind` is granted.
+    pub fn allows(self, kind: RequestKind) -> bool {
+        match kind {
+            RequestKind::Setup => true,
+            kind if kind.is_publish() => self.contains(Self::PUBLISH),
+            _ => self.contains(Self::SUBSCRIBE),
+        }
+    }
+}
+
+impl BitOr for Actions {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// ## Signing Key
+///
+/// A key an auth service signs [SignedClaims]
+/// with.
+#[derive(Clone)]
+pub enum SigningKey {
+    /// The shared secret.
+    HmacSha256(Vec<u8>),
+
+    /// The 32 byte seed of the key pair.
+    Ed25519([u8; 32]),
+}
+
+impl SigningKey {
+    fn algorithm(&self) -> Algorithm {
+        match self {
+            Self::HmacSha256(_) => Algorithm::HmacSha256,
+            Self::Ed25519(_) => Algorithm::Ed25519,
+        }
+    }
+
+    /// The [VerifyingKey] relays need to verify
+    /// the signatures of this key.
+    pub fn verifying_key(&self) -> Result<VerifyingKey, SignError> {
+        Ok(match self {
+            Self::HmacSha256(secret) => VerifyingKey::HmacSha256(secret.clone()),
+            Self::Ed25519(seed) => {
+                let pair = Ed25519KeyPair::from_seed_unchecked(seed)
+                    .ok()
+                    .context(sign_ctx::InvalidKeySnafu)?;
+                VerifyingKey::Ed25519(pair.public_key().as_ref().to_vec())
+            }
+        })
+    }
+
+    fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, SignError> {
+        Ok(match self {
+            Self::HmacSha256(secret) => {
+                let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+                hmac::sign(&key, payload).as_ref().to_vec()
+            }
+            Self::Ed25519(seed) => Ed25519KeyPair::from_seed_unchecked(seed)
+                .ok()
+                .context(sign_ctx::InvalidKeySnafu)?
+                .sign(payload)
+                .as_ref()
+                .to_vec(),
+        })
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SigningKey")
+            .field(&self.algorithm())
+            .finish_non_exhaustive()
+    }
+}
+
+/// ## Verifying Key
+///
+/// A key a relay verifies [SignedClaims] with.
+#[derive(Clone, PartialEq, Eq)]
+pub enum VerifyingKey {
+    /// The shared secret.
+    HmacSha256(Vec<u8>),
+
+    /// The 32 byte public key.
+    Ed25519(Vec<u8>),
+}
+
+impl VerifyingKey {
+    /// Whether `signature` of `payload` was made
+    /// with the signing key of this key.
+    fn verify(&self, algorithm: Algorithm, payload: &[u8], signature: &[u8]) -> bool {
+        match (self, algorithm) {
+            (Self::HmacSha256(secret), Algorithm::HmacSha256) => {
+                let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+                hmac::verify(&key, payload, signature).is_ok()
+            }
+            (Self::Ed25519(public), Algorithm::Ed25519) => {
+                signature::UnparsedPublicKey::new(&signature::ED25519, public)
+                    .verify(payload, signature)
+                    .is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for VerifyingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HmacSha256(_) => f.write_str("VerifyingKey::HmacSha256(..)"),
+            Self::Ed25519(public) => f
+                .debug_tuple("VerifyingKey::Ed25519")
+                .field(public)
+                .finish(),
+        }
+    }
+}
+
+impl SignedClaims {
+    /// Signs these claims with `key`, known to
+    /// relays by `key_id`, and returns the Token
+    /// carrying them.
+    pub fn sign(&self, key_id: u32, key: &SigningKey) -> Result<Token, SignError> {
+        let expires = self
+            .expires
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|expires| <x!(i)>::try_from(expires.as_secs()).ok())
+            .context(sign_ctx::ExpirySnafu)?;
+        let payload = Payload {
+            algorithm: (key.algorithm() as u8).into(),
+            key_id: key_id.into(),
+            namespace: self.namespace.clone(),
+            name: self.name.clone(),
+            actions: <x!(i)>::try_from(self.actions.0)
+                .ok()
+                .context(sign_ctx::ActionsSnafu)?,
+            expires,
+        };
+
+        let mut writer = ReferenceWriter::new();
+        payload
+            .encode(&mut writer, payload.len_bits().ok())
+            .context(sign_ctx::VarIntSnafu)?;
+        let payload = writer.finish().context(sign_ctx::WriterSnafu)?;
+        let signature = key.sign(&payload)?;
+
+        Ok(Token::builder()
+            .use_value()
+            .typ(TokenType::SignedClaims)
+            .value(bytes::Bytes::from([payload.as_ref(), &signature].concat()))
+            .build())
+    }
+
+    /// Verifies the `value` of a [SignedClaims](TokenType::SignedClaims)
+    /// Token with the keys of `keys`.
+    ///
+    /// Fails with the [Denial] to reject the
+    /// Request carrying it with.
+    pub fn verify(value: &[u8], keys: &KeySet) -> Result<Self, Denial> {
+        let mut reader = ReferenceReader::new(value);
+        let (algorithm, _) =
+            <x!(i)>::decode(&mut reader, None).map_err(|_| Denial::MalformedAuthToken)?;
+        let algorithm = Algorithm::try_from(algorithm.number::<u64>())?;
+        let signed = value
+            .len()
+            .checked_sub(algorithm.signature_len())
+            .ok_or(Denial::MalformedAuthToken)?;
+        let (payload, signature) = value.split_at(signed);
+
+        let mut reader = ReferenceReader::new(payload);
+        let bits = payload.len() * 8;
+        let payload_msg = match Payload::decode(&mut reader, Some(bits)) {
+            Ok((payload, len)) if len == bits => payload,
+            _ => return Err(Denial::MalformedAuthToken),
+        };
+        let key = u32::try_from(payload_msg.key_id.number::<u64>())
+            .ok()
+            .and_then(|key_id| keys.keys.get(&key_id))
+            .ok_or(Denial::Unauthorized)?;
+        if !key.verify(algorithm, payload, signature) {
+            return Err(Denial::Unauthorized);
+        }
+
+        let expires = UNIX_EPOCH + Duration::from_secs(payload_msg.expires.number());
+        if expires <= SystemTime::now() {
+            return Err(Denial::ExpiredAuthToken);
+        }
+
+        Ok(Self {
+            namespace: payload_msg.namespace,
+            name: payload_msg.name,
+            actions: Actions(payload_msg.actions.number()),
+            expires,
+        })
+    }
+
+    /// Whether these claims grant `request`.
+    ///
+    /// Requests on whole Namespaces are only
+    /// granted if all Track Names match.
+    pub fn grants(&self, request: &AuthRequest<'_>) -> bool {
+        if request.kind == RequestKind::Setup {
+            return true;
+        }
+
+        let namespace = request
+            .namespace
+            .is_some_and(|namespace| namespace.starts_with(&self.namespace));
+        let name = match request.name {
+            Some(name) => glob_matches(&self.name.bytes(), &name.bytes()),
+            None => self.name.bytes().as_ref() == b"*",
+        };
+        self.actions.allows(request.kind) && namespace && name
+    }
+}
+
+/// Whether `name` matches `glob`, in which `*`
+/// matches any run of bytes.
+///
+/// Only retries from the last `*`, which keeps
+/// it linear in the lengths of both.
+fn glob_matches(glob: &[u8], name: &[u8]) -> bool {
+    let (mut g, mut n) = (0, 0);
+    // the last `*` and where its run in `name` ends
+    let mut star = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some(b'*') => {
+                star = Some((g, n));
+                g += 1;
+            }
+            Some(byte) if *byte == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_g, star_n)) => {
+                    star = Some((star_g, star_n + 1));
+                    g = star_g + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|byte| *byte == b'*')
+}
+
+/// ## Key Set
+///
+/// The [VerifyingKey]s of the auth services by
+/// their Key ID, loaded from a file.
+///
+/// As [Authorizer] it grants Requests carrying a
+/// valid [SignedClaims] Token, sent with the
+/// Request or the ClientSetup, whose claims
+/// cover them.
+///
+/// Every line holds a Key ID, the algorithm
+/// (`hmac-sha256` or `ed25519`) and the secret or
+/// public key in hex:
+///
+/// ```text
+/// # id  algorithm    key
+/// 1     hmac-sha256  6d6f712d7365637265742d6b6579
+/// 2     ed25519      d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a
+/// ```
+#[derive(Clone, Default)]
+pub struct KeySet {
+    keys: HashMap<u32, VerifyingKey>,
+}
+
+impl KeySet {
+    /// Loads the KeySet from the file at `path`.
+    pub fn load<P>(path: P) -> Result<Self, KeySetError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let keys = std::fs::read_to_string(path).map_err(|err| KeySetError::Read {
+            path: path.to_owned(),
+            msg: err.to_string(),
+        })?;
+        keys.parse()
+    }
+
+    /// Adds `key` with `key_id`, returns the key
+    /// it replaced.
+    pub fn insert(&mut self, key_id: u32, key: VerifyingKey) -> Option<VerifyingKey> {
+        self.keys.insert(key_id, key)
+    }
+
+    /// The number of keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl fmt::Debug for KeySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeySet")
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Authorizer for KeySet {
+    /// Denies with the reason the first
+    /// [SignedClaims] Token was rejected for.
+    fn authorize(&self, request: &AuthRequest<'_>) -> Result<(), Denial> {
+        let mut denial = None;
+        let values = request
+            .tokens
+            .iter()
+            .filter(|token| token.token_type() == Some(TokenType::SignedClaims))
+            .filter_map(Token::value);
+
+        for value in values {
+            match SignedClaims::verify(&value.bytes(), self) {
+                Ok(claims) if claims.grants(request) => return Ok(()),
+                Ok(_) => denial.get_or_insert(Denial::Unauthorized),
+                Err(err) => denial.get_or_insert(err),
+            };
+        }
+        Err(denial.unwrap_or(Denial::Unauthorized))
+    }
+}
+
+impl FromStr for KeySet {
+    type Err = KeySetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys = Self::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.is_empty() {
+                continue;
+            }
+
+            let line = i + 1;
+            ensure!(
+                fields.len() == 3,
+                key_set_ctx::InvalidEntrySnafu {
+                    line,
+                    reason: "expected a key id, algorithm and key",
+                }
+            );
+            let key_id = fields[0]
+                .parse()
+                .ok()
+                .context(key_set_ctx::InvalidEntrySnafu {
+                    line,
+                    reason: "key id must be a 32 bit number",
+                })?;
+            let key = decode_hex(fields[2]).context(key_set_ctx::InvalidEntrySnafu {
+                line,
+                reason: "key must be hex encoded",
+            })?;
+            let key = match fields[1] {
+                "hmac-sha256" => VerifyingKey::HmacSha256(key),
+                "ed25519" => VerifyingKey::Ed25519(key),
+                _ => {
+                    return key_set_ctx::InvalidEntrySnafu {
+                        line,
+                        reason: "algorithm must be hmac-sha256 or ed25519",
+                    }
+                    .fail();
+                }
+            };
+
+            ensure!(
+                keys.insert(key_id, key).is_none(),
+                key_set_ctx::InvalidEntrySnafu {
+                    line,
+                    reason: "duplicate key id",
+                }
+            );
+        }
+
+        Ok(keys)
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HMAC_SECRET: &[u8] = b"moq-secret-key";
+    const ED25519_SEED: [u8; 32] = [7; 32];
+
+    fn claims(actions: Actions, expires_in: Duration) -> SignedClaims {
+        SignedClaims {
+            namespace: Namespace::from(["chat"]),
+            name: Name::from("room-*"),
+            actions,
+            expires: SystemTime::now() + expires_in,
+        }
+    }
+
+    fn request<'a>(kind: RequestKind, tokens: &'a [Token], name: &'a Name) -> AuthRequest<'a> {
+        AuthRequest {
+            kind,
+            tokens,
+            namespace: None,
+            name: Some(name),
+        }
+    }
+
+    #[test]
+    fn sign_verify_test() {
+        let hmac = SigningKey::HmacSha256(HMAC_SECRET.to_vec());
+        let ed25519 = SigningKey::Ed25519(ED25519_SEED);
+        let mut keys = KeySet::default();
+        keys.insert(1, hmac.verifying_key().unwrap());
+        keys.insert(2, ed25519.verifying_key().unwrap());
+
+        let signed = claims(Actions::SUBSCRIBE, Duration::from_secs(60));
+        for (key_id, key) in [(1, &hmac), (2, &ed25519)] {
+            let token = signed.sign(key_id, key).unwrap();
+            assert_eq!(token.token_type(), Some(TokenType::SignedClaims));
+
+            let value = token.value().unwrap().bytes();
+            let verified = SignedClaims::verify(&value, &keys).unwrap();
+            assert_eq!(verified.namespace, signed.namespace);
+            assert_eq!(verified.name, signed.name);
+            assert_eq!(verified.actions, signed.actions);
+
+            // tampered
+            let mut tampered = value.to_vec();
+            tampered[3] ^= 1;
+            assert!(SignedClaims::verify(&tampered, &keys).is_err());
+        }
+
+        // signed with another key than its key id
+        let value = signed.sign(2, &hmac).unwrap().value().unwrap().bytes();
+        assert_eq!(
+            SignedClaims::verify(&value, &keys),
+            Err(Denial::Unauthorized)
+        );
+        let value = signed.sign(3, &hmac).unwrap().value().unwrap().bytes();
+        assert_eq!(
+            SignedClaims::verify(&value, &keys),
+            Err(Denial::Unauthorized)
+        );
+        assert_eq!(
+            SignedClaims::verify(&[1, 2, 3], &keys),
+            Err(Denial::MalformedAuthToken)
+        );
+    }
+
+    #[test]
+    fn authorize_test() {
+        let key = SigningKey::HmacSha256(HMAC_SECRET.to_vec());
+        let keys: KeySet = format!("1 hmac-sha256 {}", hex(HMAC_SECRET))
+            .parse()
+            .unwrap();
+        let chat = Namespace::from(["chat", "eu"]);
+        let (room, lobby) = (Name::from("room-1"), Name::from("lobby"));
+
+        let subscriber = [claims(Actions::SUBSCRIBE, Duration::from_secs(60))
+            .sign(1, &key)
+            .unwrap()];
+        let mut subscribe = request(RequestKind::Subscribe, &subscriber, &room);
+        assert_eq!(keys.authorize(&subscribe), Err(Denial::Unauthorized));
+        subscribe.namespace = Some(&chat);
+        assert_eq!(keys.authorize(&subscribe), Ok(()));
+        let mut other = request(RequestKind::Fetch, &subscriber, &lobby);
+        other.namespace = Some(&chat);
+        assert_eq!(keys.authorize(&other), Err(Denial::Unauthorized));
+        let mut publish = request(RequestKind::Publish, &subscriber, &room);
+        publish.namespace = Some(&chat);
+        assert_eq!(keys.authorize(&publish), Err(Denial::Unauthorized));
+
+        let publisher = [
+            claims(Actions::PUBLISH | Actions::SUBSCRIBE, Duration::ZERO)
+                .sign(1, &key)
+                .unwrap(),
+        ];
+        let setup = AuthRequest {
+            kind: RequestKind::Setup,
+            tokens: &publisher,
+            namespace: None,
+            name: None,
+        };
+        assert_eq!(keys.authorize(&setup), Err(Denial::ExpiredAuthToken));
+        let setup = AuthRequest {
+            tokens: &subscriber,
+            ..setup
+        };
+        assert_eq!(keys.authorize(&setup), Ok(()));
+    }
+
+    #[test]
+    fn key_set_test() {
+        let keys = "# id algorithm key\n1 hmac-sha256 00ff\n2 ed25519 0102 # comment\n"
+            .parse::<KeySet>()
+            .unwrap();
+        assert_eq!(keys.len(), 2);
+
+        for (list, reason) in [
+            ("1 hmac-sha256", "expected a key id, algorithm and key"),
+            ("x hmac-sha256 00", "key id must be a 32 bit number"),
+            ("1 hmac-sha256 0g", "key must be hex encoded"),
+            ("1 rsa 00", "algorithm must be hmac-sha256 or ed25519"),
+        ] {
+            assert_eq!(
+                list.parse::<KeySet>().err(),
+                Some(KeySetError::InvalidEntry { line: 1, reason })
+            );
+        }
+        assert_eq!(
+            "1 ed25519 00\n1 hmac-sha256 00".parse::<KeySet>().err(),
+            Some(KeySetError::InvalidEntry {
+                line: 2,
+                reason: "duplicate key id",
+            })
+        );
+    }
+
+    #[test]
+    fn glob_test() {
+        assert!(glob_matches(b"*", b""));
+        assert!(glob_matches(b"room-*", b"room-1"));
+        assert!(glob_matches(b"*-eu-*", b"video-eu-720p"));
+        assert!(!glob_matches(b"room-*", b"lobby"));
+        assert!(!glob_matches(b"room", b"room-1"));
+        assert!(glob_matches(b"a*b*c", b"aXbYbZc"));
+        assert!(!glob_matches(b"a*b*c", b"aXbYbZ"));
+        assert!(glob_matches(b"**", b"any"));
+
+        // no exponential backtracking
+        let glob = [b"*a".repeat(20), b"*b".to_vec()].concat();
+        assert!(!glob_matches(&glob, &[b'a'; 64]));
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
@@ -0,0 +1,126 @@
+use {
+    crate::{
+        ControlStream, TlsVerification,
+        client::Connector,
+        error::ClientError,
+        transport::{Connection, memory},
+        types::Token,
+    },
+    bon::bon,
+    core::time::Duration,
+    url::Url,
+};
+
+/// ## Upstream
+///
+/// A relay or origin a [Server](crate::Server)
+/// subscribes to the Tracks no session publishes,
+/// connected to as a client.
+///
+/// Every Track is subscribed only once upstream,
+/// all local Subscribers share it.
+#[derive(Debug, Clone)]
+pub struct Upstream {
+    url: Url,
+    connector: Connector,
+}
+
+#[bon]
+impl Upstream {
+    /// Create a WebTransport [Upstream] using a Builder pattern
+    #[builder(start_fn = webtransport_builder, finish_fn = build)]
+    pub fn new_webtransport(
+        #[builder(into, setters(doc {
+            /// ## Connection URL
+            /// 
+            /// The WebTransport server to connect.
+        }))]
+        connect: Url,
+        #[builder(setters(doc {
+            /// ## Authorization Token
+            /// 
+            /// Sent with the setup to authorize the
+            /// session and all its Requests.
+        }))]
+        auth_token: Option<Token>,
+    ) -> Self {
+        Self {
+            url: connect,
+            connector: Connector::webtransport(auth_token),
+        }
+    }
+
+    /// Create a QUIC [Upstream] using a Builder pattern
+    #[builder(start_fn = quic_builder, finish_fn = build)]
+    pub fn new_quic(
+        #[builder(into, setters(doc {
+            /// ## Connection URL
+            /// 
+            /// The QUIC server to connect.
+        }))]
+        connect: Url,
+        #[builder(default, setters(doc {
+            /// ## Certificate Verification
+            /// 
+            /// How to verify the certificate of the server.
+        }))]
+        tls: TlsVerification,
+        #[builder(setters(doc {
+            /// ## Authorization Token
+            /// 
+            /// Sent with the setup to authorize the
+            /// session and all its Requests.
+        }))]
+        auth_token: Option<Token>,
+    ) -> Self {
+        Self {
+            url: connect,
+            connector: Connector::quic(tls, auth_token),
+        }
+    }
+
+    /// Create an in-memory [Upstream] using a Builder pattern
+    #[builder(start_fn = memory_builder, finish_fn = build)]
+    pub fn new_memory(
+        #[builder(setters(doc {
+            /// ## Memory Connector
+            /// 
+            /// Connects to an in-memory [Server](crate::Server).
+        }))]
+        connect: memory::Connector,
+        #[builder(default, setters(doc {
+            /// ## Link Conditions
+            /// 
+            /// The latency and loss of the connection.
+        }))]
+        conditions: memory::Conditions,
+        #[builder(setters(doc {
+            /// ## Authorization Token
+            /// 
+            /// Sent with the setup to authorize the
+            /// session and all its Requests.
+        }))]
+        auth_token: Option<Token>,
+    ) -> Self {
+        Self {
+            url: connect.url(),
+            connector: Connector::memory(connect, conditions, auth_token),
+        }
+    }
+}
+
+impl Upstream {
+    /// The URL of the upstream server.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Establishes a session to the upstream
+    /// server within `setup_timeout`.
+    pub(crate) async fn connect(
+        &self,
+        setup_timeout: Duration,
+    ) -> Result<(Connection, ControlStream), ClientError> {
+        self.connector.connect(&self.url, setup_timeout).await
+    }
+}
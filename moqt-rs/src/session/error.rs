@@ -1,13 +1,40 @@
 use snafu::Snafu;
 use tokio::sync::mpsc::error::{SendError, TryRecvError};
 
-use {super::SessionMessage, crate::error::ControlStreamError};
+use {
+    super::SessionMessage,
+    crate::{
+        error::ControlStreamError,
+        transport::error::DatagramError,
+        types::{error_code::Termination, message::GoawayError},
+    },
+};
 
 /// TODO docs
 #[derive(Debug, Snafu, Clone, PartialEq, Eq)]
 #[snafu(visibility(pub), module(ctx))]
 pub enum SessionError {
-    ControlStream { source: ControlStreamError },
+    ControlStream {
+        source: ControlStreamError,
+    },
+
+    #[snafu(display("failed to receive a datagram"))]
+    Datagram {
+        source: DatagramError,
+    },
+
+    #[snafu(display("failed to send a goaway"))]
+    Goaway {
+        source: GoawayError,
+    },
+
+    /// The peer violated the protocol and the
+    /// session is closed with `code`.
+    #[snafu(display("closing the session with {code:?}: {reason}"))]
+    Terminated {
+        code: Termination,
+        reason: String,
+    },
 
     SessionHandle,
 }
@@ -8,7 +8,7 @@ use {
     },
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SessionHandle {
     rx: Arc<RwLock<Receiver<SessionMessage>>>,
     tx: Arc<Sender<SessionMessage>>,
@@ -33,6 +33,14 @@ impl SessionHandle {
         }
     }
 
+    /// Waits for the next message.
+    ///
+    /// Yields None once the other side of the
+    /// handle is dropped.
+    pub async fn next_message(&self) -> Option<SessionMessage> {
+        self.rx.write().await.recv().await
+    }
+
     #[tracing::instrument(skip(self), err)]
     pub async fn send(&self, msg: SessionMessage) -> Result<(), SessionHandleError> {
         self.tx.send(msg).await.context(hs_ctx::ChannelSendSnafu)
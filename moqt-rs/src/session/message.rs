@@ -1,9 +1,15 @@
-use crate::types::{Name, Namespace};
-
-use super::SessionError;
+use {super::SessionError, crate::types::error_code::Termination, url::Url};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SessionMessage {
     Error(SessionError),
-    Subscribe { namespace: Namespace, name: Name },
+
+    /// Asks the session to send a
+    /// [Goaway](crate::types::message::Goaway)
+    /// with the migration URL, if any.
+    Goaway(Option<Url>),
+
+    /// Asks the session to close with the
+    /// [Termination] code.
+    Close(Termination),
 }
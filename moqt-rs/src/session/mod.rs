@@ -10,34 +10,104 @@ pub use {
 
 use {
     crate::{
-        ControlStream,
+        AuthRequest, ControlStream, RequestKind,
         error::ControlStreamError,
-        transport::{Connection, error::DecodeError},
-        types::message::ControlMessage,
+        relay::{self, Peer, Relay},
+        transport::{
+            Connection,
+            error::{DatagramError, DecodeError},
+        },
+        types::{
+            Name, Namespace, Token,
+            error_code::Termination,
+            message::{
+                ControlMessage, Fetch, FetchError, Goaway, PublishError, PublishNamespaceError,
+                SubscribeError, SubscribeNamespaceError,
+            },
+        },
     },
+    core::time::Duration,
     error::{ctx, hs_ctx},
-    snafu::ResultExt,
-    tokio::sync::mpsc,
-    tracing::error,
+    snafu::{ResultExt, ensure},
+    std::sync::{Arc, OnceLock},
+    tokio::{sync::mpsc, time::Instant},
+    tracing::{debug, warn},
+    url::Url,
+    varint::{VarIntNumber, x},
 };
 
+/// How long a peer may keep the session open
+/// after receiving a [Goaway].
+pub const GOAWAY_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct Session {
-    transport: Connection,
-    control_stream: ControlStream,
+    transport: Arc<Connection>,
+    control_stream: Arc<ControlStream>,
     handle: SessionHandle,
-    // rx: Arc<RwLock<Receiver<SessionMessage>>>,
-    // tx: Arc<Sender<SessionMessage>>,
+    relay: Arc<Relay>,
+    peer: Arc<Peer>,
+    /// The resolved Authorization Token the
+    /// client sent with its ClientSetup.
+    setup_token: OnceLock<Token>,
 }
 
 impl Session {
-    pub fn spawn(transport: Connection, control_stream: ControlStream) -> SessionHandle {
+    pub(crate) fn spawn(
+        transport: Connection,
+        control_stream: ControlStream,
+        relay: Arc<Relay>,
+    ) -> SessionHandle {
+        let transport = Arc::new(transport);
+        let control_stream = Arc::new(control_stream);
+        let peer = Arc::new(Peer::new(transport.clone(), control_stream.clone()));
+        Self::start(transport, control_stream, relay, peer)
+    }
+
+    /// Spawns the session the Relay established
+    /// to an [Upstream](crate::Upstream) server.
+    ///
+    /// Returns the server, the Relay sends its
+    /// Requests to it.
+    pub(crate) fn spawn_upstream(
+        transport: Connection,
+        control_stream: ControlStream,
+        relay: Arc<Relay>,
+    ) -> Arc<Peer> {
+        let transport = Arc::new(transport);
+        let control_stream = Arc::new(control_stream);
+        let peer = Arc::new(Peer::upstream_server(
+            transport.clone(),
+            control_stream.clone(),
+        ));
+        let handle = Self::start(transport, control_stream, relay, peer.clone());
+
+        tokio::spawn(async move {
+            while let Some(msg) = handle.next_message().await {
+                if let SessionMessage::Error(err) = msg {
+                    warn!(%err, "upstream session failed");
+                }
+            }
+        });
+        peer
+    }
+
+    fn start(
+        transport: Arc<Connection>,
+        control_stream: Arc<ControlStream>,
+        relay: Arc<Relay>,
+        peer: Arc<Peer>,
+    ) -> SessionHandle {
         let (session_tx, handler_rx) = mpsc::channel(10);
         let (handler_tx, session_rx) = mpsc::channel(10);
+        relay.join(peer.clone());
 
         let this = Self {
             transport,
             control_stream,
             handle: SessionHandle::new(session_tx, session_rx),
+            relay,
+            peer,
+            setup_token: OnceLock::new(),
         };
 
         tokio::spawn(async move { this.handle().await });
@@ -46,93 +116,507 @@ impl Session {
     }
 
     async fn handle(&self) {
-        // TODO handle loop
+        if let Err(err) = self.authorize_setup() {
+            if let SessionError::Terminated { code, .. } = &err {
+                self.transport.close(*code);
+            }
+            let _ = self.handle.send(SessionMessage::Error(err)).await;
+            self.relay.leave(&self.peer).await;
+            return;
+        }
+
+        // the peer has to close the session before
+        // this deadline once it received a Goaway
+        let mut goaway_deadline = None;
+        let mut server_open = true;
+
         loop {
-            // TODO tokio select for
-            //  [ ] recv ControlMessage -> then handle
-            //  [ ] recv SessionMessage from Relay
-            //  [ ] recv Data on Unidirectional Streams
-            // let t = self.transport.accept_uni().await
+            let deadline = goaway_deadline;
             tokio::select! {
                 msg = self.control_stream.recv() => {
                     if let Err(err) = self.handle_control_messages(msg).await {
-                        let _ = match err {
-                            SessionError::ControlStream { source: ControlStreamError::Recv { source: DecodeError::EndOfStream } } => continue,
-                            err => self.handle.send(SessionMessage::Error(err)).await,
-                        };
+                        if let SessionError::Terminated { code, .. } = &err {
+                            self.transport.close(*code);
+                        }
+                        match err {
+                            SessionError::ControlStream { source: ControlStreamError::Recv { source: DecodeError::EndOfStream } } => (),
+                            err => {
+                                let _ = self.handle.send(SessionMessage::Error(err)).await;
+                            }
+                        }
+                        break;
+                    }
+                },
+                uni = self.transport.accept_uni() => match uni {
+                    Ok(stream) => {
+                        tokio::spawn(relay::forward_stream(self.relay.clone(), self.peer.clone(), stream));
+                    }
+                    Err(err) => {
+                        debug!(%err, "connection closed");
+                        break;
+                    }
+                },
+                datagram = self.transport.recv_object_datagram() => match datagram {
+                    Ok(datagram) => relay::forward_datagram(&self.relay, &self.peer, datagram),
+                    Err(source @ DatagramError::Decode { .. }) => {
+                        let _ = self.handle.send(SessionMessage::Error(SessionError::Datagram { source })).await;
+                    }
+                    Err(err) => {
+                        debug!(%err, "connection closed");
+                        break;
+                    }
+                },
+                bidi = self.transport.accept_bi() => match bidi {
+                    // the control stream is the only bidirectional stream
+                    Ok(_) => {
+                        let code = Termination::ProtocolViolation;
+                        self.transport.close(code);
+                        let err = SessionError::Terminated { code, reason: "unexpected bidirectional stream".into() };
+                        let _ = self.handle.send(SessionMessage::Error(err)).await;
+                        break;
+                    }
+                    Err(err) => {
+                        debug!(%err, "connection closed");
+                        break;
+                    }
+                },
+                msg = self.handle.next_message(), if server_open => match msg {
+                    Some(SessionMessage::Goaway(_)) if goaway_deadline.is_some() => {
+                        debug!("goaway was already sent");
+                    }
+                    Some(SessionMessage::Goaway(url)) => {
+                        if let Err(err) = self.goaway(url).await {
+                            let _ = self.handle.send(SessionMessage::Error(err)).await;
+                            break;
+                        }
+                        goaway_deadline = Some(Instant::now() + GOAWAY_TIMEOUT);
+                    }
+                    Some(SessionMessage::Close(code)) => {
+                        debug!(?code, "closing session");
+                        self.transport.close(code);
+                        break;
                     }
+                    Some(msg) => debug!(?msg, "ignoring session message"),
+                    None => server_open = false,
+                },
+                _ = async move {
+                    match deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => core::future::pending().await,
+                    }
+                } => {
+                    let code = Termination::GoAwayTimeout;
+                    self.transport.close(code);
+                    let err = SessionError::Terminated { code, reason: "peer kept the session open after goaway".into() };
+                    let _ = self.handle.send(SessionMessage::Error(err)).await;
+                    break;
                 },
-                uni = self.transport.accept_uni() => println!("accepted uni stream"),
-                bidi = self.transport.accept_bi() => todo!("protocol violation: second bidi stream"),
             }
-            std::thread::sleep(std::time::Duration::from_secs(1));
         }
+
+        self.relay.leave(&self.peer).await;
     }
 
     async fn handle_control_messages(
         &self,
         control_message: Result<ControlMessage, ControlStreamError>,
     ) -> Result<(), SessionError> {
-        let control_message = control_message.context(ctx::ControlStreamSnafu)?;
+        let control_message = match control_message {
+            Err(err) if err.is_malformed() => {
+                return ctx::TerminatedSnafu {
+                    code: Termination::ProtocolViolation,
+                    reason: format!("malformed control message: {err}"),
+                }
+                .fail();
+            }
+            res => res.context(ctx::ControlStreamSnafu)?,
+        };
+
+        if control_message.is_response() {
+            let request_id = control_message
+                .request_id()
+                .map(|id| id.number::<u64>())
+                .unwrap_or_default();
+            ensure!(
+                self.peer.is_issued(request_id),
+                ctx::TerminatedSnafu {
+                    code: Termination::ProtocolViolation,
+                    reason: format!("response to unknown request {request_id}"),
+                }
+            );
+        }
+
+        let token = match control_message.auth_token() {
+            Some(token) => self.apply_token(token)?,
+            None => None,
+        };
+        let tokens = self.tokens(token);
 
         match control_message {
-            ControlMessage::ClientSetup(_client_setup) => todo!("error"),
-            ControlMessage::Fetch(fetch) => todo!("fetch"),
-            ControlMessage::FetchCancel(fetch_cancel) => todo!("fetch cancel"),
-            ControlMessage::FetchError(fetch_error) => todo!("fetch error"),
-            ControlMessage::FetchOk(fetch_ok) => todo!("fetch ok"),
-            ControlMessage::GoAway(goaway) => todo!("goaway"),
-            ControlMessage::MaxRequestId(max_request_id) => todo!("max request id"),
-            ControlMessage::Publish(publish) => todo!("publish"),
-            ControlMessage::PublishDone(publish_done) => todo!("publish done"),
-            ControlMessage::PublishError(publish_error) => todo!("publish error"),
-            ControlMessage::PublishNamespace(publish_namespace) => todo!("publish namespace"),
-            ControlMessage::PublishNamespaceCancel(publish_namespace_cancel) => {
-                todo!("publish namespace cancel")
+            ControlMessage::ClientSetup(_) | ControlMessage::ServerSetup(_) => {
+                return ctx::TerminatedSnafu {
+                    code: Termination::ProtocolViolation,
+                    reason: "unexpected setup message",
+                }
+                .fail();
             }
-            ControlMessage::PublishNamespaceDone(publish_namespace_done) => {
-                todo!("publish namespace done")
+            // new Requests go to the next session
+            ControlMessage::GoAway(goaway) if self.peer.is_upstream_server() => {
+                debug!(?goaway, "upstream sent goaway");
+                self.relay.upstream_goaway(&self.peer);
+            }
+            ControlMessage::GoAway(_) => {
+                return ctx::TerminatedSnafu {
+                    code: Termination::ProtocolViolation,
+                    reason: "goaway sent by a client",
+                }
+                .fail();
+            }
+            ControlMessage::MaxRequestId(max_request_id) => ensure!(
+                self.peer
+                    .raise_max_request_id(max_request_id.request_id.number()),
+                ctx::TerminatedSnafu {
+                    code: Termination::ProtocolViolation,
+                    reason: "max request id did not increase",
+                }
+            ),
+            ControlMessage::RequestsBlocked(requests_blocked) => {
+                debug!(max_id = ?requests_blocked.max_id, "peer is blocked on request ids");
+                if let Some(max) = self.peer.grant_request_ids() {
+                    self.respond(ControlMessage::MaxRequestId(max)).await?;
+                }
+            }
+            ControlMessage::Fetch(fetch) => {
+                self.accept_request(&fetch.request_id).await?;
+                let track = self.fetched_track(&fetch);
+                if let Some((namespace, name)) = &track {
+                    let request = AuthRequest {
+                        kind: RequestKind::Fetch,
+                        tokens: &tokens,
+                        namespace: Some(namespace),
+                        name: Some(name),
+                    };
+                    if let Err(denial) = self.relay.authorize(&request) {
+                        let msg = FetchError::new(fetch.request_id, denial, denial.to_string());
+                        return self.respond(ControlMessage::FetchError(msg)).await;
+                    }
+                }
+                self.relay
+                    .fetch(&self.peer, fetch)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+            }
+            ControlMessage::FetchCancel(fetch_cancel) => self
+                .relay
+                .fetch_cancel(&self.peer, fetch_cancel.request_id.number()),
+            ControlMessage::TrackStatus(track_status) => {
+                self.accept_request(&track_status.request_id).await?;
+                self.relay
+                    .track_status(&self.peer, track_status)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+            }
+            ControlMessage::TrackStatusOk(track_status_ok) => {
+                self.relay
+                    .track_status_ok(&self.peer, track_status_ok)
+                    .await
+            }
+            ControlMessage::TrackStatusError(track_status_error) => {
+                self.relay
+                    .track_status_error(&self.peer, track_status_error)
+                    .await
+            }
+            ControlMessage::SubscribeNamespace(subscribe_namespace) => {
+                self.accept_request(&subscribe_namespace.request_id).await?;
+                let request = AuthRequest {
+                    kind: RequestKind::SubscribeNamespace,
+                    tokens: &tokens,
+                    namespace: Some(&subscribe_namespace.namespace_prefix),
+                    name: None,
+                };
+                if let Err(denial) = self.relay.authorize(&request) {
+                    let msg = SubscribeNamespaceError::new(
+                        subscribe_namespace.request_id,
+                        denial,
+                        denial.to_string(),
+                    );
+                    return self
+                        .respond(ControlMessage::SubscribeNamespaceError(msg))
+                        .await;
+                }
+                self.relay
+                    .subscribe_namespace(&self.peer, subscribe_namespace)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
             }
-            ControlMessage::PublishNamespaceError(publish_namespace_error) => {
-                todo!("publish namespace error")
+            ControlMessage::UnsubscribeNamespace(unsubscribe_namespace) => self
+                .relay
+                .unsubscribe_namespace(&self.peer, &unsubscribe_namespace.namespace_prefix),
+            ControlMessage::Publish(publish) => {
+                self.accept_request(&publish.request_id).await?;
+                ensure!(
+                    !self
+                        .peer
+                        .published
+                        .contains_key(&publish.alias.number::<u64>()),
+                    ctx::TerminatedSnafu {
+                        code: Termination::DuplicateTrackAlias,
+                        reason: "track alias is already in use",
+                    }
+                );
+                let request = AuthRequest {
+                    kind: RequestKind::Publish,
+                    tokens: &tokens,
+                    namespace: Some(&publish.namespace),
+                    name: Some(&publish.name),
+                };
+                if let Err(denial) = self.relay.authorize(&request) {
+                    let msg = PublishError::new(publish.request_id, denial, denial.to_string());
+                    return self.respond(ControlMessage::PublishError(msg)).await;
+                }
+                self.relay
+                    .publish(&self.peer, publish)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+            }
+            ControlMessage::PublishDone(publish_done) => {
+                self.relay.publish_done(&self.peer, publish_done)
+            }
+            ControlMessage::PublishNamespace(publish_namespace) => {
+                self.accept_request(&publish_namespace.request_id).await?;
+                let request = AuthRequest {
+                    kind: RequestKind::PublishNamespace,
+                    tokens: &tokens,
+                    namespace: Some(&publish_namespace.namespace),
+                    name: None,
+                };
+                if let Err(denial) = self.relay.authorize(&request) {
+                    let msg = PublishNamespaceError::new(
+                        publish_namespace.request_id,
+                        denial,
+                        denial.to_string(),
+                    );
+                    return self
+                        .respond(ControlMessage::PublishNamespaceError(msg))
+                        .await;
+                }
+                self.relay
+                    .publish_namespace(&self.peer, publish_namespace)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+            }
+            ControlMessage::PublishNamespaceDone(publish_namespace_done) => {
+                self.relay
+                    .publish_namespace_done(&self.peer, &publish_namespace_done.namespace)
+                    .await
             }
             ControlMessage::PublishNamespaceOk(publish_namespace_ok) => {
-                todo!("publish namespace ok")
+                debug!(request_id = ?publish_namespace_ok.request_id, "peer accepted namespace");
+            }
+            ControlMessage::PublishNamespaceError(publish_namespace_error) => self
+                .relay
+                .publish_namespace_error(&self.peer, publish_namespace_error),
+            ControlMessage::PublishNamespaceCancel(publish_namespace_cancel) => {
+                let request_id = publish_namespace_cancel.request_id.number::<u64>();
+                ensure!(
+                    self.peer.is_issued(request_id),
+                    ctx::TerminatedSnafu {
+                        code: Termination::ProtocolViolation,
+                        reason: format!(
+                            "publish namespace cancel for unknown request {request_id}"
+                        ),
+                    }
+                );
+                debug!(request_id, "publish namespace cancel for unknown namespace");
             }
-            ControlMessage::PublishOk(publish_ok) => todo!("publish ok"),
-            ControlMessage::RequestsBlocked(requests_blocked) => todo!("requests blocked"),
-            ControlMessage::ServerSetup(_server_setup) => todo!("error"),
             ControlMessage::Subscribe(subscribe) => {
-                self.handle
-                    .send(SessionMessage::Subscribe {
-                        namespace: subscribe.namespace.clone(),
-                        name: subscribe.name.clone(),
-                    })
+                self.accept_request(&subscribe.request_id).await?;
+                let request = AuthRequest {
+                    kind: RequestKind::Subscribe,
+                    tokens: &tokens,
+                    namespace: Some(&subscribe.namespace),
+                    name: Some(&subscribe.name),
+                };
+                if let Err(denial) = self.relay.authorize(&request) {
+                    let msg = SubscribeError::new(subscribe.request_id, denial, denial.to_string());
+                    return self.respond(ControlMessage::SubscribeError(msg)).await;
+                }
+                self.relay
+                    .subscribe(&self.peer, subscribe)
+                    .await
+                    .context(ctx::ControlStreamSnafu)?;
+            }
+            ControlMessage::SubscribeOk(subscribe_ok) => {
+                let in_use = self
+                    .peer
+                    .published
+                    .get(&subscribe_ok.alias.number::<u64>())
+                    .is_some_and(|track| track.request_id() != &subscribe_ok.request_id);
+                ensure!(
+                    !in_use,
+                    ctx::TerminatedSnafu {
+                        code: Termination::DuplicateTrackAlias,
+                        reason: "track alias is already in use",
+                    }
+                );
+                self.relay.subscribe_ok(&self.peer, subscribe_ok).await;
+            }
+            ControlMessage::SubscribeError(subscribe_error) => {
+                self.relay
+                    .subscribe_error(&self.peer, subscribe_error)
+                    .await
+            }
+            ControlMessage::SubscribeUpdate(subscribe_update) => {
+                let request_id = subscribe_update.request_id.number::<u64>();
+                ensure!(
+                    self.peer.downstream.contains_key(&request_id),
+                    ctx::TerminatedSnafu {
+                        code: Termination::ProtocolViolation,
+                        reason: format!("subscribe update for unknown subscription {request_id}"),
+                    }
+                );
+                if let Err(reason) = self
+                    .relay
+                    .subscribe_update(&self.peer, subscribe_update)
                     .await
-                    .map_err(|err| {
-                        error!(%err, "failed to send message on session handle");
-                        SessionError::SessionHandle
-                    })?;
-                // TODO anything else that needs to be done?
-            }
-            ControlMessage::SubscribeError(subscribe_error) => todo!("subscribe error"),
-            ControlMessage::SubscribeNamespace(subscribe_namespace) => todo!("subscribe namespace"),
-            ControlMessage::SubscribeNamespaceError(subscribe_namespace_error) => {
-                todo!("subscribe namespace error")
-            }
-            ControlMessage::SubscribeNamespaceOk(subscribe_namespace_ok) => {
-                todo!("subscribe namespace ok")
-            }
-            ControlMessage::SubscribeOk(subscribe_ok) => todo!("subscribe ok"),
-            ControlMessage::SubscribeUpdate(subscribe_update) => todo!("subscribe update"),
-            ControlMessage::TrackStatus(track_status) => todo!("track status"),
-            ControlMessage::TrackStatusError(track_status_error) => todo!("track status error"),
-            ControlMessage::TrackStatusOk(track_status_ok) => todo!("track status ok"),
-            ControlMessage::Unsubscribe(unsubscribe) => todo!("unsubscribe"),
-            ControlMessage::UnsubscribeNamespace(unsubscribe_namespace) => {
-                todo!("unsubscribe namespace")
+                {
+                    return ctx::TerminatedSnafu {
+                        code: Termination::ProtocolViolation,
+                        reason,
+                    }
+                    .fail();
+                }
+            }
+            ControlMessage::Unsubscribe(unsubscribe) => {
+                self.relay
+                    .unsubscribe(&self.peer, unsubscribe.request_id.number())
+                    .await
+            }
+            ControlMessage::FetchOk(fetch_ok) => self.relay.fetch_ok(&self.peer, fetch_ok),
+            ControlMessage::FetchError(fetch_error) => {
+                self.relay.fetch_error(&self.peer, fetch_error)
+            }
+            // the Relay sends none of these Requests yet
+            msg @ (ControlMessage::PublishOk(_)
+            | ControlMessage::PublishError(_)
+            | ControlMessage::SubscribeNamespaceOk(_)
+            | ControlMessage::SubscribeNamespaceError(_)) => {
+                let request_id = msg.request_id().map(|id| id.number::<u64>());
+                ensure!(
+                    !request_id.is_some_and(|id| self.peer.upstream.contains_key(&id)),
+                    ctx::TerminatedSnafu {
+                        code: Termination::ProtocolViolation,
+                        reason: "response does not match its request",
+                    }
+                );
+                debug!(?request_id, "ignoring response to unknown request");
             }
         }
         Ok(())
     }
+
+    /// Resolves the Authorization Token of the
+    /// ClientSetup and authorizes the session.
+    ///
+    /// Sessions to Upstream servers are not
+    /// authorized, the Relay established them.
+    fn authorize_setup(&self) -> Result<(), SessionError> {
+        if self.peer.is_upstream_server() {
+            return Ok(());
+        }
+
+        if let Some(token) = self.control_stream.setup_token()
+            && let Some(token) = self.apply_token(token)?
+        {
+            let _ = self.setup_token.set(token);
+        }
+
+        let tokens = self.tokens(None);
+        let request = AuthRequest {
+            kind: RequestKind::Setup,
+            tokens: &tokens,
+            namespace: None,
+            name: None,
+        };
+        self.relay
+            .authorize(&request)
+            .map_err(|denial| SessionError::Terminated {
+                code: denial.into(),
+                reason: denial.to_string(),
+            })
+    }
+
+    /// The resolved `token` of a Request followed
+    /// by the one of the ClientSetup.
+    fn tokens(&self, token: Option<Token>) -> Vec<Token> {
+        token
+            .into_iter()
+            .chain(self.setup_token.get().cloned())
+            .collect()
+    }
+
+    /// The Namespace and Name of the Track `fetch`
+    /// requests, None for a Joining Fetch of an
+    /// unknown Subscription.
+    fn fetched_track(&self, fetch: &Fetch) -> Option<(Namespace, Name)> {
+        if let Some(standalone) = &fetch.standalone {
+            return Some((standalone.namespace.clone(), standalone.name.clone()));
+        }
+
+        let joining = fetch.joining.as_ref()?;
+        let track = self
+            .peer
+            .downstream
+            .get(&joining.request_id.number::<u64>())?;
+        let key = track.value().key();
+        Some((key.namespace.clone(), key.name.clone()))
+    }
+
+    /// Applies the Authorization `token` the peer
+    /// sent to the Tokens it registered.
+    fn apply_token(&self, token: &Token) -> Result<Option<Token>, SessionError> {
+        self.peer
+            .apply_token(token)
+            .map_err(|code| SessionError::Terminated {
+                code,
+                reason: code.to_string(),
+            })
+    }
+
+    /// Accepts the Request ID of a new Request and
+    /// grants the client new ones when due.
+    ///
+    /// Request IDs sent by the client must be
+    /// consecutive even numbers starting at 0 and
+    /// below the maximum granted to it.
+    async fn accept_request(&self, request_id: &x!(i)) -> Result<(), SessionError> {
+        let request_id = request_id.number::<u64>();
+        let grant =
+            self.peer
+                .accept_request_id(request_id)
+                .map_err(|code| SessionError::Terminated {
+                    code,
+                    reason: format!("unexpected request id {request_id}"),
+                })?;
+
+        if let Some(max) = grant {
+            self.respond(ControlMessage::MaxRequestId(max)).await?;
+        }
+        Ok(())
+    }
+
+    /// Asks the peer to migrate to `url` or to
+    /// reconnect to the current URL without one.
+    async fn goaway(&self, url: Option<Url>) -> Result<(), SessionError> {
+        let msg = Goaway::with_url(url.as_ref()).context(ctx::GoawaySnafu)?;
+        debug!(?url, "sending goaway");
+        self.respond(ControlMessage::GoAway(msg)).await
+    }
+
+    async fn respond(&self, msg: ControlMessage) -> Result<(), SessionError> {
+        self.control_stream
+            .send(msg)
+            .await
+            .context(ctx::ControlStreamSnafu)
+    }
 }
@@ -0,0 +1,31 @@
+use snafu::Snafu;
+
+use crate::transport::error::{ConnectionError, DecodeError, EncodeError, SendError};
+
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[snafu(visibility(pub), module(ctx))]
+pub enum SubgroupStreamError {
+    #[snafu(display("failed to open the subgroup stream"))]
+    Connection { source: ConnectionError },
+
+    #[snafu(display("failed to send on the subgroup stream"))]
+    Send { source: EncodeError },
+
+    #[snafu(display("failed to finish the subgroup stream"))]
+    Finish { source: SendError },
+
+    #[snafu(display("failed to receive on the subgroup stream"))]
+    Recv { source: DecodeError },
+
+    #[snafu(display("subgroup stream ended before the SUBGROUP_HEADER"))]
+    MissingHeader,
+
+    #[snafu(display("object ID exceeds the maximum value"))]
+    ObjectIdOverflow,
+
+    #[snafu(display("object {object} does not follow the previous object {previous}"))]
+    ObjectIdNotIncreasing { previous: u64, object: u64 },
+
+    #[snafu(display("the SUBGROUP_HEADER does not allow extension headers"))]
+    ExtensionsDisabled,
+}
@@ -0,0 +1,9 @@
+//! Sending and receiving the Objects of
+//! a single Subgroup on a unidirectional
+//! stream.
+
+mod error;
+mod reader;
+mod writer;
+
+pub use {error::SubgroupStreamError, reader::SubgroupReader, writer::SubgroupWriter};
@@ -0,0 +1,113 @@
+use {
+    super::{SubgroupStreamError, error::ctx},
+    crate::{
+        transport::RecvStream,
+        types::data::{Object, SubgroupHeader, SubgroupObject, SubgroupObjectExt},
+    },
+    bytes::BytesMut,
+    snafu::{OptionExt, ResultExt},
+    varint::{VarIntNumber, x},
+};
+
+/// ## Subgroup Reader
+///
+/// Receives the Objects of a Subgroup stream
+/// starting with a [SubgroupHeader].
+pub struct SubgroupReader {
+    header: SubgroupHeader,
+    stream: RecvStream,
+    buf: BytesMut,
+    subgroup_id: Option<x!(i)>,
+    previous: Option<u64>,
+}
+
+impl SubgroupReader {
+    /// Reads the [SubgroupHeader] from `stream`.
+    ///
+    /// `buf` holds any data already read from
+    /// `stream`.
+    pub(crate) async fn new(
+        mut stream: RecvStream,
+        mut buf: BytesMut,
+    ) -> Result<Self, SubgroupStreamError> {
+        let header = stream
+            .recv_buffered::<SubgroupHeader>(&mut buf)
+            .await
+            .context(ctx::RecvSnafu)?
+            .context(ctx::MissingHeaderSnafu)?;
+
+        Ok(Self {
+            header,
+            stream,
+            buf,
+            subgroup_id: None,
+            previous: None,
+        })
+    }
+
+    /// The [SubgroupHeader] of this stream.
+    pub fn header(&self) -> &SubgroupHeader {
+        &self.header
+    }
+
+    /// Receives the next Object.
+    ///
+    /// Yields None once the stream is finished.
+    pub async fn next(&mut self) -> Result<Option<Object>, SubgroupStreamError> {
+        let object = if self.header.has_extensions() {
+            self.stream
+                .recv_buffered::<SubgroupObjectExt>(&mut self.buf)
+                .await
+        } else {
+            self.stream
+                .recv_buffered::<SubgroupObject>(&mut self.buf)
+                .await
+                .map(|obj| obj.map(SubgroupObjectExt::from))
+        };
+        let Some(object) = object.context(ctx::RecvSnafu)? else {
+            return Ok(None);
+        };
+
+        let object_id = next_object_id(self.previous, object.id_delta.number())
+            .context(ctx::ObjectIdOverflowSnafu)?;
+        self.previous = Some(object_id);
+        let object_id = <x!(i)>::try_from(object_id)
+            .ok()
+            .context(ctx::ObjectIdOverflowSnafu)?;
+
+        let subgroup_id = self
+            .subgroup_id
+            .get_or_insert_with(|| self.header.resolve_subgroup_id(object_id.clone()))
+            .clone();
+
+        Ok(Some(Object::from_subgroup(
+            &self.header,
+            subgroup_id,
+            object_id,
+            object,
+        )))
+    }
+}
+
+/// Resolves the Object ID from the ID of the
+/// `previous` Object and the `delta` of the
+/// following Object.
+pub(super) fn next_object_id(previous: Option<u64>, delta: u64) -> Option<u64> {
+    match previous {
+        Some(previous) => previous.checked_add(delta)?.checked_add(1),
+        None => Some(delta),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_id_test() {
+        assert_eq!(next_object_id(None, 4), Some(4));
+        assert_eq!(next_object_id(Some(4), 0), Some(5));
+        assert_eq!(next_object_id(Some(5), 2), Some(8));
+        assert_eq!(next_object_id(Some(u64::MAX), 0), None);
+    }
+}
@@ -0,0 +1,154 @@
+use {
+    super::{SubgroupStreamError, error::ctx},
+    crate::{
+        transport::{Connection, SendStream},
+        types::data::{
+            Extensions, ObjectPayload, SubgroupHeader, SubgroupObject, SubgroupObjectExt,
+        },
+    },
+    snafu::{OptionExt, ResultExt, ensure},
+    varint::{VarIntNumber, x},
+};
+
+/// ## Subgroup Writer
+///
+/// Sends the Objects of a single Subgroup on
+/// a unidirectional stream starting with a
+/// [SubgroupHeader].
+pub struct SubgroupWriter {
+    header: SubgroupHeader,
+    stream: SendStream,
+    previous: Option<u64>,
+}
+
+impl SubgroupWriter {
+    /// Opens a new stream and sends `header`.
+    #[tracing::instrument(skip(conn), err)]
+    pub(crate) async fn open(
+        conn: &Connection,
+        header: SubgroupHeader,
+    ) -> Result<Self, SubgroupStreamError> {
+        let mut stream = conn.open_uni().await.context(ctx::ConnectionSnafu)?;
+
+        stream.send(header.clone()).await.context(ctx::SendSnafu)?;
+
+        Ok(Self {
+            header,
+            stream,
+            previous: None,
+        })
+    }
+
+    /// The [SubgroupHeader] of this stream.
+    pub fn header(&self) -> &SubgroupHeader {
+        &self.header
+    }
+
+    /// The ID of the last Object sent, if any.
+    pub fn previous_object(&self) -> Option<u64> {
+        self.previous
+    }
+
+    /// Sends the Object `object_id` with `payload`.
+    ///
+    /// Object IDs must be strictly increasing.
+    pub async fn write<I, P>(&mut self, object_id: I, payload: P) -> Result<(), SubgroupStreamError>
+    where
+        I: Into<x!(i)>,
+        P: Into<ObjectPayload>,
+    {
+        self.send(object_id.into(), Extensions::default(), payload.into())
+            .await
+    }
+
+    /// Sends the Object `object_id` with `payload`
+    /// and Extension Headers.
+    ///
+    /// Requires a [SubgroupHeader] whose type
+    /// [has extensions](SubgroupHeader::has_extensions).
+    pub async fn write_with_extensions<I, P>(
+        &mut self,
+        object_id: I,
+        extensions: Extensions,
+        payload: P,
+    ) -> Result<(), SubgroupStreamError>
+    where
+        I: Into<x!(i)>,
+        P: Into<ObjectPayload>,
+    {
+        ensure!(self.header.has_extensions(), ctx::ExtensionsDisabledSnafu);
+
+        self.send(object_id.into(), extensions, payload.into())
+            .await
+    }
+
+    /// Finishes the stream, signaling that all
+    /// Objects of this Subgroup have been sent.
+    pub async fn finish(mut self) -> Result<(), SubgroupStreamError> {
+        self.stream.finish().await.context(ctx::FinishSnafu)
+    }
+
+    async fn send(
+        &mut self,
+        object_id: x!(i),
+        extensions: Extensions,
+        payload: ObjectPayload,
+    ) -> Result<(), SubgroupStreamError> {
+        let object = object_id.number::<u64>();
+        let delta =
+            object_id_delta(self.previous, object).context(ctx::ObjectIdNotIncreasingSnafu {
+                previous: self.previous.unwrap_or_default(),
+                object,
+            })?;
+        let id_delta = <x!(i)>::try_from(delta)
+            .ok()
+            .context(ctx::ObjectIdOverflowSnafu)?;
+
+        if self.header.has_extensions() {
+            self.stream
+                .send(SubgroupObjectExt {
+                    id_delta,
+                    extensions,
+                    payload,
+                })
+                .await
+        } else {
+            self.stream.send(SubgroupObject { id_delta, payload }).await
+        }
+        .context(ctx::SendSnafu)?;
+
+        self.previous = Some(object);
+        Ok(())
+    }
+}
+
+/// Returns the delta to encode for `object`
+/// following the `previous` Object.
+///
+/// The inverse of
+/// [next_object_id](super::reader::next_object_id).
+pub(super) fn object_id_delta(previous: Option<u64>, object: u64) -> Option<u64> {
+    match previous {
+        Some(previous) => object.checked_sub(previous.checked_add(1)?),
+        None => Some(object),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::reader::next_object_id, *};
+
+    #[test]
+    fn object_id_delta_test() {
+        assert_eq!(object_id_delta(None, 4), Some(4));
+        assert_eq!(object_id_delta(Some(4), 5), Some(0));
+        assert_eq!(object_id_delta(Some(5), 8), Some(2));
+        assert_eq!(object_id_delta(Some(5), 5), None);
+        assert_eq!(object_id_delta(Some(u64::MAX), 0), None);
+
+        for (previous, object) in [(None, 0), (Some(0), 1), (Some(3), 10)] {
+            let delta = object_id_delta(previous, object).unwrap();
+            assert_eq!(next_object_id(previous, delta), Some(object));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use {bon::Builder, core::time::Duration};
+
+/// ## Timeouts
+///
+/// How long a peer may take before the session
+/// is closed with
+/// [ControlMessageTimeout](crate::types::error_code::Termination::ControlMessageTimeout)
+/// or [DataStreamTimeout](crate::types::error_code::Termination::DataStreamTimeout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Builder)]
+pub struct Timeouts {
+    /// Time to complete the setup of a session
+    #[builder(default = Timeouts::SETUP, setters(doc {
+        /// ## Setup Timeout
+        ///
+        /// Time to complete the setup of a session
+    }))]
+    pub setup: Duration,
+
+    /// Time to respond to a Request
+    #[builder(default = Timeouts::REQUEST, setters(doc {
+        /// ## Request Timeout
+        ///
+        /// Time to respond to a Request
+    }))]
+    pub request: Duration,
+
+    /// Time to send the header of a data stream,
+    /// or to open the stream of an accepted Fetch
+    #[builder(default = Timeouts::DATA_STREAM, setters(doc {
+        /// ## Data Stream Timeout
+        ///
+        /// Time to send the header of a data stream,
+        /// or to open the stream of an accepted Fetch
+    }))]
+    pub data_stream: Duration,
+
+    /// Time after which Subscriptions accepted by a
+    /// Relay expire unless refreshed, zero never
+    #[builder(default = Timeouts::SUBSCRIPTION, setters(doc {
+        /// ## Subscription Expiry
+        ///
+        /// Time after which Subscriptions accepted by a
+        /// Relay expire unless refreshed, zero never
+    }))]
+    pub subscription: Duration,
+
+    /// Time before a Subscription expires to
+    /// refresh it with a SubscribeUpdate, zero
+    /// never
+    #[builder(default = Timeouts::REFRESH, setters(doc {
+        /// ## Refresh Margin
+        ///
+        /// Time before a Subscription expires to
+        /// refresh it with a SubscribeUpdate, zero
+        /// never
+    }))]
+    pub refresh: Duration,
+}
+
+impl Timeouts {
+    pub const SETUP: Duration = Duration::from_secs(10);
+    pub const REQUEST: Duration = Duration::from_secs(10);
+    pub const DATA_STREAM: Duration = Duration::from_secs(10);
+    pub const SUBSCRIPTION: Duration = Duration::ZERO;
+    pub const REFRESH: Duration = Duration::from_secs(1);
+
+    /// The Timeouts of the given milliseconds,
+    /// the defaults where None.
+    pub fn from_millis(setup: Option<u64>, request: Option<u64>, data_stream: Option<u64>) -> Self {
+        Self {
+            setup: setup.map_or(Self::SETUP, Duration::from_millis),
+            request: request.map_or(Self::REQUEST, Duration::from_millis),
+            data_stream: data_stream.map_or(Self::DATA_STREAM, Duration::from_millis),
+            subscription: Self::SUBSCRIPTION,
+            refresh: Self::REFRESH,
+        }
+    }
+
+    /// How long after it was accepted or last
+    /// refreshed a Subscription expiring after
+    /// `expires` is refreshed.
+    ///
+    /// Half of `expires` when it is shorter than
+    /// twice the refresh margin, None if it never
+    /// expires or is never refreshed.
+    pub fn refresh_after(&self, expires: Duration) -> Option<Duration> {
+        if expires.is_zero() || self.refresh.is_zero() {
+            return None;
+        }
+        Some(expires.saturating_sub(self.refresh).max(expires / 2))
+    }
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_millis_test() {
+        assert_eq!(Timeouts::from_millis(None, None, None), Timeouts::default());
+
+        let timeouts = Timeouts::from_millis(Some(1), None, Some(3));
+        assert_eq!(timeouts.setup, Duration::from_millis(1));
+        assert_eq!(timeouts.request, Timeouts::REQUEST);
+        assert_eq!(timeouts.data_stream, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn refresh_after_test() {
+        let timeouts = Timeouts::builder().refresh(Duration::from_secs(1)).build();
+        assert_eq!(
+            timeouts.refresh_after(Duration::from_secs(10)),
+            Some(Duration::from_secs(9))
+        );
+        assert_eq!(
+            timeouts.refresh_after(Duration::from_secs(1)),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(timeouts.refresh_after(Duration::ZERO), None);
+
+        let never = Timeouts::builder().refresh(Duration::ZERO).build();
+        assert_eq!(never.refresh_after(Duration::from_secs(10)), None);
+    }
+}
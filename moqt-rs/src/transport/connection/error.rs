@@ -27,6 +27,18 @@ pub enum ConnectionError {
     #[snafu(display("QUIC connection failed"))]
     QuicConnection { source: quic::ConnectionError },
 
+    #[snafu(display("QUIC failed to send a datagram"))]
+    QuicSendDatagram { source: quic::SendDatagramError },
+
+    #[snafu(display("WebTransport failed to send a datagram: {msg}"))]
+    WebTransportSendDatagram { msg: String },
+
+    #[snafu(display("memory connection closed with {code:#x}: {reason}"))]
+    MemoryClosed { code: u64, reason: String },
+
+    #[snafu(display("no memory endpoint is listening at {addr}"))]
+    MemoryRefused { addr: core::net::SocketAddr },
+
     #[snafu(display("{url} is an invalid URL"))]
     InvalidUrl {
         url: String,
@@ -40,6 +52,26 @@ pub enum ConnectionError {
 impl_from_msg_error! {
     ConnectionError = [
         IoError => std::io::Error,
-        WebTransportConnecting => webtransport::error::ConnectingError
+        WebTransportConnecting => webtransport::error::ConnectingError,
+        WebTransportSendDatagram => webtransport::error::SendDatagramError,
     ]
 }
+
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[snafu(visibility(pub), module(dg_ctx))]
+pub enum DatagramError {
+    #[snafu(display("failed to encode datagram: {msg}"))]
+    Encode { msg: String },
+
+    #[snafu(display("failed to decode datagram: {cause}"))]
+    Decode { cause: String },
+
+    #[snafu(display("datagram of {size} bytes exceeds the maximum of {max} bytes"))]
+    TooLarge { size: usize, max: usize },
+
+    #[snafu(display("peer does not support datagrams"))]
+    Unsupported,
+
+    #[snafu(display("failed to transmit datagram"))]
+    Connection { source: ConnectionError },
+}
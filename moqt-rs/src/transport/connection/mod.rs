@@ -1,15 +1,20 @@
 mod builder;
 mod error;
 
-pub use error::ConnectionError;
+pub use error::{ConnectionError, DatagramError};
 
 use {
-    super::{RecvStream, SendStream},
-    crate::types::error_code::Termination,
+    super::{RecvStream, SendStream, memory},
+    crate::types::{data::ObjectDatagram, error_code::Termination},
+    bytes::Bytes,
     core::net::SocketAddr,
-    error::ctx,
-    snafu::ResultExt,
+    error::{ctx, dg_ctx},
+    snafu::{OptionExt, ResultExt},
     tracing::trace,
+    varint::{
+        VarInt, Writer,
+        core::{ReferenceReader, ReferenceWriter},
+    },
 };
 
 /// Wrapper Type around [QUIC](quic::Connection) and
@@ -18,6 +23,7 @@ use {
 pub enum Connection {
     Quic(quic::Connection),
     WebTransport(webtransport::Connection),
+    Memory(Box<memory::Connection>),
 }
 
 impl Connection {
@@ -51,6 +57,13 @@ impl Connection {
 
                 SendStream::WebTransport(tx)
             }
+            Self::Memory(conn) => {
+                let tx = conn.open_uni().await?;
+
+                trace!("opened unidirectional stream");
+
+                SendStream::Memory(tx)
+            }
         })
     }
 
@@ -75,6 +88,13 @@ impl Connection {
 
                 RecvStream::WebTransport(rx)
             }
+            Self::Memory(conn) => {
+                let rx = conn.accept_uni().await?;
+
+                trace!("accepted unidirectional stream");
+
+                RecvStream::Memory(rx)
+            }
         })
     }
 
@@ -101,6 +121,13 @@ impl Connection {
 
                 (SendStream::WebTransport(tx), RecvStream::WebTransport(rx))
             }
+            Self::Memory(conn) => {
+                let (tx, rx) = conn.open_bi().await?;
+
+                trace!("opened bidirectional stream");
+
+                (SendStream::Memory(tx), RecvStream::Memory(rx))
+            }
         })
     }
 
@@ -125,13 +152,114 @@ impl Connection {
 
                 (SendStream::WebTransport(tx), RecvStream::WebTransport(rx))
             }
+            Self::Memory(conn) => {
+                let (tx, rx) = conn.accept_bi().await?;
+
+                trace!("accepted bidirectional stream");
+
+                (SendStream::Memory(tx), RecvStream::Memory(rx))
+            }
         })
     }
 
+    /// Sends an unreliable datagram.
+    ///
+    /// The datagram may be lost, reordered or
+    /// dropped when it exceeds [max_datagram_size](Self::max_datagram_size).
+    #[tracing::instrument(skip(self, buf), err)]
+    pub fn send_datagram(&self, buf: Bytes) -> Result<(), ConnectionError> {
+        match self {
+            Self::Quic(conn) => conn.send_datagram(buf).context(ctx::QuicSendDatagramSnafu),
+            Self::WebTransport(conn) => Ok(conn.send_datagram(buf)?),
+            Self::Memory(conn) => conn.send_datagram(buf),
+        }
+    }
+
+    /// Receives the next datagram.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn recv_datagram(&self) -> Result<Bytes, ConnectionError> {
+        Ok(match self {
+            Self::Quic(conn) => conn
+                .read_datagram()
+                .await
+                .context(ctx::QuicConnectionSnafu)?,
+            Self::WebTransport(conn) => conn
+                .receive_datagram()
+                .await
+                .context(ctx::WebTransportConnectionSnafu)?
+                .payload(),
+            Self::Memory(conn) => conn.recv_datagram().await?,
+        })
+    }
+
+    /// Returns the maximum size of a datagram in
+    /// bytes, if datagrams are supported by the peer.
+    pub fn max_datagram_size(&self) -> Option<usize> {
+        match self {
+            Self::Quic(conn) => conn.max_datagram_size(),
+            Self::WebTransport(conn) => conn.max_datagram_size(),
+            Self::Memory(conn) => conn.max_datagram_size(),
+        }
+    }
+
+    /// Encodes an [ObjectDatagram] and sends it as
+    /// datagram.
+    ///
+    /// Fails instead of sending it if the encoded
+    /// Object exceeds [max_datagram_size](Self::max_datagram_size).
+    #[tracing::instrument(skip(self, datagram), err)]
+    pub fn send_object_datagram(&self, datagram: &ObjectDatagram) -> Result<(), DatagramError> {
+        let mut writer = ReferenceWriter::new();
+
+        let length = datagram.len_bits().map_err(|err| DatagramError::Encode {
+            msg: err.to_string(),
+        })?;
+        datagram
+            .encode(&mut writer, Some(length))
+            .map_err(|err| DatagramError::Encode {
+                msg: err.to_string(),
+            })?;
+        let buf = writer.finish().map_err(|err| DatagramError::Encode {
+            msg: err.to_string(),
+        })?;
+
+        let max = self.max_datagram_size().context(dg_ctx::UnsupportedSnafu)?;
+        if buf.len() > max {
+            return dg_ctx::TooLargeSnafu {
+                size: buf.len(),
+                max,
+            }
+            .fail();
+        }
+
+        self.send_datagram(buf).context(dg_ctx::ConnectionSnafu)
+    }
+
+    /// Receives the next datagram and decodes it as
+    /// [ObjectDatagram].
+    #[tracing::instrument(skip(self), err)]
+    pub async fn recv_object_datagram(&self) -> Result<ObjectDatagram, DatagramError> {
+        let buf = self
+            .recv_datagram()
+            .await
+            .context(dg_ctx::ConnectionSnafu)?;
+
+        let mut reader = ReferenceReader::new(&buf);
+        let (datagram, _bits) =
+            ObjectDatagram::decode(&mut reader, Some(buf.len() * 8)).map_err(|err| {
+                DatagramError::Decode {
+                    cause: err.to_string(),
+                }
+            })?;
+
+        Ok(datagram)
+    }
+
     pub fn remote_addr(&self) -> SocketAddr {
         match self {
             Self::Quic(conn) => conn.remote_address(),
             Self::WebTransport(conn) => conn.remote_address(),
+            Self::Memory(conn) => conn.remote_addr(),
         }
     }
 
@@ -139,6 +267,7 @@ impl Connection {
         match self {
             Self::Quic(conn) => conn.close(code.key().into(), code.to_string().as_bytes()),
             Self::WebTransport(conn) => conn.close(code.key().into(), code.to_string().as_bytes()),
+            Self::Memory(conn) => conn.close(code.key().into(), &code.to_string()),
         }
     }
 }
@@ -1,5 +1,6 @@
 use {
     super::{Endpoint, EndpointError},
+    crate::transport::memory,
     bon::bon,
     core::net::SocketAddr,
 };
@@ -37,4 +38,18 @@ impl Endpoint {
     ) -> Result<Self, EndpointError> {
         Ok(Self::Quic(quic::Endpoint::server(config, bind)?))
     }
+
+    /// Creates an in-memory Endpoint
+    #[builder(start_fn = memory_builder, finish_fn = build)]
+    pub fn new_memory(
+        #[builder(setters(doc {
+            /// ## Endpoint Address
+            /// 
+            /// The address connections to this
+            /// Endpoint are made to.
+        }))]
+        bind: SocketAddr,
+    ) -> Self {
+        Self::Memory(memory::Endpoint::new(bind))
+    }
 }
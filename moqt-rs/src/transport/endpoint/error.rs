@@ -17,6 +17,9 @@ pub enum EndpointError {
     WebTransportConnection {
         source: webtransport::error::ConnectionError,
     },
+
+    #[snafu(display("memory endpoint closed"))]
+    MemoryClosed,
 }
 
 impl_from_msg_error! {
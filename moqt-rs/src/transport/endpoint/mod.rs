@@ -5,7 +5,7 @@ pub use error::EndpointError;
 use snafu::ResultExt;
 use tracing::{debug, info, trace};
 
-use crate::transport::{QUIC, WEBTRANSPORT};
+use crate::transport::{MEMORY, QUIC, WEBTRANSPORT, memory};
 
 use {
     crate::transport::Connection, core::net::SocketAddr, error::ctx, std::fmt::Debug,
@@ -16,6 +16,7 @@ use {
 pub enum Endpoint {
     Quic(quic::Endpoint),
     WebTransport(webtransport::Endpoint<Server>),
+    Memory(memory::Endpoint),
 }
 
 impl Endpoint {
@@ -24,6 +25,7 @@ impl Endpoint {
         match self {
             Self::Quic(_) => QUIC,
             Self::WebTransport(_) => WEBTRANSPORT,
+            Self::Memory(_) => MEMORY,
         }
     }
 
@@ -32,6 +34,7 @@ impl Endpoint {
         Ok(match self {
             Self::Quic(ep) => ep.local_addr()?,
             Self::WebTransport(ep) => ep.local_addr()?,
+            Self::Memory(ep) => ep.local_addr(),
         })
     }
 
@@ -72,6 +75,15 @@ impl Endpoint {
 
                 Ok(Connection::WebTransport(connection))
             }
+            Self::Memory(ep) => {
+                let connection = ep.accept().await?;
+
+                tracing::Span::current()
+                    .record("remote_addr", connection.remote_addr().to_string());
+                debug!("new session accepted");
+
+                Ok(Connection::Memory(Box::new(connection)))
+            }
         }
     }
 }
@@ -84,6 +96,7 @@ impl Debug for Endpoint {
                 .debug_tuple("WebTransport")
                 .field(&"/* inaccessible */")
                 .finish(),
+            Self::Memory(ep) => f.debug_tuple("Memory").field(&ep.local_addr()).finish(),
         }
     }
 }
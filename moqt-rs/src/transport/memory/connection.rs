@@ -0,0 +1,224 @@
+use {
+    super::{Close, Conditions, Loss, RecvStream, SendStream, stream},
+    crate::transport::{PACKET_SIZE, error::ConnectionError},
+    bytes::Bytes,
+    core::{net::SocketAddr, time::Duration},
+    std::sync::Arc,
+    tokio::{
+        sync::{Mutex, mpsc, watch},
+        time::Instant,
+    },
+};
+
+/// A datagram and when it arrives.
+type Datagram = (Instant, Bytes);
+
+/// ## Memory Connection
+///
+/// One end of a connection whose streams and
+/// datagrams are channels to the other end.
+pub struct Connection {
+    remote: SocketAddr,
+    latency: Duration,
+    loss: std::sync::Mutex<Loss>,
+    uni_tx: mpsc::UnboundedSender<RecvStream>,
+    uni_rx: Mutex<mpsc::UnboundedReceiver<RecvStream>>,
+    bi_tx: mpsc::UnboundedSender<(SendStream, RecvStream)>,
+    bi_rx: Mutex<mpsc::UnboundedReceiver<(SendStream, RecvStream)>>,
+    datagram_tx: mpsc::UnboundedSender<Datagram>,
+    datagram_rx: Mutex<DatagramQueue>,
+    /// Shared by both ends, set once either
+    /// closes the connection.
+    closed: Arc<watch::Sender<Option<Close>>>,
+}
+
+/// Received datagrams, the first one may still
+/// be on its way.
+struct DatagramQueue {
+    rx: mpsc::UnboundedReceiver<Datagram>,
+    next: Option<Datagram>,
+}
+
+/// Creates both ends of a memory connection
+/// between `client` and `server`.
+pub(crate) fn pair(
+    client: SocketAddr,
+    server: SocketAddr,
+    conditions: &Conditions,
+) -> (Connection, Connection) {
+    let (client_uni_tx, server_uni_rx) = mpsc::unbounded_channel();
+    let (server_uni_tx, client_uni_rx) = mpsc::unbounded_channel();
+    let (client_bi_tx, server_bi_rx) = mpsc::unbounded_channel();
+    let (server_bi_tx, client_bi_rx) = mpsc::unbounded_channel();
+    let (client_datagram_tx, server_datagram_rx) = mpsc::unbounded_channel();
+    let (server_datagram_tx, client_datagram_rx) = mpsc::unbounded_channel();
+    let closed = Arc::new(watch::Sender::new(None));
+
+    let end = |remote, uni_tx, uni_rx, bi_tx, bi_rx, datagram_tx, datagram_rx| Connection {
+        remote,
+        latency: conditions.latency,
+        loss: std::sync::Mutex::new(Loss::new(conditions)),
+        uni_tx,
+        uni_rx: Mutex::new(uni_rx),
+        bi_tx,
+        bi_rx: Mutex::new(bi_rx),
+        datagram_tx,
+        datagram_rx: Mutex::new(DatagramQueue {
+            rx: datagram_rx,
+            next: None,
+        }),
+        closed: closed.clone(),
+    };
+
+    (
+        end(
+            server,
+            client_uni_tx,
+            client_uni_rx,
+            client_bi_tx,
+            client_bi_rx,
+            client_datagram_tx,
+            client_datagram_rx,
+        ),
+        end(
+            client,
+            server_uni_tx,
+            server_uni_rx,
+            server_bi_tx,
+            server_bi_rx,
+            server_datagram_tx,
+            server_datagram_rx,
+        ),
+    )
+}
+
+impl Connection {
+    pub async fn open_uni(&self) -> Result<SendStream, ConnectionError> {
+        self.ensure_open()?;
+
+        let (tx, rx) = stream::pair(self.latency, self.closed.subscribe());
+        self.uni_tx.send(rx).map_err(|_| self.closed_error())?;
+        Ok(tx)
+    }
+
+    pub async fn accept_uni(&self) -> Result<RecvStream, ConnectionError> {
+        let mut closed = self.closed.subscribe();
+
+        tokio::select! {
+            biased;
+            _ = closed.wait_for(Option::is_some) => Err(self.closed_error()),
+            rx = async { self.uni_rx.lock().await.recv().await } => {
+                rx.ok_or_else(|| self.closed_error())
+            }
+        }
+    }
+
+    pub async fn open_bi(&self) -> Result<(SendStream, RecvStream), ConnectionError> {
+        self.ensure_open()?;
+
+        let (local_tx, remote_rx) = stream::pair(self.latency, self.closed.subscribe());
+        let (remote_tx, local_rx) = stream::pair(self.latency, self.closed.subscribe());
+        self.bi_tx
+            .send((remote_tx, remote_rx))
+            .map_err(|_| self.closed_error())?;
+        Ok((local_tx, local_rx))
+    }
+
+    pub async fn accept_bi(&self) -> Result<(SendStream, RecvStream), ConnectionError> {
+        let mut closed = self.closed.subscribe();
+
+        tokio::select! {
+            biased;
+            _ = closed.wait_for(Option::is_some) => Err(self.closed_error()),
+            streams = async { self.bi_rx.lock().await.recv().await } => {
+                streams.ok_or_else(|| self.closed_error())
+            }
+        }
+    }
+
+    /// Sends a datagram which arrives after the
+    /// latency, unless it is lost.
+    pub fn send_datagram(&self, buf: Bytes) -> Result<(), ConnectionError> {
+        self.ensure_open()?;
+        if buf.len() > PACKET_SIZE {
+            return Err(ConnectionError::Other {
+                msg: format!("datagram of {} bytes is too large", buf.len()),
+            });
+        }
+
+        if self.loss.lock().expect("loss lock poisoned").next() {
+            return Ok(());
+        }
+        // the receiver is only gone once the connection is
+        let _ = self.datagram_tx.send((Instant::now() + self.latency, buf));
+        Ok(())
+    }
+
+    /// Receives the next datagram.
+    ///
+    /// This operation is cancel-safe.
+    pub async fn recv_datagram(&self) -> Result<Bytes, ConnectionError> {
+        let mut closed = self.closed.subscribe();
+
+        tokio::select! {
+            biased;
+            _ = closed.wait_for(Option::is_some) => Err(self.closed_error()),
+            buf = async {
+                let mut queue = self.datagram_rx.lock().await;
+                if queue.next.is_none() {
+                    queue.next = Some(queue.rx.recv().await?);
+                }
+                let (arrival, _) = queue.next.as_ref()?;
+                tokio::time::sleep_until(*arrival).await;
+                queue.next.take().map(|(_, buf)| buf)
+            } => buf.ok_or_else(|| self.closed_error()),
+        }
+    }
+
+    pub fn max_datagram_size(&self) -> Option<usize> {
+        Some(PACKET_SIZE)
+    }
+
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote
+    }
+
+    /// Closes both ends of the connection with
+    /// `code`, unless already closed.
+    pub fn close(&self, code: u64, reason: &str) {
+        self.closed.send_if_modified(|closed| {
+            if closed.is_some() {
+                return false;
+            }
+            *closed = Some(Close {
+                code,
+                reason: reason.to_owned(),
+            });
+            true
+        });
+    }
+
+    fn ensure_open(&self) -> Result<(), ConnectionError> {
+        match self.closed.borrow().as_ref() {
+            Some(close) => Err(close.into()),
+            None => Ok(()),
+        }
+    }
+
+    fn closed_error(&self) -> ConnectionError {
+        match self.closed.borrow().as_ref() {
+            Some(close) => close.into(),
+            None => ConnectionError::MemoryClosed {
+                code: 0,
+                reason: "peer dropped the connection".into(),
+            },
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // like an idle timeout, without an explicit close
+        self.close(0, "peer dropped the connection");
+    }
+}
@@ -0,0 +1,376 @@
+//! In-memory transport connecting both ends of a
+//! [Connection](super::Connection) with channels.
+//!
+//! Runs Clients and Relays within a single process
+//! without sockets or certificates, e.g. in tests.
+
+mod connection;
+mod stream;
+
+pub(crate) use {
+    connection::{Connection, pair},
+    stream::{RecvStream, SendStream},
+};
+
+use {
+    super::error::{ConnectionError, EndpointError},
+    bon::Builder,
+    core::{
+        net::{Ipv4Addr, SocketAddr},
+        time::Duration,
+    },
+    std::sync::{
+        Arc,
+        atomic::{AtomicU16, Ordering},
+    },
+    tokio::sync::{Mutex, mpsc},
+    url::Url,
+};
+
+/// The scheme of the URL of a memory [Endpoint].
+pub const SCHEME: &str = "memory";
+
+/// ## Link Conditions
+///
+/// The network conditions a memory connection
+/// simulates.
+///
+/// Stream data is delayed but always delivered,
+/// datagrams are delayed and may be lost.
+#[derive(Debug, Clone, Default, PartialEq, Builder)]
+pub struct Conditions {
+    /// One way delay of stream data and datagrams
+    #[builder(default, setters(doc {
+        /// ## Latency
+        ///
+        /// One way delay of stream data and datagrams
+    }))]
+    pub latency: Duration,
+
+    /// Probability between 0 and 1 of a datagram
+    /// to be lost
+    #[builder(default, setters(doc {
+        /// ## Datagram Loss
+        ///
+        /// Probability between 0 and 1 of a datagram
+        /// to be lost
+    }))]
+    pub loss: f64,
+
+    /// Seed of the pattern of lost datagrams,
+    /// the same seed loses the same datagrams
+    #[builder(default, setters(doc {
+        /// ## Loss Seed
+        ///
+        /// Seed of the pattern of lost datagrams,
+        /// the same seed loses the same datagrams
+    }))]
+    pub seed: u64,
+}
+
+/// Why a memory connection was closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Close {
+    code: u64,
+    reason: String,
+}
+
+impl From<&Close> for ConnectionError {
+    fn from(close: &Close) -> Self {
+        Self::MemoryClosed {
+            code: close.code,
+            reason: close.reason.clone(),
+        }
+    }
+}
+
+/// ## Memory Endpoint
+///
+/// Accepts the memory connections of its
+/// [Connector]s.
+pub(crate) struct Endpoint {
+    incoming: Mutex<mpsc::UnboundedReceiver<Connection>>,
+    connector: Connector,
+}
+
+impl Endpoint {
+    /// Creates an Endpoint reachable at `addr`.
+    pub fn new(addr: SocketAddr) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            incoming: Mutex::new(rx),
+            connector: Connector {
+                addr,
+                next_port: Arc::new(AtomicU16::new(1)),
+                tx,
+            },
+        }
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.connector.addr
+    }
+
+    /// A [Connector] to connect to this Endpoint.
+    pub fn connector(&self) -> Connector {
+        self.connector.clone()
+    }
+
+    /// Accepts the next incoming connection.
+    pub async fn accept(&self) -> Result<Connection, EndpointError> {
+        self.incoming
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or(EndpointError::MemoryClosed)
+    }
+}
+
+/// ## Memory Connector
+///
+/// Connects to the memory Endpoint of a
+/// [Server](crate::Server).
+#[derive(Debug, Clone)]
+pub struct Connector {
+    addr: SocketAddr,
+    next_port: Arc<AtomicU16>,
+    tx: mpsc::UnboundedSender<Connection>,
+}
+
+impl Connector {
+    /// The URL of the Endpoint.
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("{SCHEME}://{}", self.addr)).expect("memory URL is valid")
+    }
+
+    /// Connects a new client to the Endpoint with
+    /// the network `conditions`.
+    pub(crate) fn connect(&self, conditions: &Conditions) -> Result<Connection, ConnectionError> {
+        // every client gets its own address to tell them apart
+        let port = self.next_port.fetch_add(1, Ordering::Relaxed);
+        let client = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+
+        let (client, server) = pair(client, self.addr, conditions);
+        self.tx
+            .send(server)
+            .map_err(|_| ConnectionError::MemoryRefused { addr: self.addr })?;
+        Ok(client)
+    }
+}
+
+/// A small deterministic PRNG (SplitMix64) to
+/// decide which datagrams are lost.
+#[derive(Debug)]
+struct Loss {
+    probability: f64,
+    state: u64,
+}
+
+impl Loss {
+    fn new(conditions: &Conditions) -> Self {
+        Self {
+            probability: conditions.loss,
+            state: conditions.seed,
+        }
+    }
+
+    /// Whether the next datagram is lost.
+    fn next(&mut self) -> bool {
+        if self.probability <= 0.0 {
+            return false;
+        }
+
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        // the upper 53 bits make an evenly distributed f64 in [0, 1)
+        ((z >> 11) as f64 / (1u64 << 53) as f64) < self.probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            transport::{self, error::DatagramError},
+            types::{data::ObjectDatagram, error_code::Termination},
+        },
+        bytes::Bytes,
+        tokio::time::Instant,
+    };
+
+    fn addrs() -> (SocketAddr, SocketAddr) {
+        (
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 1)),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 4443)),
+        )
+    }
+
+    fn transport_pair(conditions: &Conditions) -> (transport::Connection, transport::Connection) {
+        let (client, server) = addrs();
+        let (client, server) = pair(client, server, conditions);
+        (
+            transport::Connection::Memory(Box::new(client)),
+            transport::Connection::Memory(Box::new(server)),
+        )
+    }
+
+    #[tokio::test]
+    async fn uni_stream_test() {
+        let (client, server) = transport_pair(&Conditions::default());
+
+        let mut tx = client.open_uni().await.unwrap();
+        tx.write(b"hello ").await.unwrap();
+        tx.write(b"world").await.unwrap();
+        tx.finish().await.unwrap();
+
+        let mut rx = server.accept_uni().await.unwrap();
+        let mut buf = [0; 4];
+        let mut data = Vec::new();
+        while let Some(len) = rx.read(&mut buf).await.unwrap() {
+            data.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(data, b"hello world");
+    }
+
+    #[tokio::test]
+    async fn bi_stream_test() {
+        let (client, server) = transport_pair(&Conditions::default());
+
+        let (mut client_tx, mut client_rx) = client.open_bi().await.unwrap();
+        let (mut server_tx, mut server_rx) = server.accept_bi().await.unwrap();
+
+        client_tx.write(b"ping").await.unwrap();
+        let mut buf = [0; 8];
+        assert_eq!(server_rx.read(&mut buf).await.unwrap(), Some(4));
+        assert_eq!(&buf[..4], b"ping");
+
+        server_tx.write(b"pong").await.unwrap();
+        assert_eq!(client_rx.read(&mut buf).await.unwrap(), Some(4));
+        assert_eq!(&buf[..4], b"pong");
+
+        // dropping a stream without finishing resets it
+        drop(server_tx);
+        assert!(client_rx.read(&mut buf).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn datagram_test() {
+        let (client, server) = transport_pair(&Conditions::default());
+
+        let datagram = ObjectDatagram::builder()
+            .alias(1u8)
+            .group(2u8)
+            .object(3u8)
+            .priority(4)
+            .payload(Bytes::from_static(b"payload"))
+            .build();
+        client.send_object_datagram(&datagram).unwrap();
+        assert_eq!(server.recv_object_datagram().await.unwrap(), datagram);
+
+        let too_large = Bytes::from(vec![0; server.max_datagram_size().unwrap() + 1]);
+        assert!(server.send_datagram(too_large.clone()).is_err());
+
+        // rejected before it is sent
+        let datagram = ObjectDatagram::builder()
+            .alias(1u8)
+            .group(2u8)
+            .object(3u8)
+            .priority(4)
+            .payload(too_large)
+            .build();
+        assert!(matches!(
+            client.send_object_datagram(&datagram),
+            Err(DatagramError::TooLarge { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn close_test() {
+        let (client, server) = transport_pair(&Conditions::default());
+        let mut rx = client.open_uni().await.unwrap();
+        rx.write(b"data").await.unwrap();
+        let mut stream = server.accept_uni().await.unwrap();
+
+        server.close(Termination::GoAwayTimeout);
+
+        let err = client.accept_uni().await.err().unwrap();
+        assert_eq!(
+            err,
+            ConnectionError::MemoryClosed {
+                code: Termination::GoAwayTimeout.key().into(),
+                reason: Termination::GoAwayTimeout.to_string(),
+            }
+        );
+        assert!(client.open_uni().await.is_err());
+        assert!(rx.write(b"more").await.is_err());
+        assert!(stream.read(&mut [0; 4]).await.is_err());
+        assert!(client.send_datagram(Bytes::from_static(b"x")).is_err());
+    }
+
+    #[tokio::test]
+    async fn latency_test() {
+        let latency = Duration::from_millis(20);
+        let (client, server) = transport_pair(&Conditions::builder().latency(latency).build());
+
+        let start = Instant::now();
+        client
+            .send_datagram(Bytes::from_static(b"datagram"))
+            .unwrap();
+        server.recv_datagram().await.unwrap();
+        assert!(start.elapsed() >= latency);
+
+        let start = Instant::now();
+        let mut tx = client.open_uni().await.unwrap();
+        tx.write(b"stream").await.unwrap();
+        let mut rx = server.accept_uni().await.unwrap();
+        assert_eq!(rx.read(&mut [0; 8]).await.unwrap(), Some(6));
+        assert!(start.elapsed() >= latency);
+    }
+
+    #[test]
+    fn loss_test() {
+        let conditions = Conditions::builder().loss(0.25).seed(7).build();
+
+        let mut loss = Loss::new(&conditions);
+        let pattern: Vec<bool> = (0..1000).map(|_| loss.next()).collect();
+        let lost = pattern.iter().filter(|lost| **lost).count();
+        assert!((200..300).contains(&lost), "{lost} lost");
+
+        // the same seed loses the same datagrams
+        let mut loss = Loss::new(&conditions);
+        assert!(pattern.iter().all(|lost| *lost == loss.next()));
+
+        let mut loss = Loss::new(&Conditions::default());
+        assert!((0..1000).all(|_| !loss.next()));
+    }
+
+    #[tokio::test]
+    async fn endpoint_test() {
+        let (_, addr) = addrs();
+        let endpoint = Endpoint::new(addr);
+        let connector = endpoint.connector();
+        assert_eq!(connector.url().as_str(), "memory://127.0.0.1:4443");
+
+        let first = connector.connect(&Conditions::default()).unwrap();
+        let _second = connector.connect(&Conditions::default()).unwrap();
+        assert_eq!(first.remote_addr(), addr);
+
+        // every client gets its own address
+        let first = endpoint.accept().await.unwrap();
+        let second = endpoint.accept().await.unwrap();
+        assert_ne!(first.remote_addr(), second.remote_addr());
+        assert_eq!(endpoint.local_addr(), addr);
+
+        drop(endpoint);
+        assert_eq!(
+            connector.connect(&Conditions::default()).err(),
+            Some(ConnectionError::MemoryRefused { addr })
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use {
+    super::Close,
+    crate::transport::error::{RecvError, SendError},
+    bytes::{Buf, Bytes},
+    core::time::Duration,
+    tokio::{
+        sync::{mpsc, watch},
+        time::Instant,
+    },
+};
+
+/// Data written to a stream and when it arrives.
+enum Chunk {
+    Data(Instant, Bytes),
+    Finish(Instant),
+}
+
+impl Chunk {
+    fn arrival(&self) -> Instant {
+        match self {
+            Self::Data(arrival, _) | Self::Finish(arrival) => *arrival,
+        }
+    }
+}
+
+/// Creates both ends of a memory stream whose
+/// data arrives after `latency`.
+pub(super) fn pair(
+    latency: Duration,
+    closed: watch::Receiver<Option<Close>>,
+) -> (SendStream, RecvStream) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (
+        SendStream {
+            tx: Some(tx),
+            latency,
+            closed: closed.clone(),
+        },
+        RecvStream {
+            rx,
+            next: None,
+            buf: Bytes::new(),
+            finished: false,
+            closed,
+        },
+    )
+}
+
+/// ## Memory Send Stream
+///
+/// Dropping it without finishing resets the
+/// stream.
+pub struct SendStream {
+    /// None once finished.
+    tx: Option<mpsc::UnboundedSender<Chunk>>,
+    latency: Duration,
+    closed: watch::Receiver<Option<Close>>,
+}
+
+impl SendStream {
+    pub async fn write_all(&mut self, buf: &[u8]) -> Result<(), SendError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let chunk = Chunk::Data(self.arrival(), Bytes::copy_from_slice(buf));
+        self.send(chunk)
+    }
+
+    pub async fn finish(&mut self) -> Result<(), SendError> {
+        let chunk = Chunk::Finish(self.arrival());
+        self.send(chunk)?;
+        self.tx = None;
+        Ok(())
+    }
+
+    fn send(&self, chunk: Chunk) -> Result<(), SendError> {
+        if let Some(close) = self.closed.borrow().as_ref() {
+            return Err(SendError::Memory {
+                msg: format!("connection closed: {}", close.reason),
+            });
+        }
+
+        let tx = self.tx.as_ref().ok_or_else(|| SendError::Memory {
+            msg: "stream already finished".into(),
+        })?;
+        tx.send(chunk).map_err(|_| SendError::Memory {
+            msg: "stream stopped by peer".into(),
+        })
+    }
+
+    fn arrival(&self) -> Instant {
+        Instant::now() + self.latency
+    }
+}
+
+/// ## Memory Receive Stream
+pub struct RecvStream {
+    rx: mpsc::UnboundedReceiver<Chunk>,
+    /// The chunk which may still be on its way.
+    next: Option<Chunk>,
+    /// Data of the last chunk not read yet.
+    buf: Bytes,
+    finished: bool,
+    closed: watch::Receiver<Option<Close>>,
+}
+
+impl RecvStream {
+    /// Read data contiguously from the stream.
+    ///
+    /// Yields None once the stream was finished.
+    ///
+    /// This operation is cancel-safe.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, RecvError> {
+        loop {
+            if !self.buf.is_empty() {
+                let len = buf.len().min(self.buf.len());
+                self.buf.copy_to_slice(&mut buf[..len]);
+                return Ok(Some(len));
+            }
+            if self.finished {
+                return Ok(None);
+            }
+
+            let mut closed = self.closed.clone();
+            let chunk = tokio::select! {
+                biased;
+                close = closed.wait_for(Option::is_some) => {
+                    let reason = close
+                        .ok()
+                        .and_then(|close| close.as_ref().map(|close| close.reason.clone()))
+                        .unwrap_or_default();
+                    return Err(RecvError::Memory {
+                        msg: format!("connection closed: {reason}"),
+                    });
+                }
+                chunk = Self::next_chunk(&mut self.rx, &mut self.next) => chunk?,
+            };
+
+            match chunk {
+                Chunk::Data(_, data) => self.buf = data,
+                Chunk::Finish(_) => self.finished = true,
+            }
+        }
+    }
+
+    /// Waits for the next chunk to arrive.
+    async fn next_chunk(
+        rx: &mut mpsc::UnboundedReceiver<Chunk>,
+        next: &mut Option<Chunk>,
+    ) -> Result<Chunk, RecvError> {
+        let arrival = match next {
+            Some(chunk) => chunk.arrival(),
+            None => {
+                let chunk = rx.recv().await.ok_or_else(|| RecvError::Memory {
+                    msg: "stream reset by peer".into(),
+                })?;
+                next.insert(chunk).arrival()
+            }
+        };
+
+        tokio::time::sleep_until(arrival).await;
+        Ok(next.take().expect("chunk was received"))
+    }
+}
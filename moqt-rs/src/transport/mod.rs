@@ -1,8 +1,12 @@
 //! Wrapper types around QUIC (quinn) and WebTransport (wtransport)
 //! types to have a unified API to use either of them.
+//!
+//! The [memory] transport connects both ends within
+//! the same process, e.g. for tests.
 
 mod connection;
 mod endpoint;
+pub mod memory;
 mod recv_stream;
 mod send_stream;
 
@@ -17,7 +21,7 @@ pub mod quic {
 
 pub mod error {
     pub use super::{
-        connection::ConnectionError,
+        connection::{ConnectionError, DatagramError},
         endpoint::EndpointError,
         recv_stream::{DecodeError, RecvError},
         send_stream::{EncodeError, SendError},
@@ -30,4 +34,5 @@ pub(crate) use {
 
 const WEBTRANSPORT: &str = "WebTransport";
 const QUIC: &str = "QUIC";
+const MEMORY: &str = "Memory";
 const PACKET_SIZE: usize = (1 << 16) - 1;
@@ -10,6 +10,9 @@ pub enum RecvError {
     WebTransport {
         source: webtransport::error::StreamReadError,
     },
+
+    #[snafu(display("failed to read from stream: {msg}"))]
+    Memory { msg: String },
 }
 
 #[derive(Debug, Snafu, Clone, PartialEq, Eq)]
@@ -23,4 +26,7 @@ pub enum DecodeError {
 
     #[snafu(display("unexpected end of stream reached"))]
     EndOfStream,
+
+    #[snafu(display("value exceeds {max} buffered bytes"))]
+    TooLarge { max: usize },
 }
@@ -1,19 +1,27 @@
 mod error;
 
+use bytes::{Buf, BytesMut};
 pub use error::{DecodeError, RecvError};
-use snafu::OptionExt;
-use varint::{VarInt, core::ReferenceReader};
+use varint::{
+    VarInt,
+    core::{ReaderError, ReferenceReader},
+};
 
-use crate::transport::PACKET_SIZE;
+use crate::transport::{PACKET_SIZE, memory};
 
 use {
     error::{ctx, dec_ctx},
-    snafu::ResultExt,
+    snafu::{ResultExt, ensure},
 };
 
+/// Most bytes buffered to decode a single value,
+/// like an Object with its payload.
+const MAX_BUFFERED: usize = 16 * 1024 * 1024;
+
 pub enum RecvStream {
     Quic(quic::RecvStream),
     WebTransport(webtransport::RecvStream),
+    Memory(memory::RecvStream),
 }
 
 impl RecvStream {
@@ -27,29 +35,166 @@ impl RecvStream {
         match self {
             Self::Quic(rx) => rx.read(buf).await.context(ctx::QuicSnafu),
             Self::WebTransport(rx) => rx.read(buf).await.context(ctx::WebTransportSnafu),
+            Self::Memory(rx) => rx.read(buf).await,
         }
     }
 
-    #[tracing::instrument(skip(self), err)]
-    pub async fn recv<V>(&mut self) -> Result<V, DecodeError>
+    /// Decodes the next `V` from `buf`, reading more data
+    /// from the stream while `buf` holds an incomplete `V`.
+    ///
+    /// Any bytes following the decoded `V` remain in `buf`
+    /// for the next call.
+    ///
+    /// Yields None if the stream was finished without
+    /// any data left in `buf`. Fails once a `V` does
+    /// not fit [MAX_BUFFERED] bytes.
+    #[tracing::instrument(skip(self, buf), err)]
+    pub async fn recv_buffered<V>(&mut self, buf: &mut BytesMut) -> Result<Option<V>, DecodeError>
+    where
+        V: VarInt,
+        V::Error: 'static,
+    {
+        self.decode_buffered(buf, true).await
+    }
+
+    /// Like [recv_buffered](Self::recv_buffered), but leaves
+    /// the decoded `V` in `buf`.
+    #[tracing::instrument(skip(self, buf), err)]
+    pub async fn peek_buffered<V>(&mut self, buf: &mut BytesMut) -> Result<Option<V>, DecodeError>
     where
         V: VarInt,
+        V::Error: 'static,
     {
-        let mut buf = vec![0; PACKET_SIZE];
-        let len = self
-            .read(&mut buf)
-            .await
-            .context(dec_ctx::RecvSnafu)?
-            .context(dec_ctx::EndOfStreamSnafu)?;
-        let buf = &buf[..len];
-
-        let mut reader = ReferenceReader::new(buf);
-
-        let (msg, _bits) =
-            V::decode(&mut reader, Some(len * 8)).map_err(|err| DecodeError::VarInt {
-                cause: err.to_string(),
-            })?;
-
-        Ok(msg)
+        self.decode_buffered(buf, false).await
+    }
+
+    async fn decode_buffered<V>(
+        &mut self,
+        buf: &mut BytesMut,
+        consume: bool,
+    ) -> Result<Option<V>, DecodeError>
+    where
+        V: VarInt,
+        V::Error: 'static,
+    {
+        loop {
+            if !buf.is_empty() {
+                let mut reader = ReferenceReader::new(buf);
+
+                match V::decode(&mut reader, None) {
+                    Ok((msg, bits)) => {
+                        if consume {
+                            buf.advance(bits.div_ceil(8));
+                        }
+                        return Ok(Some(msg));
+                    }
+                    Err(err) if !is_incomplete(&err) => {
+                        return Err(DecodeError::VarInt {
+                            cause: err.to_string(),
+                        });
+                    }
+                    Err(_) => (),
+                }
+            }
+
+            ensure!(
+                buf.len() < MAX_BUFFERED,
+                dec_ctx::TooLargeSnafu { max: MAX_BUFFERED }
+            );
+            let len = PACKET_SIZE.min(MAX_BUFFERED - buf.len());
+            let mut tail = Tail::new(buf, len);
+            match self.read(tail.spare()).await.context(dec_ctx::RecvSnafu)? {
+                Some(len) => tail.fill(len),
+                None if tail.filled == 0 => return Ok(None),
+                None => return Err(DecodeError::EndOfStream),
+            }
+        }
+    }
+}
+
+/// The spare end of a buffer data is read into
+/// without another allocation, cut off again when
+/// dropped, even if the read was cancelled.
+struct Tail<'a> {
+    buf: &'a mut BytesMut,
+    /// Length of the data in `buf`.
+    filled: usize,
+}
+
+impl<'a> Tail<'a> {
+    /// Extends `buf` by `len` spare bytes.
+    fn new(buf: &'a mut BytesMut, len: usize) -> Self {
+        let filled = buf.len();
+        buf.resize(filled + len, 0);
+        Self { buf, filled }
+    }
+
+    fn spare(&mut self) -> &mut [u8] {
+        &mut self.buf[self.filled..]
+    }
+
+    /// Keeps the first `len` spare bytes.
+    fn fill(&mut self, len: usize) {
+        self.filled += len;
+    }
+}
+
+impl Drop for Tail<'_> {
+    fn drop(&mut self) {
+        self.buf.truncate(self.filled);
+    }
+}
+
+/// Whether `err` was caused by a buffer which ended
+/// before the value was fully decoded.
+fn is_incomplete(err: &(dyn core::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(ReaderError::MissingBytes { .. }) = err.downcast_ref() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            transport::{
+                Connection,
+                memory::{self, Conditions},
+            },
+            types::data::SubgroupObject,
+        },
+    };
+
+    #[tokio::test]
+    async fn too_large_test() {
+        let (client, server) = memory::pair(
+            ([127, 0, 0, 1], 1).into(),
+            ([127, 0, 0, 1], 4443).into(),
+            &Conditions::default(),
+        );
+        let (client, server) = (
+            Connection::Memory(Box::new(client)),
+            Connection::Memory(Box::new(server)),
+        );
+        let mut tx = client.open_uni().await.unwrap();
+        let mut rx = server.accept_uni().await.unwrap();
+
+        // Object ID 0 with a payload of 32 MiB
+        tx.write(&[0, 0x82, 0, 0, 0]).await.unwrap();
+        let chunk = vec![0; PACKET_SIZE];
+        for _ in 0..=MAX_BUFFERED / PACKET_SIZE {
+            tx.write(&chunk).await.unwrap();
+        }
+
+        let mut buf = BytesMut::new();
+        let res = rx.recv_buffered::<SubgroupObject>(&mut buf).await;
+        assert_eq!(res.err(), Some(DecodeError::TooLarge { max: MAX_BUFFERED }));
+        assert_eq!(buf.len(), MAX_BUFFERED);
     }
 }
@@ -11,11 +11,21 @@ pub enum SendError {
         source: quic::WriteError,
     },
 
+    #[snafu(display("failed to finish stream"))]
+    QuicFinish {
+        source: quic::ClosedStream,
+    },
+
     #[snafu(display("failed to write data to stream"))]
     WebTransport {
         source: webtransport::error::StreamWriteError,
     },
 
+    #[snafu(display("failed to write data to stream: {msg}"))]
+    Memory {
+        msg: String,
+    },
+
     VarInt {
         cause: String,
     },
@@ -3,6 +3,7 @@ mod error;
 pub use error::{EncodeError, SendError};
 
 use {
+    super::memory,
     error::{ctx, enc_ctx},
     snafu::ResultExt,
     varint::{VarInt, Writer, core::ReferenceWriter},
@@ -11,6 +12,7 @@ use {
 pub enum SendStream {
     Quic(quic::SendStream),
     WebTransport(webtransport::SendStream),
+    Memory(memory::SendStream),
 }
 
 impl SendStream {
@@ -22,6 +24,18 @@ impl SendStream {
         match self {
             Self::Quic(tx) => tx.write_all(buf).await.context(ctx::QuicSnafu),
             Self::WebTransport(tx) => tx.write_all(buf).await.context(ctx::WebTransportSnafu),
+            Self::Memory(tx) => tx.write_all(buf).await,
+        }
+    }
+
+    /// Gracefully finishes the stream after all
+    /// previously written data has been sent.
+    #[tracing::instrument(skip(self), err)]
+    pub async fn finish(&mut self) -> Result<(), SendError> {
+        match self {
+            Self::Quic(tx) => tx.finish().context(ctx::QuicFinishSnafu),
+            Self::WebTransport(tx) => tx.finish().await.context(ctx::WebTransportSnafu),
+            Self::Memory(tx) => tx.finish().await,
         }
     }
 
@@ -1,5 +1,5 @@
 mod subscribe;
 
-const DEFAULT_PRIORITY: u8 = u8::MAX / 2;
+pub(crate) const DEFAULT_PRIORITY: u8 = u8::MAX / 2;
 
 pub use subscribe::SubscribeConfig;
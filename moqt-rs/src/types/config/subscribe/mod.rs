@@ -128,7 +128,7 @@ where
     {
         let this = self.start_location_internal(Some(start.into()));
         let this = this.end_group_internal(Some(end_group.into()));
-        this.filter_type_internal(FilterType::AbsoluteStart)
+        this.filter_type_internal(FilterType::AbsoluteRange)
     }
 }
 
@@ -184,7 +184,7 @@ impl Default for SubscribeConfig {
         Self::builder()
             .sub_prio(DEFAULT_PRIORITY)
             .group_order(GroupOrder::Original)
-            .forward(Forward::Disabled)
+            .forward(Forward::Enabled)
             .with_next_group_start()
             .build()
     }
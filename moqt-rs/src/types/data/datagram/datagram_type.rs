@@ -0,0 +1,132 @@
+use varint::varint_enum;
+
+varint_enum! {
+    /// ## Object Datagram Type
+    ///
+    /// The type of an [ObjectDatagram](super::ObjectDatagram).
+    ///
+    /// The type defines whether the Object ID is
+    /// present, whether the Object carries
+    /// [Extensions](crate::types::data::Extensions),
+    /// whether it is the last Object of its Group
+    /// and whether it carries a payload or an
+    /// [ObjectStatus](crate::types::data::ObjectStatus).
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[varint(value = x(i))]
+    pub enum ObjectDatagramType {
+        /// ## Object
+        ///
+        /// The Object ID is present.
+        Object = 0x0,
+
+        /// ## Object with Extensions
+        ObjectExt = 0x1,
+
+        /// ## Object, End of Group
+        ObjectEndOfGroup = 0x2,
+
+        /// ## Object with Extensions, End of Group
+        ObjectExtEndOfGroup = 0x3,
+
+        /// ## First Object
+        ///
+        /// The Object ID is absent and is 0.
+        FirstObject = 0x4,
+
+        /// ## First Object with Extensions
+        FirstObjectExt = 0x5,
+
+        /// ## First Object, End of Group
+        FirstObjectEndOfGroup = 0x6,
+
+        /// ## First Object with Extensions, End of Group
+        FirstObjectExtEndOfGroup = 0x7,
+
+        /// ## Object Status
+        ///
+        /// The Object carries an [ObjectStatus](crate::types::data::ObjectStatus)
+        /// instead of a payload.
+        Status = 0x20,
+
+        /// ## Object Status with Extensions
+        StatusExt = 0x21,
+    }
+}
+
+/// Bit indicating that the Object carries Extensions.
+const EXTENSIONS_BIT: u32 = 0x01;
+
+/// Bit indicating that the Object ends the Group.
+const END_OF_GROUP_BIT: u32 = 0x02;
+
+/// Bit indicating that the Object ID is absent.
+const NO_OBJECT_ID_BIT: u32 = 0x04;
+
+impl ObjectDatagramType {
+    /// Returns the [ObjectDatagramType] of a datagram
+    /// carrying a payload.
+    ///
+    /// Datagrams of the first Object (ID 0) of a Group
+    /// omit the Object ID.
+    pub fn payload(first_object: bool, extensions: bool, end_of_group: bool) -> Self {
+        match (first_object, extensions, end_of_group) {
+            (false, false, false) => Self::Object,
+            (false, true, false) => Self::ObjectExt,
+            (false, false, true) => Self::ObjectEndOfGroup,
+            (false, true, true) => Self::ObjectExtEndOfGroup,
+            (true, false, false) => Self::FirstObject,
+            (true, true, false) => Self::FirstObjectExt,
+            (true, false, true) => Self::FirstObjectEndOfGroup,
+            (true, true, true) => Self::FirstObjectExtEndOfGroup,
+        }
+    }
+
+    /// Returns the [ObjectDatagramType] of a datagram
+    /// carrying an [ObjectStatus](crate::types::data::ObjectStatus).
+    pub fn status(extensions: bool) -> Self {
+        if extensions {
+            Self::StatusExt
+        } else {
+            Self::Status
+        }
+    }
+
+    /// Whether this datagram carries an
+    /// [ObjectStatus](crate::types::data::ObjectStatus)
+    /// instead of a payload.
+    pub fn is_status(&self) -> bool {
+        matches!(self, Self::Status | Self::StatusExt)
+    }
+
+    /// Whether the Object carries
+    /// [Extensions](crate::types::data::Extensions).
+    pub fn has_extensions(&self) -> bool {
+        self.key() & EXTENSIONS_BIT != 0
+    }
+
+    /// Whether the Object is the last Object of
+    /// its Group.
+    pub fn is_end_of_group(&self) -> bool {
+        !self.is_status() && self.key() & END_OF_GROUP_BIT != 0
+    }
+
+    /// Whether the Object ID is part of the datagram.
+    pub fn has_object_id(&self) -> bool {
+        self.is_status() || self.key() & NO_OBJECT_ID_BIT == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::varint_enum_test;
+
+    use super::*;
+
+    const BUF: &[u8] = &[0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x20, 0x21];
+
+    varint_enum_test!(ObjectDatagramType; BUF; 0x3F;
+        Object, ObjectExt, ObjectEndOfGroup, ObjectExtEndOfGroup,
+        FirstObject, FirstObjectExt, FirstObjectEndOfGroup, FirstObjectExtEndOfGroup,
+        Status, StatusExt,
+    );
+}
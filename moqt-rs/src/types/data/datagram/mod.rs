@@ -0,0 +1,279 @@
+mod datagram_type;
+
+pub use datagram_type::ObjectDatagramType;
+
+use {
+    super::{Extensions, ObjectPayload, ObjectStatus},
+    bon::bon,
+    varint::{VarInt, x},
+};
+
+/// ## Object Datagram
+///
+/// A single Object sent in a QUIC or WebTransport
+/// datagram.
+///
+/// Depending on the [ObjectDatagramType] it carries
+/// either a payload or an [ObjectStatus]. The payload
+/// spans the remainder of the datagram, hence
+/// decoding requires the length of the datagram.
+#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[varint::draft_ref(v = 14, rename = "name-object-datagram")]
+pub struct ObjectDatagram {
+    /// ## Datagram Type
+    ///
+    /// [ObjectDatagramType]
+    pub typ: ObjectDatagramType,
+
+    /// ## Track Alias
+    ///
+    /// The Track Alias assigned by the
+    /// Publisher.
+    pub track_alias: x!(i),
+
+    /// ## Group ID
+    pub group_id: x!(i),
+
+    /// ## Object ID
+    ///
+    /// Some when `typ` is:
+    ///
+    /// * [ObjectDatagramType::Object]
+    /// * [ObjectDatagramType::ObjectExt]
+    /// * [ObjectDatagramType::ObjectEndOfGroup]
+    /// * [ObjectDatagramType::ObjectExtEndOfGroup]
+    /// * [ObjectDatagramType::Status]
+    /// * [ObjectDatagramType::StatusExt]
+    ///
+    /// Otherwise None, in which case the
+    /// Object ID is 0.
+    #[varint(when(typ = 0x0 || 0x1 || 0x2 || 0x3 || 0x20 || 0x21))]
+    pub object_id: x!([i]),
+
+    /// ## Publisher Priority
+    ///
+    /// Lower means higher priority.
+    pub publisher_priority: x!(8),
+
+    /// ## Extension Headers
+    ///
+    /// Some when [ObjectDatagramType::has_extensions].
+    ///
+    /// [Extensions]
+    #[varint(when(typ = 0x1 || 0x3 || 0x5 || 0x7 || 0x21), length = x(i))]
+    pub extensions: x!([Extensions]),
+
+    /// ## Object Status
+    ///
+    /// Some when [ObjectDatagramType::is_status].
+    ///
+    /// [ObjectStatus]
+    #[varint(when(typ = 0x20 || 0x21))]
+    pub status: x!([ObjectStatus]),
+
+    /// ## Object Payload
+    ///
+    /// Some when `typ` is not a status type.
+    #[varint(when(typ = 0x0 || 0x1 || 0x2 || 0x3 || 0x4 || 0x5 || 0x6 || 0x7))]
+    pub payload: x!([..]),
+}
+
+impl ObjectDatagram {
+    /// Returns the Object ID, taking absent IDs
+    /// into account.
+    pub fn resolve_object_id(&self) -> x!(i) {
+        self.object_id.clone().unwrap_or_default()
+    }
+
+    /// Returns the payload or the status of
+    /// this Object.
+    pub fn object_payload(&self) -> ObjectPayload {
+        match (&self.status, &self.payload) {
+            (Some(status), _) => ObjectPayload::Status(*status),
+            (None, Some(payload)) => ObjectPayload::Data(payload.clone()),
+            (None, None) => ObjectPayload::default(),
+        }
+    }
+
+    /// Whether this Object is the last Object of
+    /// its Group.
+    pub fn is_end_of_group(&self) -> bool {
+        self.typ.is_end_of_group() || self.status == Some(ObjectStatus::EndOfGroup)
+    }
+}
+
+#[bon]
+impl ObjectDatagram {
+    /// Creates a Builder for [ObjectDatagram].
+    ///
+    /// The [ObjectDatagramType] is derived from the
+    /// given values, i.e. a [ObjectPayload::Status]
+    /// results in a status datagram.
+    #[builder]
+    pub fn new(
+        #[builder(into, setters(
+            name = alias,
+            doc {
+                /// Sets the track alias on [ObjectDatagram].
+            }
+        ))]
+        track_alias: x!(i),
+
+        #[builder(into, setters(
+            name = group,
+            doc {
+                /// Sets the group ID on [ObjectDatagram].
+            }
+        ))]
+        group_id: x!(i),
+
+        #[builder(into, setters(
+            name = object,
+            doc {
+                /// Sets the object ID on [ObjectDatagram].
+            }
+        ))]
+        object_id: x!(i),
+
+        #[builder(
+            name = priority,
+            with = |p: u8| <x!(8)>::try_from(p).expect("u8 will fit into 8 bits"),
+            setters(
+                doc {
+                    /// Sets the publisher priority on [ObjectDatagram].
+                }
+        ))]
+        publisher_priority: x!(8),
+
+        #[builder(setters(
+            doc {
+                /// Sets the extension headers on [ObjectDatagram].
+            }
+        ))]
+        extensions: Option<Extensions>,
+
+        #[builder(default, setters(
+            doc {
+                /// Sets whether this Object ends the Group on [ObjectDatagram].
+            }
+        ))]
+        end_of_group: bool,
+
+        #[builder(into, setters(
+            doc {
+                /// Sets the payload or status on [ObjectDatagram].
+            }
+        ))]
+        payload: ObjectPayload,
+    ) -> Self {
+        let has_extensions = extensions.is_some();
+
+        match payload {
+            ObjectPayload::Status(status) => Self {
+                typ: ObjectDatagramType::status(has_extensions),
+                track_alias,
+                group_id,
+                object_id: Some(object_id),
+                publisher_priority,
+                extensions,
+                status: Some(status),
+                payload: None,
+            },
+            ObjectPayload::Data(data) => {
+                let first_object = object_id == 0;
+                Self {
+                    typ: ObjectDatagramType::payload(first_object, has_extensions, end_of_group),
+                    track_alias,
+                    group_id,
+                    object_id: (!first_object).then_some(object_id),
+                    publisher_priority,
+                    extensions,
+                    status: None,
+                    payload: Some(data),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for ObjectDatagram {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let v1 = Self::builder()
+                .alias(1u8)
+                .group(2u8)
+                .object(3u8)
+                .priority(4)
+                .payload(*b"audio")
+                .build();
+            let b1 = [
+                [
+                    0x0, // object type
+                    1,   // track alias
+                    2,   // group ID
+                    3,   // object ID
+                    4,   // publisher priority
+                ]
+                .to_vec(),
+                b"audio".to_vec(),
+            ]
+            .concat();
+            let l1 = b1.len() * 8;
+
+            let mut extensions = Extensions::new();
+            extensions.push_number(0x2u8, 8u8);
+            let v2 = Self::builder()
+                .alias(1u8)
+                .group(5u8)
+                .object(0u8)
+                .priority(4)
+                .extensions(extensions.clone())
+                .end_of_group(true)
+                .payload(*b"a")
+                .build();
+            let b2 = vec![
+                0x7, // first object type with extensions and end of group
+                1,   // track alias
+                5,   // group ID
+                // object ID not needed
+                4, // publisher priority
+                2, // extensions length
+                2, // extension key
+                8, // extension value
+                b'a',
+            ];
+            let l2 = b2.len() * 8;
+
+            let v3 = Self::builder()
+                .alias(1u8)
+                .group(5u8)
+                .object(9u8)
+                .priority(4)
+                .extensions(extensions)
+                .payload(ObjectStatus::EndOfTrack)
+                .build();
+            let b3 = vec![
+                0x21, // status type with extensions
+                1,    // track alias
+                5,    // group ID
+                9,    // object ID
+                4,    // publisher priority
+                2,    // extensions length
+                2,    // extension key
+                8,    // extension value
+                4,    // end of track status
+                      // payload not needed
+            ];
+            let l3 = b3.len() * 8;
+
+            vec![(v1, b1, l1), (v2, b2, l2), (v3, b3, l3)]
+        }
+    }
+
+    varint_struct_test!(ObjectDatagram);
+}
@@ -0,0 +1,231 @@
+use {
+    bytes::Bytes,
+    snafu::{OptionExt, ensure},
+    varint::{
+        Reader, VarInt, VarIntBytes, VarIntNumber, Writer,
+        core::external_impls::{IndexMapError, KeyValuePair, Value},
+        error::ctx,
+        x,
+    },
+};
+
+/// ## Extension Headers
+///
+/// Additional metadata attached to an Object.
+///
+/// Each header is a Key-Value-Pair, even keys
+/// carry a Number and odd keys carry bytes.
+/// Unlike [Parameters](crate::types::Parameters)
+/// Extension Headers are preceded by their
+/// total length in bytes instead of their count
+/// and the same key may occur multiple times.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Extensions {
+    inner: Vec<KeyValuePair>,
+}
+
+impl Extensions {
+    /// Creates an empty set of [Extensions].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a numeric Extension Header.
+    ///
+    /// # Panics
+    ///
+    /// When `key` is odd, those keys carry bytes.
+    pub fn push_number<K, V>(&mut self, key: K, value: V) -> &mut Self
+    where
+        K: Into<x!(i)>,
+        V: Into<x!(i)>,
+    {
+        let key = key.into();
+        assert!(
+            key.number::<u128>().is_multiple_of(2),
+            "numeric extension headers require an even key"
+        );
+        self.inner.push(KeyValuePair {
+            key,
+            value: Value::Number(value.into()),
+        });
+        self
+    }
+
+    /// Appends a binary Extension Header.
+    ///
+    /// # Panics
+    ///
+    /// When `key` is even, those keys carry numbers.
+    pub fn push_bytes<K, V>(&mut self, key: K, value: V) -> &mut Self
+    where
+        K: Into<x!(i)>,
+        V: Into<x!(..)>,
+    {
+        let key = key.into();
+        assert!(
+            !key.number::<u128>().is_multiple_of(2),
+            "binary extension headers require an odd key"
+        );
+        self.inner.push(KeyValuePair {
+            key,
+            value: Value::Bytes(value.into()),
+        });
+        self
+    }
+
+    /// Returns the first numeric value with `key`, if any.
+    pub fn number<K>(&self, key: K) -> Option<x!(i)>
+    where
+        K: Into<x!(i)>,
+    {
+        let key = key.into();
+        self.inner.iter().find_map(|kvp| match &kvp.value {
+            Value::Number(num) if kvp.key == key => Some(num.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the first binary value with `key`, if any.
+    pub fn bytes<K>(&self, key: K) -> Option<Bytes>
+    where
+        K: Into<x!(i)>,
+    {
+        let key = key.into();
+        self.inner.iter().find_map(|kvp| match &kvp.value {
+            Value::Bytes(buf) if kvp.key == key => Some(buf.bytes()),
+            _ => None,
+        })
+    }
+
+    /// Iterates over all Extension Headers in the
+    /// order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &KeyValuePair> {
+        self.inner.iter()
+    }
+
+    /// Returns the number of Extension Headers.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns true when there are no Extension Headers.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl From<Vec<KeyValuePair>> for Extensions {
+    fn from(value: Vec<KeyValuePair>) -> Self {
+        Self { inner: value }
+    }
+}
+
+impl VarInt for Extensions {
+    type Error = varint::Error;
+
+    fn decode<R>(reader: &mut R, length: Option<usize>) -> Result<(Self, usize), Self::Error>
+    where
+        R: Reader,
+    {
+        let length = length.context(ctx::MissingLengthSnafu)?;
+
+        let mut bits = 0;
+        let mut inner = Vec::new();
+
+        while bits < length {
+            let (kvp, len) = KeyValuePair::decode(reader, None)
+                .map_err(|source| IndexMapError::KeyValuePair { source })?;
+            bits += len;
+            inner.push(kvp);
+        }
+
+        ensure!(
+            bits == length,
+            ctx::LengthMismatchSnafu {
+                expected: length,
+                got: bits
+            }
+        );
+
+        Ok((Self { inner }, bits))
+    }
+
+    fn encode<W>(&self, writer: &mut W, _length: Option<usize>) -> Result<usize, Self::Error>
+    where
+        W: Writer,
+    {
+        let mut bits = 0;
+
+        for kvp in &self.inner {
+            bits += kvp
+                .encode(writer, None)
+                .map_err(|source| IndexMapError::KeyValuePair { source })?;
+        }
+
+        Ok(bits)
+    }
+
+    fn len_bits(&self) -> Result<usize, Self::Error> {
+        let mut bits = 0;
+
+        for kvp in &self.inner {
+            bits += kvp
+                .len_bits()
+                .map_err(|source| IndexMapError::KeyValuePair { source })?;
+        }
+
+        Ok(bits)
+    }
+
+    fn length_required() -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for Extensions {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let mut v1 = Self::new();
+            v1.push_number(0x2u8, 9u8).push_bytes(0x3u8, "ab");
+            let b1 = [
+                [
+                    2, // numeric key
+                    9, // value
+                    3, // binary key
+                    2, // num bytes
+                ]
+                .to_vec(),
+                b"ab".to_vec(),
+            ]
+            .concat();
+            let l1 = b1.len() * 8;
+
+            let v2 = Self::new();
+            let b2 = vec![];
+            let l2 = 0;
+
+            vec![(v1, b1, l1), (v2, b2, l2)]
+        }
+    }
+
+    varint_struct_test!(Extensions);
+
+    #[test]
+    fn getter_test() {
+        let mut ext = Extensions::new();
+        ext.push_number(0x2u8, 9u8)
+            .push_number(0x2u8, 10u8)
+            .push_bytes(0x3u8, "ab");
+
+        assert_eq!(ext.len(), 3);
+        assert_eq!(ext.number(0x2u8), Some(9u8.into()));
+        assert_eq!(ext.bytes(0x3u8), Some(Bytes::from_static(b"ab")));
+        assert_eq!(ext.number(0x4u8), None);
+    }
+}
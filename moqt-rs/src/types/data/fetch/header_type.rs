@@ -0,0 +1,30 @@
+use varint::varint_enum;
+
+varint_enum! {
+    /// ## Fetch Header Type
+    ///
+    /// The type of a [FetchHeader](super::FetchHeader).
+    ///
+    /// Identifies a unidirectional Data Stream as the
+    /// response to a [Fetch](crate::types::message::Fetch).
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+    #[varint(value = x(i))]
+    pub enum FetchHeaderType {
+        /// ## Fetch Header
+        #[default]
+        Fetch = 0x5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::varint_enum_test;
+
+    use super::*;
+
+    const BUF: &[u8] = &[0x5];
+
+    varint_enum_test!(FetchHeaderType; BUF; 0x3F;
+        Fetch,
+    );
+}
@@ -0,0 +1,74 @@
+mod header_type;
+mod object;
+
+pub use {header_type::FetchHeaderType, object::FetchObject};
+
+use {
+    bon::bon,
+    varint::{VarInt, x},
+};
+
+/// ## Fetch Header
+///
+/// The first message sent on a unidirectional
+/// Data Stream which carries the Objects requested
+/// by a [Fetch](crate::types::message::Fetch).
+///
+/// It is followed by any number of [FetchObject]s.
+#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[varint::draft_ref(v = 14, rename = "name-fetch-header")]
+pub struct FetchHeader {
+    /// ## Header Type
+    ///
+    /// [FetchHeaderType]
+    pub typ: FetchHeaderType,
+
+    /// ## Request ID
+    ///
+    /// The Request ID of the
+    /// [Fetch](crate::types::message::Fetch)
+    /// this stream responds to.
+    pub request_id: x!(i),
+}
+
+#[bon]
+impl FetchHeader {
+    /// Creates a Builder for [FetchHeader].
+    #[builder]
+    pub fn new(
+        #[builder(into, setters(
+            name = id,
+            doc {
+                /// Sets the request ID on [FetchHeader].
+            }
+        ))]
+        request_id: x!(i),
+    ) -> Self {
+        Self {
+            typ: FetchHeaderType::Fetch,
+            request_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for FetchHeader {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let v1 = Self::builder().id(3u8).build();
+            let b1 = vec![
+                0x5, // fetch header type
+                3,   // request ID
+            ];
+            let l1 = b1.len() * 8;
+
+            vec![(v1, b1, l1)]
+        }
+    }
+
+    varint_struct_test!(FetchHeader);
+}
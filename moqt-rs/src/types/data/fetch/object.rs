@@ -0,0 +1,183 @@
+use {
+    super::super::{Extensions, ObjectPayload, ObjectStatus},
+    crate::types::misc::Location,
+    bon::bon,
+    varint::{VarInt, x},
+};
+
+/// ## Fetch Object
+///
+/// An Object sent on a Fetch stream, following
+/// the [FetchHeader](super::FetchHeader).
+///
+/// Unlike Objects on Subgroup streams every
+/// Fetch Object carries its full location,
+/// since consecutive Objects may belong to
+/// different Groups and Subgroups.
+#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[varint::draft_ref(v = 14, rename = "name-fetch-header")]
+pub struct FetchObject {
+    /// ## Group ID
+    pub group_id: x!(i),
+
+    /// ## Subgroup ID
+    pub subgroup_id: x!(i),
+
+    /// ## Object ID
+    pub object_id: x!(i),
+
+    /// ## Publisher Priority
+    ///
+    /// Lower means higher priority.
+    pub publisher_priority: x!(8),
+
+    /// ## Extension Headers
+    ///
+    /// [Extensions]
+    #[varint(length = x(i))]
+    pub extensions: Extensions,
+
+    /// ## Payload
+    ///
+    /// Either the payload of the Object or its
+    /// status.
+    ///
+    /// [ObjectPayload]
+    pub payload: ObjectPayload,
+}
+
+impl FetchObject {
+    /// Returns the [Location] of this Object.
+    pub fn location(&self) -> Location {
+        Location::new(self.group_id.clone(), self.object_id.clone())
+    }
+
+    /// Returns the [ObjectStatus] of this Object.
+    pub fn status(&self) -> ObjectStatus {
+        self.payload.status()
+    }
+}
+
+#[bon]
+impl FetchObject {
+    /// Creates a Builder for [FetchObject].
+    #[builder]
+    pub fn new(
+        #[builder(into, setters(
+            name = group,
+            doc {
+                /// Sets the group ID on [FetchObject].
+            }
+        ))]
+        group_id: x!(i),
+
+        #[builder(into, default = <x!(i)>::from(0u8), setters(
+            name = subgroup,
+            doc {
+                /// Sets the subgroup ID on [FetchObject].
+            }
+        ))]
+        subgroup_id: x!(i),
+
+        #[builder(into, setters(
+            name = object,
+            doc {
+                /// Sets the object ID on [FetchObject].
+            }
+        ))]
+        object_id: x!(i),
+
+        #[builder(
+            name = priority,
+            with = |p: u8| <x!(8)>::try_from(p).expect("u8 will fit into 8 bits"),
+            setters(
+                doc {
+                    /// Sets the publisher priority on [FetchObject].
+                }
+        ))]
+        publisher_priority: x!(8),
+
+        #[builder(default, setters(
+            doc {
+                /// Sets the extension headers on [FetchObject].
+            }
+        ))]
+        extensions: Extensions,
+
+        #[builder(into, default, setters(
+            doc {
+                /// Sets the payload or status on [FetchObject].
+            }
+        ))]
+        payload: ObjectPayload,
+    ) -> Self {
+        Self {
+            group_id,
+            subgroup_id,
+            object_id,
+            publisher_priority,
+            extensions,
+            payload,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for FetchObject {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let v1 = Self::builder()
+                .group(4u8)
+                .subgroup(1u8)
+                .object(2u8)
+                .priority(8)
+                .payload(*b"frame")
+                .build();
+            let b1 = [
+                [
+                    4, // group ID
+                    1, // subgroup ID
+                    2, // object ID
+                    8, // publisher priority
+                    0, // no extensions
+                    5, // payload length
+                ]
+                .to_vec(),
+                b"frame".to_vec(),
+            ]
+            .concat();
+            let l1 = b1.len() * 8;
+
+            let mut extensions = Extensions::new();
+            extensions.push_bytes(0x3u8, "x");
+            let v2 = Self::builder()
+                .group(4u8)
+                .object(3u8)
+                .priority(8)
+                .extensions(extensions)
+                .payload(ObjectStatus::EndOfGroup)
+                .build();
+            let b2 = vec![
+                4,    // group ID
+                0,    // subgroup ID
+                3,    // object ID
+                8,    // publisher priority
+                3,    // extensions length
+                3,    // extension key
+                1,    // num bytes
+                b'x', // extension value
+                0,    // no payload
+                3,    // end of group status
+            ];
+            let l2 = b2.len() * 8;
+
+            vec![(v1, b1, l1), (v2, b2, l2)]
+        }
+    }
+
+    varint_struct_test!(FetchObject);
+}
@@ -0,0 +1,21 @@
+//! Types carried on Data Streams and Datagrams
+
+mod datagram;
+mod extensions;
+mod fetch;
+mod object;
+mod object_status;
+mod payload;
+mod subgroup;
+
+pub use {
+    datagram::{ObjectDatagram, ObjectDatagramType},
+    extensions::Extensions,
+    fetch::{FetchHeader, FetchHeaderType, FetchObject},
+    object::Object,
+    object_status::ObjectStatus,
+    payload::ObjectPayload,
+    subgroup::{
+        SubgroupHeader, SubgroupHeaderType, SubgroupIdMode, SubgroupObject, SubgroupObjectExt,
+    },
+};
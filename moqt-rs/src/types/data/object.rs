@@ -0,0 +1,169 @@
+use {
+    super::{
+        Extensions, FetchObject, ObjectDatagram, ObjectPayload, ObjectStatus, SubgroupHeader,
+        SubgroupObjectExt,
+    },
+    crate::types::misc::Location,
+    bytes::Bytes,
+    varint::x,
+};
+
+/// ## Object
+///
+/// An Object as it is handed to the application,
+/// regardless of whether it was received on a
+/// Subgroup stream, a Fetch stream or as datagram.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Object {
+    /// ## Group ID
+    pub group_id: x!(i),
+
+    /// ## Subgroup ID
+    ///
+    /// Objects received as datagram
+    /// have the Subgroup ID 0.
+    pub subgroup_id: x!(i),
+
+    /// ## Object ID
+    pub object_id: x!(i),
+
+    /// ## Publisher Priority
+    ///
+    /// Lower means higher priority.
+    pub publisher_priority: x!(8),
+
+    /// ## Object Status
+    ///
+    /// [ObjectStatus]
+    pub status: ObjectStatus,
+
+    /// ## Extension Headers
+    ///
+    /// [Extensions]
+    pub extensions: Extensions,
+
+    /// ## Payload
+    ///
+    /// Empty unless `status` is
+    /// [ObjectStatus::Normal].
+    pub payload: Bytes,
+}
+
+impl Object {
+    /// Creates an [Object] from an Object received
+    /// on the Subgroup stream of `header`.
+    ///
+    /// The Subgroup and Object ID are not part of
+    /// `object` and must be resolved by the reader
+    /// of the stream.
+    pub fn from_subgroup(
+        header: &SubgroupHeader,
+        subgroup_id: x!(i),
+        object_id: x!(i),
+        object: SubgroupObjectExt,
+    ) -> Self {
+        Self {
+            group_id: header.group_id.clone(),
+            subgroup_id,
+            object_id,
+            publisher_priority: header.publisher_priority.clone(),
+            status: object.payload.status(),
+            extensions: object.extensions,
+            payload: object.payload.bytes(),
+        }
+    }
+
+    /// Returns the [Location] of this Object.
+    pub fn location(&self) -> Location {
+        Location::new(self.group_id.clone(), self.object_id.clone())
+    }
+
+    /// Returns the payload or, for Objects
+    /// without data, the status of this Object.
+    pub fn object_payload(&self) -> ObjectPayload {
+        match self.status {
+            ObjectStatus::Normal => ObjectPayload::new(self.payload.clone()),
+            status => ObjectPayload::Status(status),
+        }
+    }
+}
+
+impl From<FetchObject> for Object {
+    fn from(value: FetchObject) -> Self {
+        Self {
+            group_id: value.group_id,
+            subgroup_id: value.subgroup_id,
+            object_id: value.object_id,
+            publisher_priority: value.publisher_priority,
+            status: value.payload.status(),
+            extensions: value.extensions,
+            payload: value.payload.bytes(),
+        }
+    }
+}
+
+impl From<ObjectDatagram> for Object {
+    fn from(value: ObjectDatagram) -> Self {
+        let payload = value.object_payload();
+
+        Self {
+            object_id: value.resolve_object_id(),
+            group_id: value.group_id,
+            subgroup_id: <x!(i)>::from(0u8),
+            publisher_priority: value.publisher_priority,
+            status: payload.status(),
+            extensions: value.extensions.unwrap_or_default(),
+            payload: payload.bytes(),
+        }
+    }
+}
+
+impl From<Object> for FetchObject {
+    fn from(value: Object) -> Self {
+        let payload = value.object_payload();
+
+        Self {
+            group_id: value.group_id,
+            subgroup_id: value.subgroup_id,
+            object_id: value.object_id,
+            publisher_priority: value.publisher_priority,
+            extensions: value.extensions,
+            payload,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion_test() {
+        let fetched = FetchObject::builder()
+            .group(4u8)
+            .subgroup(1u8)
+            .object(2u8)
+            .priority(8)
+            .payload(*b"frame")
+            .build();
+
+        let object = Object::from(fetched.clone());
+        assert_eq!(object.location(), Location::new(4u8, 2u8));
+        assert_eq!(object.status, ObjectStatus::Normal);
+        assert_eq!(object.payload, Bytes::from_static(b"frame"));
+        assert_eq!(FetchObject::from(object), fetched);
+
+        let datagram = ObjectDatagram::builder()
+            .alias(1u8)
+            .group(5u8)
+            .object(0u8)
+            .priority(4)
+            .payload(ObjectStatus::EndOfGroup)
+            .build();
+
+        let object = Object::from(datagram);
+        assert_eq!(object.subgroup_id, 0u8);
+        assert_eq!(object.status, ObjectStatus::EndOfGroup);
+        assert!(object.payload.is_empty());
+    }
+}
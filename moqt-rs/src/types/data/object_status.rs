@@ -0,0 +1,50 @@
+use varint::varint_enum;
+
+varint_enum! {
+    /// ## Object Status
+    ///
+    /// Conveys the state of an Object which
+    /// has no payload.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+    #[varint(value = x(i))]
+    pub enum ObjectStatus {
+        /// ## Normal
+        ///
+        /// A regular Object, with an empty
+        /// payload.
+        #[default]
+        Normal = 0x0,
+
+        /// ## Object Does Not Exist
+        ///
+        /// This Object does not exist at any
+        /// Publisher and will not be published
+        /// in the future.
+        DoesNotExist = 0x1,
+
+        /// ## End of Group
+        ///
+        /// No Objects with the same Group ID and
+        /// a larger Object ID exist.
+        EndOfGroup = 0x3,
+
+        /// ## End of Track
+        ///
+        /// No Objects with a larger Location
+        /// exist in this Track.
+        EndOfTrack = 0x4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::varint_enum_test;
+
+    use super::*;
+
+    const BUF: &[u8] = &[0x0, 0x1, 0x3, 0x4];
+
+    varint_enum_test!(ObjectStatus; BUF; 0x3F;
+        Normal, DoesNotExist, EndOfGroup, EndOfTrack,
+    );
+}
@@ -0,0 +1,217 @@
+use {
+    super::ObjectStatus,
+    bytes::Bytes,
+    varint::{Reader, VarInt, VarIntBytes, VarIntNumber, Writer, x},
+};
+
+/// ## Object Payload
+///
+/// The payload of an Object as it is sent on
+/// Data Streams, including the preceding
+/// payload length.
+///
+/// Objects without any payload carry an
+/// [ObjectStatus] instead.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ObjectPayload {
+    /// ## Status
+    ///
+    /// The Object has no payload, only
+    /// an [ObjectStatus].
+    Status(ObjectStatus),
+
+    /// ## Data
+    ///
+    /// The Object carries a non-empty payload.
+    Data(x!(..)),
+}
+
+impl ObjectPayload {
+    /// Creates a new [ObjectPayload] from `buf`.
+    ///
+    /// An empty `buf` results in [ObjectStatus::Normal].
+    pub fn new<B>(buf: B) -> Self
+    where
+        B: Into<Bytes>,
+    {
+        let buf = buf.into();
+        if buf.is_empty() {
+            return Self::Status(ObjectStatus::Normal);
+        }
+        Self::Data(buf.into())
+    }
+
+    /// Returns the [ObjectStatus] of this payload.
+    ///
+    /// Objects carrying data always have the
+    /// status [ObjectStatus::Normal].
+    pub fn status(&self) -> ObjectStatus {
+        match self {
+            Self::Status(status) => *status,
+            Self::Data(_) => ObjectStatus::Normal,
+        }
+    }
+
+    /// Returns the payload bytes.
+    ///
+    /// Empty when this is a [ObjectPayload::Status].
+    pub fn bytes(&self) -> Bytes {
+        match self {
+            Self::Status(_) => Bytes::new(),
+            Self::Data(data) => data.bytes(),
+        }
+    }
+
+    /// Returns the size of the payload in bytes.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Status(_) => 0,
+            Self::Data(data) => data.bytes().len(),
+        }
+    }
+
+    /// Returns true when this payload carries
+    /// no data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for ObjectPayload {
+    fn default() -> Self {
+        Self::Status(ObjectStatus::Normal)
+    }
+}
+
+impl From<ObjectStatus> for ObjectPayload {
+    fn from(value: ObjectStatus) -> Self {
+        Self::Status(value)
+    }
+}
+
+impl From<Bytes> for ObjectPayload {
+    fn from(value: Bytes) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&[u8]> for ObjectPayload {
+    fn from(value: &[u8]) -> Self {
+        Self::new(Bytes::copy_from_slice(value))
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for ObjectPayload {
+    fn from(value: [u8; N]) -> Self {
+        Self::new(Bytes::copy_from_slice(&value))
+    }
+}
+
+impl VarInt for ObjectPayload {
+    type Error = varint::Error;
+
+    fn decode<R>(reader: &mut R, _length: Option<usize>) -> Result<(Self, usize), Self::Error>
+    where
+        R: Reader,
+    {
+        let mut bits = 0;
+
+        let (payload_length, len) = <x!(i)>::decode(reader, None)?;
+        bits += len;
+
+        if payload_length == 0 {
+            let (status, len) = ObjectStatus::decode(reader, None)?;
+            bits += len;
+
+            return Ok((Self::Status(status), bits));
+        }
+
+        let (data, len) = <x!(..)>::decode(reader, Some(payload_length.number::<usize>() * 8))?;
+        bits += len;
+
+        Ok((Self::Data(data), bits))
+    }
+
+    fn encode<W>(&self, writer: &mut W, _length: Option<usize>) -> Result<usize, Self::Error>
+    where
+        W: Writer,
+    {
+        let mut bits = 0;
+
+        match self {
+            Self::Data(data) if !data.bytes().is_empty() => {
+                let data_len = data.len_bits()?;
+                bits += <x!(i)>::try_from(data_len / 8)?.encode(writer, None)?;
+                bits += data.encode(writer, Some(data_len))?;
+            }
+            _ => {
+                bits += <x!(i)>::from(0u8).encode(writer, None)?;
+                bits += self.status().encode(writer, None)?;
+            }
+        }
+
+        Ok(bits)
+    }
+
+    fn len_bits(&self) -> Result<usize, Self::Error> {
+        let mut bits = 0;
+
+        match self {
+            Self::Data(data) if !data.bytes().is_empty() => {
+                let data_len = data.len_bits()?;
+                bits += <x!(i)>::try_from(data_len / 8)?.len_bits()?;
+                bits += data_len;
+            }
+            _ => {
+                bits += <x!(i)>::from(0u8).len_bits()?;
+                bits += self.status().len_bits()?;
+            }
+        }
+
+        Ok(bits)
+    }
+
+    fn length_required() -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for ObjectPayload {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let v1 = Self::new(Bytes::from_static(b"hello"));
+            let b1 = [
+                [
+                    5, // payload length
+                ]
+                .to_vec(),
+                b"hello".to_vec(),
+            ]
+            .concat();
+            let l1 = b1.len() * 8;
+
+            let v2 = Self::from(ObjectStatus::EndOfGroup);
+            let b2 = vec![
+                0, // no payload
+                3, // end of group status
+            ];
+            let l2 = b2.len() * 8;
+
+            let v3 = Self::new(Bytes::new());
+            let b3 = vec![
+                0, // no payload
+                0, // normal status
+            ];
+            let l3 = b3.len() * 8;
+
+            vec![(v1, b1, l1), (v2, b2, l2), (v3, b3, l3)]
+        }
+    }
+
+    varint_struct_test!(ObjectPayload);
+}
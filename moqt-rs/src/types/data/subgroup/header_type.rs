@@ -0,0 +1,158 @@
+use varint::varint_enum;
+
+varint_enum! {
+    /// ## Subgroup Header Type
+    ///
+    /// The type of a [SubgroupHeader](super::SubgroupHeader).
+    ///
+    /// The type defines how the Subgroup ID is
+    /// conveyed, whether the Objects on the stream
+    /// carry [Extensions](crate::types::data::Extensions)
+    /// and whether the stream contains the final
+    /// Object of the Group.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[varint(value = x(i))]
+    pub enum SubgroupHeaderType {
+        /// ## Subgroup ID Zero
+        ///
+        /// The Subgroup ID is absent and is 0.
+        Zero = 0x10,
+
+        /// ## Subgroup ID Zero with Extensions
+        ZeroExt = 0x11,
+
+        /// ## First Object ID
+        ///
+        /// The Subgroup ID is absent and is the
+        /// Object ID of the first Object on the
+        /// stream.
+        FirstObject = 0x12,
+
+        /// ## First Object ID with Extensions
+        FirstObjectExt = 0x13,
+
+        /// ## Explicit Subgroup ID
+        ///
+        /// The Subgroup ID is present.
+        Explicit = 0x14,
+
+        /// ## Explicit Subgroup ID with Extensions
+        ExplicitExt = 0x15,
+
+        /// ## Subgroup ID Zero, End of Group
+        ZeroEndOfGroup = 0x18,
+
+        /// ## Subgroup ID Zero with Extensions, End of Group
+        ZeroExtEndOfGroup = 0x19,
+
+        /// ## First Object ID, End of Group
+        FirstObjectEndOfGroup = 0x1A,
+
+        /// ## First Object ID with Extensions, End of Group
+        FirstObjectExtEndOfGroup = 0x1B,
+
+        /// ## Explicit Subgroup ID, End of Group
+        ExplicitEndOfGroup = 0x1C,
+
+        /// ## Explicit Subgroup ID with Extensions, End of Group
+        ExplicitExtEndOfGroup = 0x1D,
+    }
+}
+
+/// Bit indicating that Objects carry Extensions.
+const EXTENSIONS_BIT: u32 = 0x01;
+
+/// Bit indicating that the stream ends the Group.
+const END_OF_GROUP_BIT: u32 = 0x08;
+
+impl SubgroupHeaderType {
+    /// Returns the [SubgroupHeaderType] for the combination
+    /// of Subgroup ID mode and flags.
+    pub fn new(mode: SubgroupIdMode, extensions: bool, end_of_group: bool) -> Self {
+        match (mode, extensions, end_of_group) {
+            (SubgroupIdMode::Zero, false, false) => Self::Zero,
+            (SubgroupIdMode::Zero, true, false) => Self::ZeroExt,
+            (SubgroupIdMode::FirstObject, false, false) => Self::FirstObject,
+            (SubgroupIdMode::FirstObject, true, false) => Self::FirstObjectExt,
+            (SubgroupIdMode::Explicit, false, false) => Self::Explicit,
+            (SubgroupIdMode::Explicit, true, false) => Self::ExplicitExt,
+            (SubgroupIdMode::Zero, false, true) => Self::ZeroEndOfGroup,
+            (SubgroupIdMode::Zero, true, true) => Self::ZeroExtEndOfGroup,
+            (SubgroupIdMode::FirstObject, false, true) => Self::FirstObjectEndOfGroup,
+            (SubgroupIdMode::FirstObject, true, true) => Self::FirstObjectExtEndOfGroup,
+            (SubgroupIdMode::Explicit, false, true) => Self::ExplicitEndOfGroup,
+            (SubgroupIdMode::Explicit, true, true) => Self::ExplicitExtEndOfGroup,
+        }
+    }
+
+    /// Returns how the Subgroup ID is conveyed.
+    pub fn mode(&self) -> SubgroupIdMode {
+        match self {
+            Self::Zero | Self::ZeroExt | Self::ZeroEndOfGroup | Self::ZeroExtEndOfGroup => {
+                SubgroupIdMode::Zero
+            }
+            Self::FirstObject
+            | Self::FirstObjectExt
+            | Self::FirstObjectEndOfGroup
+            | Self::FirstObjectExtEndOfGroup => SubgroupIdMode::FirstObject,
+            Self::Explicit
+            | Self::ExplicitExt
+            | Self::ExplicitEndOfGroup
+            | Self::ExplicitExtEndOfGroup => SubgroupIdMode::Explicit,
+        }
+    }
+
+    /// Whether the Objects on this stream carry
+    /// [Extensions](crate::types::data::Extensions).
+    pub fn has_extensions(&self) -> bool {
+        self.key() & EXTENSIONS_BIT != 0
+    }
+
+    /// Whether this stream contains the last Object
+    /// of its Group.
+    pub fn contains_end_of_group(&self) -> bool {
+        self.key() & END_OF_GROUP_BIT != 0
+    }
+}
+
+/// ## Subgroup ID Mode
+///
+/// Defines how the Subgroup ID of a
+/// [SubgroupHeader](super::SubgroupHeader) is
+/// conveyed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SubgroupIdMode {
+    /// ## Zero
+    ///
+    /// The Subgroup ID is 0.
+    #[default]
+    Zero,
+
+    /// ## First Object
+    ///
+    /// The Subgroup ID equals the Object ID of
+    /// the first Object.
+    FirstObject,
+
+    /// ## Explicit
+    ///
+    /// The Subgroup ID is part of the header.
+    Explicit,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::varint_enum_test;
+
+    use super::*;
+
+    const BUF: &[u8] = &[
+        0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x18, 0x19, 0x1A, 0x1B, 0x1C, 0x1D,
+    ];
+
+    varint_enum_test!(SubgroupHeaderType; BUF; 0x3F;
+        Zero, ZeroExt, FirstObject, FirstObjectExt, Explicit, ExplicitExt,
+        ZeroEndOfGroup, ZeroExtEndOfGroup, FirstObjectEndOfGroup,
+        FirstObjectExtEndOfGroup, ExplicitEndOfGroup, ExplicitExtEndOfGroup,
+    );
+}
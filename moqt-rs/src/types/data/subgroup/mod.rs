@@ -0,0 +1,273 @@
+mod header_type;
+mod object;
+mod object_ext;
+
+pub use {
+    header_type::{SubgroupHeaderType, SubgroupIdMode},
+    object::SubgroupObject,
+    object_ext::SubgroupObjectExt,
+};
+
+use {
+    bon::bon,
+    varint::{VarInt, x},
+};
+
+/// ## Subgroup Header
+///
+/// The first message sent on a unidirectional
+/// Data Stream which carries the Objects of a
+/// single Subgroup.
+///
+/// It is followed by any number of Objects, either
+/// [SubgroupObject]s or [SubgroupObjectExt]s depending
+/// on [SubgroupHeaderType::has_extensions].
+#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[varint::draft_ref(v = 14, rename = "name-subgroup-header")]
+pub struct SubgroupHeader {
+    /// ## Header Type
+    ///
+    /// [SubgroupHeaderType]
+    pub typ: SubgroupHeaderType,
+
+    /// ## Track Alias
+    ///
+    /// The Track Alias assigned by the
+    /// Publisher.
+    pub track_alias: x!(i),
+
+    /// ## Group ID
+    pub group_id: x!(i),
+
+    /// ## Subgroup ID
+    ///
+    /// Some when `typ` is any explicit
+    /// Subgroup ID type:
+    ///
+    /// * [SubgroupHeaderType::Explicit]
+    /// * [SubgroupHeaderType::ExplicitExt]
+    /// * [SubgroupHeaderType::ExplicitEndOfGroup]
+    /// * [SubgroupHeaderType::ExplicitExtEndOfGroup]
+    ///
+    /// Otherwise None.
+    #[varint(when(typ = 0x14 || 0x15 || 0x1C || 0x1D))]
+    pub subgroup_id: x!([i]),
+
+    /// ## Publisher Priority
+    ///
+    /// The priority of this Subgroup in relation
+    /// to all other Subgroups of the Session.
+    ///
+    /// Lower means higher priority.
+    pub publisher_priority: x!(8),
+}
+
+impl SubgroupHeader {
+    /// Whether the Objects following this header
+    /// carry Extensions.
+    pub fn has_extensions(&self) -> bool {
+        self.typ.has_extensions()
+    }
+
+    /// Whether this Subgroup contains the last Object
+    /// of its Group.
+    pub fn contains_end_of_group(&self) -> bool {
+        self.typ.contains_end_of_group()
+    }
+
+    /// Returns the Subgroup ID.
+    ///
+    /// Depending on the [SubgroupIdMode] this is 0,
+    /// the explicit Subgroup ID or `first_object_id`.
+    pub fn resolve_subgroup_id<I>(&self, first_object_id: I) -> x!(i)
+    where
+        I: Into<x!(i)>,
+    {
+        match (self.typ.mode(), &self.subgroup_id) {
+            (SubgroupIdMode::Explicit, Some(id)) => id.clone(),
+            (SubgroupIdMode::FirstObject, _) => first_object_id.into(),
+            _ => <x!(i)>::default(),
+        }
+    }
+}
+
+use subgroup_header_builder::{IsUnset, SetMode, SetSubgroupId, State};
+impl<S: State> SubgroupHeaderBuilder<S>
+where
+    S::Mode: IsUnset,
+    S::SubgroupId: IsUnset,
+{
+    /// Sets the Subgroup ID to 0 on [SubgroupHeader].
+    pub fn with_zero_subgroup(self) -> SubgroupHeaderBuilder<SetMode<SetSubgroupId<S>>> {
+        let this = self.subgroup_id_internal(None);
+        this.mode_internal(SubgroupIdMode::Zero)
+    }
+
+    /// Sets the Subgroup ID to the Object ID of the first
+    /// Object on [SubgroupHeader].
+    pub fn with_first_object_subgroup(self) -> SubgroupHeaderBuilder<SetMode<SetSubgroupId<S>>> {
+        let this = self.subgroup_id_internal(None);
+        this.mode_internal(SubgroupIdMode::FirstObject)
+    }
+
+    /// Sets an explicit Subgroup ID on [SubgroupHeader].
+    pub fn with_subgroup_id<I>(self, id: I) -> SubgroupHeaderBuilder<SetMode<SetSubgroupId<S>>>
+    where
+        I: Into<x!(i)>,
+    {
+        let this = self.subgroup_id_internal(Some(id.into()));
+        this.mode_internal(SubgroupIdMode::Explicit)
+    }
+}
+
+#[bon]
+impl SubgroupHeader {
+    /// Creates a Builder for [SubgroupHeader].
+    #[builder]
+    pub fn new(
+        #[builder(setters(vis = "", name = mode_internal))] mode: SubgroupIdMode,
+        #[builder(setters(vis = "", name = subgroup_id_internal))] subgroup_id: x!([i]),
+
+        #[builder(into, setters(
+            name = alias,
+            doc {
+                /// Sets the track alias on [SubgroupHeader].
+            }
+        ))]
+        track_alias: x!(i),
+
+        #[builder(into, setters(
+            name = group,
+            doc {
+                /// Sets the group ID on [SubgroupHeader].
+            }
+        ))]
+        group_id: x!(i),
+
+        #[builder(
+            name = priority,
+            with = |p: u8| <x!(8)>::try_from(p).expect("u8 will fit into 8 bits"),
+            setters(
+                doc {
+                    /// Sets the publisher priority on [SubgroupHeader].
+                }
+        ))]
+        publisher_priority: x!(8),
+
+        #[builder(default, setters(
+            doc {
+                /// Sets whether Objects carry extensions on [SubgroupHeader].
+            }
+        ))]
+        extensions: bool,
+
+        #[builder(default, setters(
+            doc {
+                /// Sets whether this Subgroup ends the Group on [SubgroupHeader].
+            }
+        ))]
+        end_of_group: bool,
+    ) -> Self {
+        Self {
+            typ: SubgroupHeaderType::new(mode, extensions, end_of_group),
+            track_alias,
+            group_id,
+            subgroup_id,
+            publisher_priority,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for SubgroupHeader {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let v1 = Self::builder()
+                .alias(2u8)
+                .group(10u8)
+                .with_zero_subgroup()
+                .priority(64)
+                .build();
+            let b1 = vec![
+                0x10, // zero subgroup ID type
+                2,    // track alias
+                10,   // group ID
+                // subgroup ID not needed
+                64, // publisher priority
+            ];
+            let l1 = b1.len() * 8;
+
+            let v2 = Self::builder()
+                .alias(2u8)
+                .group(11u8)
+                .with_first_object_subgroup()
+                .priority(0)
+                .extensions(true)
+                .build();
+            let b2 = vec![
+                0x13, // first object subgroup ID type with extensions
+                2,    // track alias
+                11,   // group ID
+                // subgroup ID not needed
+                0, // publisher priority
+            ];
+            let l2 = b2.len() * 8;
+
+            let v3 = Self::builder()
+                .alias(7u8)
+                .group(1u8)
+                .with_subgroup_id(4u8)
+                .priority(255)
+                .extensions(true)
+                .end_of_group(true)
+                .build();
+            let b3 = vec![
+                0x1D, // explicit subgroup ID type with extensions and end of group
+                7,    // track alias
+                1,    // group ID
+                4,    // subgroup ID
+                255,  // publisher priority
+            ];
+            let l3 = b3.len() * 8;
+
+            vec![(v1, b1, l1), (v2, b2, l2), (v3, b3, l3)]
+        }
+    }
+
+    varint_struct_test!(SubgroupHeader);
+
+    #[test]
+    fn subgroup_id_test() {
+        let [zero, first, explicit] = [
+            SubgroupHeader::builder()
+                .alias(0u8)
+                .group(0u8)
+                .with_zero_subgroup()
+                .priority(0)
+                .build(),
+            SubgroupHeader::builder()
+                .alias(0u8)
+                .group(0u8)
+                .with_first_object_subgroup()
+                .priority(0)
+                .build(),
+            SubgroupHeader::builder()
+                .alias(0u8)
+                .group(0u8)
+                .with_subgroup_id(9u8)
+                .priority(0)
+                .end_of_group(true)
+                .build(),
+        ];
+
+        assert_eq!(zero.resolve_subgroup_id(5u8), 0);
+        assert_eq!(first.resolve_subgroup_id(5u8), 5);
+        assert_eq!(explicit.resolve_subgroup_id(5u8), 9);
+        assert!(explicit.contains_end_of_group());
+        assert!(!explicit.has_extensions());
+    }
+}
@@ -0,0 +1,99 @@
+use {
+    super::super::{ObjectPayload, ObjectStatus},
+    bon::bon,
+    varint::{VarInt, x},
+};
+
+/// ## Subgroup Object
+///
+/// An Object sent on a Subgroup stream whose
+/// [SubgroupHeader](super::SubgroupHeader) has no
+/// Extensions.
+#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[varint::draft_ref(v = 14, rename = "name-subgroup-header")]
+pub struct SubgroupObject {
+    /// ## Object ID Delta
+    ///
+    /// The difference between this Object ID and
+    /// the previous Object ID on the stream minus
+    /// one. The delta of the first Object is its
+    /// Object ID.
+    pub id_delta: x!(i),
+
+    /// ## Payload
+    ///
+    /// Either the payload of the Object or its
+    /// status.
+    ///
+    /// [ObjectPayload]
+    pub payload: ObjectPayload,
+}
+
+impl SubgroupObject {
+    /// Returns the [ObjectStatus] of this Object.
+    pub fn status(&self) -> ObjectStatus {
+        self.payload.status()
+    }
+}
+
+#[bon]
+impl SubgroupObject {
+    /// Creates a Builder for [SubgroupObject].
+    #[builder]
+    pub fn new(
+        #[builder(into, setters(
+            name = delta,
+            doc {
+                /// Sets the object ID delta on [SubgroupObject].
+            }
+        ))]
+        id_delta: x!(i),
+
+        #[builder(into, default, setters(
+            doc {
+                /// Sets the payload or status on [SubgroupObject].
+            }
+        ))]
+        payload: ObjectPayload,
+    ) -> Self {
+        Self { id_delta, payload }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for SubgroupObject {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let v1 = Self::builder().delta(0u8).payload(*b"frame").build();
+            let b1 = [
+                [
+                    0, // ID delta
+                    5, // payload length
+                ]
+                .to_vec(),
+                b"frame".to_vec(),
+            ]
+            .concat();
+            let l1 = b1.len() * 8;
+
+            let v2 = Self::builder()
+                .delta(3u8)
+                .payload(ObjectStatus::EndOfTrack)
+                .build();
+            let b2 = vec![
+                3, // ID delta
+                0, // no payload
+                4, // end of track status
+            ];
+            let l2 = b2.len() * 8;
+
+            vec![(v1, b1, l1), (v2, b2, l2)]
+        }
+    }
+
+    varint_struct_test!(SubgroupObject);
+}
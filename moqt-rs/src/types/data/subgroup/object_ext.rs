@@ -0,0 +1,149 @@
+use {
+    super::{
+        super::{Extensions, ObjectPayload, ObjectStatus},
+        SubgroupObject,
+    },
+    bon::bon,
+    varint::{VarInt, x},
+};
+
+/// ## Subgroup Object with Extensions
+///
+/// An Object sent on a Subgroup stream whose
+/// [SubgroupHeader](super::SubgroupHeader) has
+/// Extensions.
+#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[varint::draft_ref(v = 14, rename = "name-subgroup-header")]
+pub struct SubgroupObjectExt {
+    /// ## Object ID Delta
+    ///
+    /// The difference between this Object ID and
+    /// the previous Object ID on the stream minus
+    /// one. The delta of the first Object is its
+    /// Object ID.
+    pub id_delta: x!(i),
+
+    /// ## Extension Headers
+    ///
+    /// [Extensions]
+    #[varint(length = x(i))]
+    pub extensions: Extensions,
+
+    /// ## Payload
+    ///
+    /// Either the payload of the Object or its
+    /// status.
+    ///
+    /// [ObjectPayload]
+    pub payload: ObjectPayload,
+}
+
+impl SubgroupObjectExt {
+    /// Returns the [ObjectStatus] of this Object.
+    pub fn status(&self) -> ObjectStatus {
+        self.payload.status()
+    }
+}
+
+impl From<SubgroupObject> for SubgroupObjectExt {
+    fn from(value: SubgroupObject) -> Self {
+        Self {
+            id_delta: value.id_delta,
+            extensions: Extensions::default(),
+            payload: value.payload,
+        }
+    }
+}
+
+impl From<SubgroupObjectExt> for SubgroupObject {
+    fn from(value: SubgroupObjectExt) -> Self {
+        Self {
+            id_delta: value.id_delta,
+            payload: value.payload,
+        }
+    }
+}
+
+#[bon]
+impl SubgroupObjectExt {
+    /// Creates a Builder for [SubgroupObjectExt].
+    #[builder]
+    pub fn new(
+        #[builder(into, setters(
+            name = delta,
+            doc {
+                /// Sets the object ID delta on [SubgroupObjectExt].
+            }
+        ))]
+        id_delta: x!(i),
+
+        #[builder(default, setters(
+            doc {
+                /// Sets the extension headers on [SubgroupObjectExt].
+            }
+        ))]
+        extensions: Extensions,
+
+        #[builder(into, default, setters(
+            doc {
+                /// Sets the payload or status on [SubgroupObjectExt].
+            }
+        ))]
+        payload: ObjectPayload,
+    ) -> Self {
+        Self {
+            id_delta,
+            extensions,
+            payload,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helper::{TestData, varint_struct_test};
+
+    use super::*;
+
+    impl TestData for SubgroupObjectExt {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let mut extensions = Extensions::new();
+            extensions.push_number(0x2u8, 1u8);
+
+            let v1 = Self::builder()
+                .delta(1u8)
+                .extensions(extensions)
+                .payload(*b"abc")
+                .build();
+            let b1 = [
+                [
+                    1, // ID delta
+                    2, // extensions length
+                    2, // extension key
+                    1, // extension value
+                    3, // payload length
+                ]
+                .to_vec(),
+                b"abc".to_vec(),
+            ]
+            .concat();
+            let l1 = b1.len() * 8;
+
+            let v2 = Self::builder()
+                .delta(0u8)
+                .payload(ObjectStatus::DoesNotExist)
+                .build();
+            let b2 = vec![
+                0, // ID delta
+                0, // no extensions
+                0, // no payload
+                1, // does not exist status
+            ];
+            let l2 = b2.len() * 8;
+
+            vec![(v1, b1, l1), (v2, b2, l2)]
+        }
+    }
+
+    varint_struct_test!(SubgroupObjectExt);
+}
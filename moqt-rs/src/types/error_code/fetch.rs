@@ -3,7 +3,7 @@ use varint::varint_enum;
 varint_enum! {
     /// Error Code associated with a
     /// [FetchError](crate::types::message::FetchError) Control Message.
-    #[derive(Debug, PartialEq, Clone, Copy)]
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     #[varint::draft_ref(v = 14, error)]
     pub enum Fetch {
         /// An implementation specific or generic
@@ -3,7 +3,7 @@ use varint::varint_enum;
 varint_enum! {
     /// Error Code associated with a
     /// [PublishNamespaceError](crate::types::message::PublishNamespaceError) Control Message.
-    #[derive(Debug, PartialEq, Clone, Copy)]
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     #[varint(value = x(i))]
     #[varint::draft_ref(v = 14, error)]
     pub enum PublishNamespace {
@@ -10,7 +10,7 @@ varint_enum! {
     /// capsule.
     ///
     /// Using this code in a termination is optional.
-    #[derive(Debug, PartialEq, Clone, Copy)]
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     #[varint(value = x(i))]
     #[varint::draft_ref(v = 14)]
     pub enum Termination {
@@ -114,6 +114,17 @@ varint_enum! {
     }
 }
 
+impl TryFrom<u64> for Termination {
+    type Error = u64;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        u32::try_from(value)
+            .ok()
+            .and_then(Self::from_key)
+            .ok_or(value)
+    }
+}
+
 impl Display for Termination {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
@@ -166,4 +177,13 @@ mod tests {
         ExpiredAuthToken, InvalidAuthority,
         MalformedAuthority,
     );
+
+    #[test]
+    fn try_from_test() {
+        for &code in BUF {
+            let termination = Termination::try_from(u64::from(code)).unwrap();
+            assert_eq!(termination.key(), u32::from(code));
+        }
+        assert_eq!(Termination::try_from(0x3F_u64), Err(0x3F));
+    }
 }
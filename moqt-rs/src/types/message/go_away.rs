@@ -1,10 +1,28 @@
 use {
+    snafu::{OptionExt, ResultExt, Snafu},
     url::Url,
-    varint::{VarInt, x},
+    varint::{VarInt, VarIntBytes, x},
 };
 
+/// The maximum length of a migration URL in bytes.
+pub const MAX_URL_LEN: usize = 8192;
+
 // TODO also breakout Goaway into a dir mod
-pub enum GoawayError {}
+#[derive(Debug, Snafu, Clone, PartialEq, Eq)]
+#[snafu(visibility(pub), module(ctx))]
+pub enum GoawayError {
+    #[snafu(display("migration URL is {len} bytes long, at most {MAX_URL_LEN} are allowed"))]
+    TooLong { len: usize },
+
+    #[snafu(display("migration URL is not valid UTF-8"))]
+    InvalidUtf8,
+
+    #[snafu(display("{url} is an invalid migration URL"))]
+    InvalidUrl {
+        url: String,
+        source: url::ParseError,
+    },
+}
 
 /// ## Goaway
 ///
@@ -33,10 +51,38 @@ impl Goaway {
         Self::from(url)
     }
 
+    /// Creates a Goaway with the migration URL `url`.
+    ///
+    /// Without `url` the recipient reconnects to
+    /// the current URL.
+    pub fn with_url(url: Option<&Url>) -> Result<Self, GoawayError> {
+        let url = url.map(Url::as_str).unwrap_or_default();
+        snafu::ensure!(
+            url.len() <= MAX_URL_LEN,
+            ctx::TooLongSnafu { len: url.len() }
+        );
+
+        Ok(Self::new(url))
+    }
+
+    /// The migration URL, None when it is empty.
+    ///
+    /// Fails for URLs longer than [MAX_URL_LEN]
+    /// bytes, which is a protocol violation.
     pub fn migration_url(&self) -> Result<Option<Url>, GoawayError> {
-        // TODO not specified but should probably be valid utf-8
-        // TODO larger than 8192 bytes => protocol violation
-        todo!("# TODO validate url and return url is it exists (length could be 0 with no data)")
+        let bytes = self.url.bytes();
+        snafu::ensure!(
+            bytes.len() <= MAX_URL_LEN,
+            ctx::TooLongSnafu { len: bytes.len() }
+        );
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+
+        let url = str::from_utf8(&bytes).ok().context(ctx::InvalidUtf8Snafu)?;
+        Url::parse(url)
+            .context(ctx::InvalidUrlSnafu { url })
+            .map(Some)
     }
 }
 
@@ -70,4 +116,35 @@ mod tests {
     }
 
     varint_struct_test!(Goaway);
+
+    #[test]
+    fn migration_url_test() {
+        let url = Url::parse("https://relay.example.com:4443/moq").unwrap();
+        let goaway = Goaway::with_url(Some(&url)).unwrap();
+        assert_eq!(goaway.migration_url(), Ok(Some(url)));
+
+        let goaway = Goaway::with_url(None).unwrap();
+        assert_eq!(goaway.migration_url(), Ok(None));
+
+        let goaway = Goaway::new("not a url");
+        assert!(matches!(
+            goaway.migration_url(),
+            Err(GoawayError::InvalidUrl { .. })
+        ));
+
+        let goaway = Goaway::new(&[0xFF, 0xFE][..]);
+        assert_eq!(goaway.migration_url(), Err(GoawayError::InvalidUtf8));
+
+        let long = format!("https://example.com/{}", "a".repeat(MAX_URL_LEN));
+        let goaway = Goaway::new(long.as_str());
+        assert_eq!(
+            goaway.migration_url(),
+            Err(GoawayError::TooLong { len: long.len() })
+        );
+        let url = Url::parse(&long).unwrap();
+        assert_eq!(
+            Goaway::with_url(Some(&url)),
+            Err(GoawayError::TooLong { len: long.len() })
+        );
+    }
 }
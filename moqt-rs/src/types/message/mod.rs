@@ -15,7 +15,7 @@ mod track_status;
 
 pub use {
     fetch::{Fetch, FetchCancel, FetchError, FetchOk},
-    go_away::Goaway,
+    go_away::{Goaway, GoawayError, MAX_URL_LEN},
     max_request_id::MaxRequestId,
     publish::{Publish, PublishDone, PublishError, PublishOk},
     publish_namespace::{
@@ -84,6 +84,96 @@ varint::varint_enum! {
     }
 }
 
+impl ControlMessage {
+    /// Returns the Request ID this message refers to.
+    ///
+    /// None for messages which are not associated
+    /// with a Request, e.g. [ControlMessage::GoAway].
+    pub fn request_id(&self) -> Option<&varint::x!(i)> {
+        Some(match self {
+            Self::Subscribe(msg) | Self::TrackStatus(msg) => &msg.request_id,
+            Self::SubscribeOk(msg) | Self::TrackStatusOk(msg) => &msg.request_id,
+            Self::SubscribeError(msg) => &msg.request_id,
+            Self::SubscribeUpdate(msg) => &msg.request_id,
+            Self::Unsubscribe(msg) => &msg.request_id,
+            Self::PublishDone(msg) => &msg.request_id,
+            Self::Publish(msg) => &msg.request_id,
+            Self::PublishOk(msg) => &msg.request_id,
+            Self::PublishError(msg) => &msg.request_id,
+            Self::Fetch(msg) => &msg.request_id,
+            Self::FetchOk(msg) => &msg.request_id,
+            Self::FetchError(msg) => &msg.request_id,
+            Self::FetchCancel(msg) => &msg.request_id,
+            Self::TrackStatusError(msg) => &msg.request_id,
+            Self::PublishNamespace(msg) => &msg.request_id,
+            Self::PublishNamespaceOk(msg) => &msg.request_id,
+            Self::PublishNamespaceError(msg) => &msg.request_id,
+            Self::PublishNamespaceCancel(msg) => &msg.request_id,
+            Self::SubscribeNamespace(msg) => &msg.request_id,
+            Self::SubscribeNamespaceOk(msg) => &msg.request_id,
+            Self::SubscribeNamespaceError(msg) => &msg.request_id,
+            Self::ClientSetup(_)
+            | Self::ServerSetup(_)
+            | Self::GoAway(_)
+            | Self::MaxRequestId(_)
+            | Self::RequestsBlocked(_)
+            | Self::PublishNamespaceDone(_)
+            | Self::UnsubscribeNamespace(_) => return None,
+        })
+    }
+
+    /// Returns the Authorization Token this
+    /// message carries.
+    ///
+    /// None for messages which cannot carry one
+    /// or which were sent without.
+    pub fn auth_token(&self) -> Option<&crate::types::Token> {
+        match self {
+            Self::Subscribe(msg) | Self::TrackStatus(msg) => msg.auth_token(),
+            Self::SubscribeUpdate(msg) => msg.auth_token(),
+            Self::Publish(msg) => msg.auth_token(),
+            Self::Fetch(msg) => msg.auth_token(),
+            Self::PublishNamespace(msg) => msg.auth_token(),
+            Self::SubscribeNamespace(msg) => msg.auth_token(),
+            _ => None,
+        }
+    }
+
+    /// Whether this message starts a new Request
+    /// and carries a new Request ID.
+    pub fn is_request(&self) -> bool {
+        matches!(
+            self,
+            Self::Subscribe(_)
+                | Self::TrackStatus(_)
+                | Self::Publish(_)
+                | Self::Fetch(_)
+                | Self::PublishNamespace(_)
+                | Self::SubscribeNamespace(_)
+        )
+    }
+
+    /// Whether this message is the response to
+    /// a Request sent by the peer.
+    pub fn is_response(&self) -> bool {
+        matches!(
+            self,
+            Self::SubscribeOk(_)
+                | Self::SubscribeError(_)
+                | Self::PublishOk(_)
+                | Self::PublishError(_)
+                | Self::FetchOk(_)
+                | Self::FetchError(_)
+                | Self::TrackStatusOk(_)
+                | Self::TrackStatusError(_)
+                | Self::PublishNamespaceOk(_)
+                | Self::PublishNamespaceError(_)
+                | Self::SubscribeNamespaceOk(_)
+                | Self::SubscribeNamespaceError(_)
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -154,4 +244,27 @@ mod tests {
     }
 
     varint_struct_test!(ControlMessage);
+
+    #[test]
+    fn request_id_test() {
+        let [ok, error, goaway] = [
+            ControlMessage::SubscribeOk(
+                SubscribeOk::builder()
+                    .id(9u8)
+                    .alias(13u8)
+                    .expires(Duration::from_millis(10))
+                    .group_order(GroupOrder::Original)
+                    .build(),
+            ),
+            ControlMessage::FetchError(FetchError::new(50u8, error_code::Fetch::InvalidRange, "")),
+            ControlMessage::GoAway(Goaway::new("")),
+        ];
+
+        assert_eq!(ok.request_id(), Some(&9u8.into()));
+        assert!(ok.is_response());
+        assert_eq!(error.request_id(), Some(&50u8.into()));
+        assert!(error.is_response());
+        assert_eq!(goaway.request_id(), None);
+        assert!(!goaway.is_response());
+    }
 }
@@ -1,20 +1,111 @@
-use crate::macro_helper::control_message_error;
+use {
+    crate::types::{error_code, misc::ReasonPhrase},
+    bon::Builder,
+    varint::{VarInt, x},
+};
 
-control_message_error! {
-    /// ## PublishDone
+/// ## PublishDone
+///
+/// Signals a Subscriber that the Publisher
+/// finished publishing Objects for a
+/// Subscription.
+#[derive(Debug, VarInt, Builder, PartialEq, Clone)]
+#[varint::draft_ref(v = 14)]
+pub struct PublishDone {
+    /// ## Request ID
+    #[builder(into, setters(
+        name = id,
+        doc {
+            /// Sets the request ID on [PublishDone].
+        }
+    ))]
+    pub request_id: x!(i),
+
+    /// ## Status Code
+    ///
+    /// Why the Subscription ended.
+    ///
+    /// [PublishDone](error_code::PublishDone)
+    #[builder(into, setters(
+        doc {
+            /// Sets the status code on [PublishDone].
+        }
+    ))]
+    pub code: error_code::PublishDone,
+
+    /// ## Stream Count
+    ///
+    /// The number of data streams the Publisher
+    /// opened for the Subscription.
     ///
-    /// Signals a Relay that a Publisher finished
-    /// publishing Objects.
-    #[derive(Debug, PartialEq, Clone)]
-    #[varint::draft_ref(v = 14)]
-    Publish + Done
+    /// The Subscriber has received everything once
+    /// it processed as many streams.
+    #[builder(into, setters(
+        doc {
+            /// Sets the stream count on [PublishDone].
+        }
+    ))]
+    pub stream_count: x!(i),
+
+    /// ## Status Message
+    ///
+    /// [ReasonPhrase]
+    #[builder(into, setters(
+        doc {
+            /// Sets the reason phrase on [PublishDone].
+        }
+    ))]
+    pub reason: ReasonPhrase,
+}
+
+impl PublishDone {
+    /// Creates a new Instance.
+    pub fn new<ID, C, N, R>(id: ID, code: C, stream_count: N, reason: R) -> Self
+    where
+        ID: Into<x!(i)>,
+        C: Into<error_code::PublishDone>,
+        N: Into<x!(i)>,
+        R: Into<ReasonPhrase>,
+    {
+        Self {
+            request_id: id.into(),
+            code: code.into(),
+            stream_count: stream_count.into(),
+            reason: reason.into(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::test_helper::control_message_error_test;
+    use crate::test_helper::{TestData, varint_struct_test};
 
     use super::*;
 
-    control_message_error_test!(Publish + Done; NotSupported = 0x3);
+    impl TestData for PublishDone {
+        fn test_data() -> Vec<(Self, Vec<u8>, usize)> {
+            let v1 = Self::new(
+                13u8,
+                error_code::PublishDone::SubscriptionEnded,
+                4u8,
+                "end of range",
+            );
+            let b1 = [
+                [
+                    13, // ID
+                    3,  // status code
+                    4,  // stream count
+                    12, // phrase length
+                ]
+                .to_vec(),
+                b"end of range".to_vec(),
+            ]
+            .concat();
+            let l1 = b1.len() * 8;
+
+            vec![(v1, b1, l1)]
+        }
+    }
+
+    varint_struct_test!(PublishDone);
 }
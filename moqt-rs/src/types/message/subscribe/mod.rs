@@ -185,7 +185,7 @@ where
     {
         let this = self.start_location_internal(Some(start.into()));
         let this = this.end_group_internal(Some(end_group.into()));
-        this.filter_type_internal(FilterType::AbsoluteStart)
+        this.filter_type_internal(FilterType::AbsoluteRange)
     }
 }
 
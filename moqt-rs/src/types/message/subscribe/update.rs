@@ -1,7 +1,10 @@
 use {
-    crate::types::{Parameters, misc::Location},
+    crate::types::{
+        Parameters,
+        misc::{Forward, Location},
+    },
     bon::bon,
-    varint::{VarInt, x},
+    varint::{VarInt, VarIntNumber, x},
 };
 
 /// ## SubscribeUpdate
@@ -11,6 +14,8 @@ use {
 /// A Subscription can only be narrowed and not widened, i.e.
 /// `start_location` must not decrease and `end_group`
 /// must not increase.
+///
+/// Forwarding can be switched on and off freely.
 #[derive(Debug, VarInt, PartialEq, Clone)]
 #[varint::draft_ref(v = 14)]
 #[varint(parameters(auth_token, delivery_timeout))]
@@ -27,7 +32,9 @@ pub struct SubscribeUpdate {
 
     /// ## Final Group
     ///
-    /// The new final Group.
+    /// The new final Group plus 1.
+    ///
+    /// 0 indicates an open-ended Subscription.
     pub end_group: x!(i),
 
     /// ## Subscriber Priority
@@ -38,6 +45,14 @@ pub struct SubscribeUpdate {
     /// Lower means higher priority.
     pub subscriber_priority: x!(8),
 
+    /// ## Forward Mode
+    ///
+    /// Whether Objects are forwarded from
+    /// now on.
+    ///
+    /// [Forward]
+    pub forward: Forward,
+
     /// ## Parameters
     ///
     /// [Parameters]
@@ -80,17 +95,62 @@ impl SubscribeUpdate {
                 }
         ))]
         subscriber_priority: x!(8),
+        #[builder(into, setters(
+            doc {
+                /// Sets the forwarding on [SubscribeUpdate].
+            }
+        ))]
+        forward: Forward,
     ) -> Self {
         Self {
             request_id,
             start_location,
             end_group,
             subscriber_priority,
+            forward,
             parameters,
         }
     }
 }
 
+impl SubscribeUpdate {
+    /// The final Group, None while open-ended.
+    pub fn final_group(&self) -> Option<u64> {
+        self.end_group.number::<u64>().checked_sub(1)
+    }
+
+    /// Checks that this update only narrows a
+    /// Subscription starting at `start`, None while
+    /// at the next Object, and ending with
+    /// `end_group`, None while open-ended.
+    ///
+    /// Fails with the reason if the update widens
+    /// it, which is a protocol violation.
+    pub fn narrows(
+        &self,
+        start: Option<&Location>,
+        end_group: Option<u64>,
+    ) -> Result<(), &'static str> {
+        let new_start = &self.start_location;
+        let new_end_group = self.final_group();
+
+        if start.is_some_and(|start| new_start < start) {
+            return Err("subscribe update moved the start backwards");
+        }
+        match (end_group, new_end_group) {
+            (Some(_), None) => return Err("subscribe update removed the end group"),
+            (Some(end_group), Some(new_end_group)) if new_end_group > end_group => {
+                return Err("subscribe update moved the end group forward");
+            }
+            _ => (),
+        }
+        if new_end_group.is_some_and(|end_group| end_group < new_start.group::<u64>()) {
+            return Err("subscribe update ends before its start");
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_helper::{TestData, varint_struct_test};
@@ -104,6 +164,7 @@ mod tests {
                 .start(13u8, 1u8)
                 .end_group(50u8)
                 .subscriber_priority(0)
+                .forward(true)
                 .build();
             let b1 = vec![
                 9,  // ID 9
@@ -111,6 +172,7 @@ mod tests {
                 1,  // start object 1
                 50, // end group 50
                 0,  // sub prio
+                1,  // forward
                 0,  // no parameters
             ];
             let l1 = b1.len() * 8;
@@ -120,4 +182,42 @@ mod tests {
     }
 
     varint_struct_test!(SubscribeUpdate);
+
+    #[test]
+    fn narrows_test() {
+        let update = |group: u8, object: u8, end_group: u8| {
+            SubscribeUpdate::builder()
+                .id(1u8)
+                .start(group, object)
+                .end_group(end_group)
+                .subscriber_priority(0)
+                .forward(true)
+                .build()
+        };
+        let start = Location::new(1u8, 2u8);
+        let narrows = |update: SubscribeUpdate, end_group| update.narrows(Some(&start), end_group);
+
+        assert_eq!(update(1, 2, 3).final_group(), Some(2));
+        assert_eq!(update(1, 2, 0).final_group(), None);
+        assert_eq!(narrows(update(1, 2, 3), Some(2)), Ok(()));
+        assert_eq!(narrows(update(4, 0, 0), None), Ok(()));
+        assert_eq!(update(0, 0, 0).narrows(None, None), Ok(()));
+
+        assert_eq!(
+            narrows(update(1, 1, 0), None),
+            Err("subscribe update moved the start backwards")
+        );
+        assert_eq!(
+            narrows(update(1, 2, 0), Some(2)),
+            Err("subscribe update removed the end group")
+        );
+        assert_eq!(
+            narrows(update(1, 2, 4), Some(2)),
+            Err("subscribe update moved the end group forward")
+        );
+        assert_eq!(
+            narrows(update(3, 0, 3), Some(5)),
+            Err("subscribe update ends before its start")
+        );
+    }
 }
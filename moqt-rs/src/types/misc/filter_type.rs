@@ -1,4 +1,7 @@
-use varint::varint_enum;
+use {
+    super::Location,
+    varint::{varint_enum, x},
+};
 
 varint_enum! {
     /// ## Filter Type
@@ -43,6 +46,35 @@ varint_enum! {
     }
 }
 
+impl FilterType {
+    /// The first Location of a Subscription with
+    /// this filter and `start`, accepted while the
+    /// Largest Location of the Track was `largest`.
+    ///
+    /// None if it does not fit a varint.
+    pub fn resolve_start(
+        self,
+        start: Option<&Location>,
+        largest: Option<&Location>,
+    ) -> Option<Location> {
+        let (group, object) = match (self, start, largest) {
+            (Self::AbsoluteStart | Self::AbsoluteRange, Some(start), _) => {
+                return Some(start.clone());
+            }
+            (_, _, None) => (0, 0),
+            (Self::NextGroupStart, _, Some(largest)) => (largest.group::<u64>().checked_add(1)?, 0),
+            (_, _, Some(largest)) => (
+                largest.group::<u64>(),
+                largest.object::<u64>().checked_add(1)?,
+            ),
+        };
+        Some(Location::new(
+            <x!(i)>::try_from(group).ok()?,
+            <x!(i)>::try_from(object).ok()?,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_helper::varint_enum_test;
@@ -55,4 +87,31 @@ mod tests {
         LargestObject, NextGroupStart,
         AbsoluteStart, AbsoluteRange,
     );
+
+    #[test]
+    fn resolve_start_test() {
+        let (start, largest) = (Location::new(1u8, 2u8), Location::new(4u8, 7u8));
+        let resolve = |filter: FilterType, largest| filter.resolve_start(Some(&start), largest);
+
+        assert_eq!(
+            resolve(FilterType::AbsoluteStart, Some(&largest)),
+            Some(start.clone())
+        );
+        assert_eq!(
+            resolve(FilterType::AbsoluteRange, None),
+            Some(start.clone())
+        );
+        assert_eq!(
+            resolve(FilterType::LargestObject, Some(&largest)),
+            Some(Location::new(4u8, 8u8))
+        );
+        assert_eq!(
+            resolve(FilterType::NextGroupStart, Some(&largest)),
+            Some(Location::new(5u8, 0u8))
+        );
+        assert_eq!(
+            resolve(FilterType::LargestObject, None),
+            Some(Location::new(0u8, 0u8))
+        );
+    }
 }
@@ -76,14 +76,9 @@ impl PartialOrd for Location {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         // Ref: https://www.ietf.org/archive/id/draft-ietf-moq-transport-14.html#name-location-structure
         // A < B: A.Group < B.Group || (A.Group == B.Group && A.Object < B.Object)
-        if self.group < other.group || (self.group == other.group && self.object < other.object) {
-            return Some(std::cmp::Ordering::Less);
-        } else if other.group < self.group
-            || (other.group == self.group && other.object < self.object)
-        {
-            return Some(std::cmp::Ordering::Greater);
-        }
-        Some(std::cmp::Ordering::Equal)
+        // by number, a decoded VarInt keeps its encoding
+        let key = |location: &Self| (location.group::<u64>(), location.object::<u64>());
+        key(self).partial_cmp(&key(other))
     }
 }
 
@@ -112,4 +107,16 @@ mod tests {
     }
 
     varint_struct_test!(Location);
+
+    #[test]
+    fn ord_test() {
+        // decoded zeros are stored in fewer bits
+        let mut reader = varint::core::ReferenceReader::new(&[0, 0]);
+        let (decoded, _) = Location::decode(&mut reader, None).unwrap();
+        let object = <x!(i)>::try_from(0u64).unwrap();
+
+        assert!(Location::new(0u8, object) <= decoded);
+        assert!(Location::new(0u8, 1u8) > decoded);
+        assert!(Location::new(1u8, 0u8) > Location::new(0u8, 5u8));
+    }
 }
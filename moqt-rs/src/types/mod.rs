@@ -1,11 +1,13 @@
 //! Contains all basic MOQT Types defined in the the Draft
 
 pub mod config;
+pub mod data;
 pub mod error_code;
 pub mod message;
 mod misc;
 mod parameter;
 mod request_id;
+mod token_cache;
 mod track;
 
 pub use {
@@ -15,9 +17,10 @@ pub use {
     },
     parameter::{
         ClientSetupParameter, ClientSetupParameters, Parameter, Parameters, ServerSetupParameter,
-        ServerSetupParameters, Token,
+        ServerSetupParameters, Token, TokenType,
     },
-    request_id::RequestId,
+    request_id::{RequestId, RequestWindow},
+    token_cache::TokenCache,
     track::{Name, Namespace},
 };
 
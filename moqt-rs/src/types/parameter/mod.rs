@@ -8,7 +8,7 @@ mod version_specific_parameter;
 pub use {
     client_setup_parameter::{ClientSetupParameter, ClientSetupParameterError},
     server_setup_parameter::{ServerSetupParameter, ServerSetupParameterError},
-    token::Token,
+    token::{Token, TokenType},
     version_specific_parameter::{Parameter, ParameterError},
 };
 
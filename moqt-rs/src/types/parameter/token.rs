@@ -4,7 +4,7 @@ use {
     snafu::{ResultExt, Snafu},
     std::fmt::Debug,
     varint::{
-        VarInt, VarIntBytes, Writer,
+        VarInt, VarIntBytes, VarIntNumber, Writer,
         core::{ReferenceReader, ReferenceWriter, WriterError},
         x,
     },
@@ -46,7 +46,9 @@ pub struct Token {
     ///
     /// Numeric ID of the type of Payload.
     ///
-    /// Possible IDs are defined in [Draft](https://www.ietf.org/archive/id/draft-ietf-moq-transport-14.html#iana).
+    /// Possible IDs are defined in [Draft](https://www.ietf.org/archive/id/draft-ietf-moq-transport-14.html#iana),
+    /// the ones this crate understands are listed
+    /// in [TokenType].
     ///
     /// 0 means that the type is signaled out-of-band.
     ///
@@ -58,7 +60,7 @@ pub struct Token {
     /// Otherwise None.
     #[builder(setters(vis = "", name = typ_internal))]
     #[varint(when(alias_typ = 0x1 || 0x3))]
-    pub typ: x!([i]), // TODO there are none defined right now. Once there this will likely be replaced by an Enum
+    pub typ: x!([i]),
 
     /// ## Token Value
     ///
@@ -141,6 +143,52 @@ where
     }
 }
 
+impl Token {
+    /// The Token Value, Some for [Register](AliasType::Register)
+    /// and [UseValue](AliasType::UseValue).
+    pub fn value(&self) -> Option<&x!(..)> {
+        self.value.as_ref()
+    }
+
+    /// The [TokenType] of this Token, None if it
+    /// has no Type or one this crate does not know.
+    pub fn token_type(&self) -> Option<TokenType> {
+        TokenType::try_from(self.typ.as_ref()?.number::<u64>()).ok()
+    }
+}
+
+/// ## Token Type Registry
+///
+/// The Types of Token Values this crate
+/// understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenType {
+    /// The Type is signaled out-of-band.
+    OutOfBand = 0x0,
+
+    /// Claims signed with HMAC-SHA256 or Ed25519,
+    /// see [SignedClaims](crate::SignedClaims).
+    SignedClaims = 0x1,
+}
+
+impl TryFrom<u64> for TokenType {
+    type Error = u64;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(Self::OutOfBand),
+            0x1 => Ok(Self::SignedClaims),
+            typ => Err(typ),
+        }
+    }
+}
+
+impl From<TokenType> for x!(i) {
+    fn from(value: TokenType) -> Self {
+        (value as u8).into()
+    }
+}
+
 impl Debug for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Token")
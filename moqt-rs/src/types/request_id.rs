@@ -1,23 +1,33 @@
-use {snafu::Snafu, varint::x};
+use {super::error_code::Termination, snafu::Snafu, varint::x};
 
+/// Number of Requests a peer may send before
+/// it has to wait for a [MaxRequestId](super::message::MaxRequestId).
+pub(crate) const DEFAULT_REQUEST_WINDOW: u64 = 32;
+
+/// ## Request ID
+///
+/// Issues the Request IDs of outgoing Requests
+/// up to the maximum the peer granted.
 #[derive(Debug)]
 pub struct RequestId {
     id: u64,
+    max: u64,
 }
 
 impl RequestId {
     pub fn new_client() -> Self {
-        Self { id: 0 }
+        Self { id: 0, max: 0 }
     }
 
     pub fn new_server() -> Self {
-        Self { id: 1 }
+        Self { id: 1, max: 0 }
     }
 
     pub fn get(&mut self) -> Result<x!(i), RequestIdError> {
         let id = self.id;
 
-        snafu::ensure!(id <= <x!(i)>::MAX, RequestIdSnafu);
+        snafu::ensure!(id <= <x!(i)>::MAX, ExhaustedSnafu);
+        snafu::ensure!(id < self.max, BlockedSnafu { max: self.max });
         self.id += 2;
 
         // uncheck is possible here because we've ensured it
@@ -28,8 +38,170 @@ impl RequestId {
     pub fn is_client(&self) -> bool {
         self.id.is_multiple_of(2)
     }
+
+    /// Whether `id` was already returned by
+    /// [RequestId::get].
+    pub fn is_issued(&self, id: u64) -> bool {
+        id < self.id && id % 2 == self.id % 2
+    }
+
+    /// The maximum Request ID granted by the peer.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Raises the maximum Request ID granted by
+    /// the peer to `max`.
+    ///
+    /// Returns false when `max` does not exceed
+    /// the current maximum.
+    pub fn set_max(&mut self, max: u64) -> bool {
+        if max <= self.max {
+            return false;
+        }
+        self.max = max;
+        true
+    }
 }
 
 #[derive(Debug, Snafu, Clone, PartialEq, Eq)]
-#[snafu(display("request limit reached"))]
-pub struct RequestIdError;
+#[snafu(visibility(pub(crate)))]
+pub enum RequestIdError {
+    #[snafu(display("request IDs are exhausted"))]
+    Exhausted,
+
+    #[snafu(display("request limit reached, the peer granted IDs below {max}"))]
+    Blocked { max: u64 },
+}
+
+/// ## Request Window
+///
+/// Validates the Request IDs of incoming Requests
+/// and grants the peer new ones in increments of
+/// a fixed window.
+#[derive(Debug)]
+pub struct RequestWindow {
+    next: u64,
+    max: u64,
+    window: u64,
+}
+
+impl RequestWindow {
+    /// Accepts the Request IDs issued by a
+    /// [client](RequestId::new_client).
+    pub fn new_client() -> Self {
+        Self::new(0, DEFAULT_REQUEST_WINDOW)
+    }
+
+    /// Accepts the Request IDs issued by a
+    /// [server](RequestId::new_server).
+    pub fn new_server() -> Self {
+        Self::new(1, DEFAULT_REQUEST_WINDOW)
+    }
+
+    fn new(first: u64, window: u64) -> Self {
+        Self {
+            next: first,
+            max: first + 2 * window,
+            window,
+        }
+    }
+
+    /// The maximum Request ID currently granted
+    /// to the peer.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Accepts the Request ID of a new Request.
+    ///
+    /// Request IDs must be consecutive and below
+    /// the granted maximum.
+    pub fn accept(&mut self, id: u64) -> Result<(), Termination> {
+        if id != self.next {
+            return Err(Termination::InvalidRequestID);
+        }
+        if id >= self.max {
+            return Err(Termination::TooManyRequests);
+        }
+        self.next += 2;
+        Ok(())
+    }
+
+    /// Grants the peer another window of Request
+    /// IDs once less than half of it is left.
+    ///
+    /// Returns the new maximum to send in a
+    /// [MaxRequestId](super::message::MaxRequestId).
+    pub fn grant(&mut self) -> Option<x!(i)> {
+        if self.max - self.next >= self.window {
+            return None;
+        }
+
+        let max = self.next.saturating_add(2 * self.window).min(<x!(i)>::MAX);
+        if max <= self.max {
+            return None;
+        }
+        self.max = max;
+        Some(<x!(i)>::new_unchecked(max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_test() {
+        let mut ids = RequestId::new_server();
+        ids.set_max(8);
+        assert!(!ids.is_issued(1));
+
+        assert_eq!(ids.get().unwrap(), 1u8);
+        assert_eq!(ids.get().unwrap(), 3u8);
+
+        assert!(ids.is_issued(1));
+        assert!(ids.is_issued(3));
+        assert!(!ids.is_issued(2));
+        assert!(!ids.is_issued(5));
+    }
+
+    #[test]
+    fn blocked_test() {
+        let mut ids = RequestId::new_client();
+        assert_eq!(ids.get(), Err(RequestIdError::Blocked { max: 0 }));
+
+        assert!(ids.set_max(4));
+        assert!(!ids.set_max(4));
+        assert!(!ids.set_max(2));
+
+        assert_eq!(ids.get().unwrap(), 0u8);
+        assert_eq!(ids.get().unwrap(), 2u8);
+        assert_eq!(ids.get(), Err(RequestIdError::Blocked { max: 4 }));
+
+        assert!(ids.set_max(6));
+        assert_eq!(ids.get().unwrap(), 4u8);
+    }
+
+    #[test]
+    fn window_test() {
+        let mut window = RequestWindow::new(0, 4);
+        assert_eq!(window.max(), 8);
+
+        assert_eq!(window.accept(2), Err(Termination::InvalidRequestID));
+        assert_eq!(window.accept(0), Ok(()));
+        assert_eq!(window.grant(), None);
+        assert_eq!(window.accept(2), Ok(()));
+        assert_eq!(window.accept(2), Err(Termination::InvalidRequestID));
+
+        // less than half of the window is left
+        assert_eq!(window.accept(4), Ok(()));
+        assert_eq!(window.grant(), Some(14u8.into()));
+        assert_eq!(window.max(), 14);
+
+        for id in [6, 8, 10, 12] {
+            assert_eq!(window.accept(id), Ok(()));
+        }
+        assert_eq!(window.accept(14), Err(Termination::TooManyRequests));
+    }
+}
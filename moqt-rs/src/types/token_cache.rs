@@ -0,0 +1,167 @@
+use {
+    super::{AliasType, Token, error_code::Termination},
+    std::collections::HashMap,
+    varint::{VarIntBytes, VarIntNumber},
+};
+
+/// ## Token Cache
+///
+/// The Authorization Tokens a peer registered
+/// for the duration of a session by their Alias.
+///
+/// The size of a registered Token is the length
+/// of its Value, all of them together must not
+/// exceed the
+/// [MaxAuthorizationTokenCacheSize](super::ServerSetupParameter::MaxAuthorizationTokenCacheSize)
+/// the server advertised.
+#[derive(Debug, Default)]
+pub struct TokenCache {
+    max_size: u64,
+    size: u64,
+    tokens: HashMap<u64, Token>,
+}
+
+impl TokenCache {
+    /// Creates an empty TokenCache holding at most
+    /// `max_size` bytes of Token Values.
+    ///
+    /// Registering is prohibited with 0.
+    pub fn new(max_size: u64) -> Self {
+        Self {
+            max_size,
+            ..Self::default()
+        }
+    }
+
+    /// The size of all registered Tokens.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Applies `token` sent by the peer.
+    ///
+    /// Returns the Token to authorize the message
+    /// carrying it with as [UseValue](AliasType::UseValue),
+    /// None once it was deleted.
+    ///
+    /// Fails with the [Termination] to close the
+    /// session with if the peer registers an Alias
+    /// twice, refers to an unknown one or exceeds
+    /// the maximum size.
+    pub fn apply(&mut self, token: &Token) -> Result<Option<Token>, Termination> {
+        let alias = token.alias.as_ref().map(VarIntNumber::number::<u64>);
+        match (token.alias_typ, alias) {
+            (AliasType::Delete, Some(alias)) => {
+                let deleted = self
+                    .tokens
+                    .remove(&alias)
+                    .ok_or(Termination::UnknownAuthTokenAlias)?;
+                self.size -= token_size(&deleted);
+                Ok(None)
+            }
+            (AliasType::Register, Some(alias)) => {
+                if self.tokens.contains_key(&alias) {
+                    return Err(Termination::DuplicateAuthTokenAlias);
+                }
+                let registered = use_value(token)?;
+                let size = self.size + token_size(&registered);
+                if size > self.max_size {
+                    return Err(Termination::AuthTokenCacheOverflow);
+                }
+
+                self.size = size;
+                self.tokens.insert(alias, registered.clone());
+                Ok(Some(registered))
+            }
+            (AliasType::UseAlias, Some(alias)) => self
+                .tokens
+                .get(&alias)
+                .cloned()
+                .map(Some)
+                .ok_or(Termination::UnknownAuthTokenAlias),
+            (AliasType::UseValue, None) => use_value(token).map(Some),
+            _ => Err(Termination::MalformedAuthToken),
+        }
+    }
+}
+
+/// The [UseValue](AliasType::UseValue) Token
+/// with the Type and Value of `token`.
+fn use_value(token: &Token) -> Result<Token, Termination> {
+    match (&token.typ, token.value()) {
+        (Some(typ), Some(value)) => Ok(Token::builder()
+            .use_value()
+            .typ(typ.clone())
+            .value(value.clone())
+            .build()),
+        _ => Err(Termination::MalformedAuthToken),
+    }
+}
+
+fn token_size(token: &Token) -> u64 {
+    token.value().map_or(0, |value| value.bytes().len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register(alias: u8, value: &[u8]) -> Token {
+        Token::builder()
+            .register()
+            .alias(alias)
+            .typ(1u8)
+            .value(value)
+            .build()
+    }
+
+    #[test]
+    fn register_test() {
+        let mut cache = TokenCache::new(8);
+        let used = Token::builder()
+            .use_value()
+            .typ(1u8)
+            .value([1, 2, 3, 4, 5])
+            .build();
+
+        let registered = cache.apply(&register(3, &[1, 2, 3, 4, 5]));
+        assert_eq!(registered, Ok(Some(used.clone())));
+        assert_eq!(cache.size(), 5);
+
+        let alias = Token::builder().use_alias().alias(3u8).build();
+        assert_eq!(cache.apply(&alias), Ok(Some(used.clone())));
+        assert_eq!(cache.apply(&used), Ok(Some(used)));
+
+        let delete = Token::builder().delete().alias(3u8).build();
+        assert_eq!(cache.apply(&delete), Ok(None));
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.apply(&alias), Err(Termination::UnknownAuthTokenAlias));
+        assert_eq!(
+            cache.apply(&delete),
+            Err(Termination::UnknownAuthTokenAlias)
+        );
+    }
+
+    #[test]
+    fn violation_test() {
+        let mut cache = TokenCache::new(8);
+        assert!(cache.apply(&register(1, &[1, 2, 3, 4])).is_ok());
+        assert_eq!(
+            cache.apply(&register(1, &[5])),
+            Err(Termination::DuplicateAuthTokenAlias)
+        );
+        assert_eq!(
+            cache.apply(&register(2, &[1, 2, 3, 4, 5])),
+            Err(Termination::AuthTokenCacheOverflow)
+        );
+        assert!(cache.apply(&register(2, &[1, 2, 3, 4])).is_ok());
+        assert_eq!(cache.size(), 8);
+
+        // aliases are prohibited without a cache
+        let mut cache = TokenCache::new(0);
+        assert_eq!(
+            cache.apply(&register(1, &[1])),
+            Err(Termination::AuthTokenCacheOverflow)
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::Display;
 use varint::{VarInt, VarIntBytes, x};
 
 /// ## Track Name
-#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[derive(Debug, VarInt, PartialEq, Eq, Clone, Hash)]
 pub struct Name {
     #[varint(length = x(i))]
     inner: x!(..),
@@ -16,6 +16,11 @@ impl Name {
     {
         Self::from(num)
     }
+
+    /// The bytes of this Name.
+    pub fn bytes(&self) -> bytes::Bytes {
+        self.inner.bytes()
+    }
 }
 
 impl Display for Name {
@@ -1,10 +1,13 @@
-use varint::{VarInt, x};
+use {
+    std::fmt::Display,
+    varint::{VarInt, x},
+};
 
 const MIN_LEN: usize = 1;
 const MAX_LEN: usize = 32;
 
 /// ## Track Namespace
-#[derive(Debug, VarInt, PartialEq, Eq, Clone)]
+#[derive(Debug, VarInt, PartialEq, Eq, Clone, Hash)]
 pub struct Namespace {
     // TODO how to make sure there are only 1..=32 Tuples?
     // TODO total length should also not exceed 4096 bytes
@@ -19,6 +22,35 @@ impl Namespace {
     {
         Self::from(tup)
     }
+
+    /// Returns the number of Tuple fields.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns true when there are no Tuple fields.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the Tuple fields.
+    pub fn fields(&self) -> &[x!(b)] {
+        &self.inner
+    }
+
+    /// Whether the leading Tuple fields of this
+    /// Namespace equal all fields of `prefix`.
+    ///
+    /// Every Namespace starts with itself.
+    pub fn starts_with(&self, prefix: &Namespace) -> bool {
+        self.inner.starts_with(&prefix.inner)
+    }
+}
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
 }
 
 // TODO impl some stuff to make them more useable, IntoIterator, IntoIterator<'a>, Deref, see Tuple and BinaryData!
@@ -73,4 +105,16 @@ mod tests {
     }
 
     varint_struct_test!(Namespace);
+
+    #[test]
+    fn prefix_test() {
+        let ns = Namespace::from(["moq", "vod", "banana"]);
+
+        assert!(ns.starts_with(&Namespace::from(["moq"])));
+        assert!(ns.starts_with(&Namespace::from(["moq", "vod"])));
+        assert!(ns.starts_with(&ns));
+        assert!(!ns.starts_with(&Namespace::from(["vod"])));
+        assert!(!ns.starts_with(&Namespace::from(["moq", "vod", "banana", "split"])));
+        assert_eq!(ns.len(), 3);
+    }
 }
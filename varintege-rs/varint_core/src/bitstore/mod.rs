@@ -7,7 +7,7 @@ use funty::Unsigned;
 use bytes::Bytes;
 use snafu::{ResultExt, Snafu};
 
-#[derive(Clone, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct BitStore<const MIN: usize = 0, const MAX: usize = { usize::MAX }> {
     data: Bytes,
     len: usize,
@@ -27,7 +27,7 @@ pub(super) use error::{ctx, ctx_conv};
 ///   with a value between `C` and `D`
 ///   (inclusive), C and D are optional
 ///     - For convenience use: [x!(N = C..D)](varint_derive::x) // TODO doc.rs link once varint_derive is published
-#[derive(Clone, Default, PartialEq, PartialOrd)]
+#[derive(Clone, Default, PartialEq, PartialOrd, Eq, Ord)]
 pub struct BitNumber<const N: usize, const MIN: u128 = 0, const MAX: u128 = { u128::MAX }> {
     data: BitStore<N, N>,
 }
@@ -26,7 +26,7 @@ pub(super) use error::{ctx, ctx_conv};
 ///
 /// `A` and `B` can both be omitted to
 /// open their respective range limits.
-#[derive(Default, Clone, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Default, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct BitRange<const MIN: usize = 0, const MAX: usize = { usize::MAX }> {
     data: BitStore<MIN, MAX>,
 }
@@ -20,7 +20,7 @@ pub(super) use error::ctx;
 /// It starts with a [VarInt](crate::Number) signaling
 /// the number of [BinaryData] elements, followed by
 /// that many [BinaryData].
-#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct Tuple {
     data: Vec<BinaryData>,
 }
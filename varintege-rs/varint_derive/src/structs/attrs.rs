@@ -180,7 +180,7 @@ impl StructAttrs {
                     #(
                         #[#docs]
                     )*
-                    fn #fn_name<V>(mut self, value: V) -> Self
+                    pub fn #fn_name<V>(mut self, value: V) -> Self
                     where
                         V: Into<#ty>
                     {
@@ -221,7 +221,7 @@ impl StructAttrs {
                 )*
 
                 /// Adds a generic number parameter.
-                fn number<K, V>(mut self, key: K, value: V) -> Self
+                pub fn number<K, V>(mut self, key: K, value: V) -> Self
                 where
                     K: Into<#varint::x!(i)>,
                     V: Into<#varint::x!(i)>,
@@ -232,7 +232,7 @@ impl StructAttrs {
                 }
 
                 /// Adds a generic bytes parameter.
-                fn bytes<K, V>(mut self, key: K, value: V) -> Self
+                pub fn bytes<K, V>(mut self, key: K, value: V) -> Self
                 where
                     K: Into<#varint::x!(i)>,
                     V: Into<#varint::x!(..)>,
@@ -44,6 +44,7 @@ impl Field {
             name,
             attr: &self.attr,
             length_required,
+            option: option_type(&self.ty).is_some(),
         }
         .into_token_stream();
 
@@ -7,6 +7,10 @@ pub struct FieldLengthEncoder<'a> {
     pub name: &'a Ident,
     pub attr: &'a StructFieldAttributes,
     pub length_required: bool,
+
+    /// whether the field is an Option, in which case
+    /// the length is taken from the unwrapped `val`
+    pub option: bool,
 }
 
 impl<'a> ToTokens for FieldLengthEncoder<'a> {
@@ -14,8 +18,13 @@ impl<'a> ToTokens for FieldLengthEncoder<'a> {
         match self.attr.length {
             Some(ref ty) => {
                 let field = &self.name;
+                let value = if self.option {
+                    quote! { val }
+                } else {
+                    quote! { self.#field }
+                };
                 quote! {
-                    let field_len = self.#field.len_bits()?;
+                    let field_len = #value.len_bits()?;
                     let field_length = <#ty>::try_from(field_len / 8)?;
                     bits += field_length.encode(writer, None)?;
                     let field_len = Some(field_len);
@@ -42,6 +42,26 @@ impl ToTokens for VarIntEnum {
             .iter()
             .map(|v| v.key_getter())
             .collect::<Vec<_>>();
+        // enums of unit variants only can be created from their key
+        let from_key = self
+            .variants
+            .iter()
+            .all(|v| v.fields.is_none())
+            .then(|| {
+                let names = self.variants.iter().map(|v| &v.name);
+                let values = self.variants.iter().map(|v| &v.value);
+                quote! {
+                    /// The variant with `key`, if any.
+                    pub fn from_key(key: u32) -> Option<Self> {
+                        match key {
+                            #(
+                                #values => Some(Self::#names),
+                            )*
+                            _ => None,
+                        }
+                    }
+                }
+            });
         Brace::default().surround(tokens, |f| {
             quote! {
                     pub fn key(&self) -> u32 {
@@ -51,6 +71,8 @@ impl ToTokens for VarIntEnum {
                             )*
                         }
                     }
+
+                    #from_key
             }
             .to_tokens(f);
         });